@@ -27,3 +27,24 @@ _command_vault_log_command() {
 
 # Add the function to the PROMPT_COMMAND
 PROMPT_COMMAND="_command_vault_log_command${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+
+# Complete command IDs for the subcommands that take one
+_command_vault_complete() {
+    local cur=${COMP_WORDS[COMP_CWORD]}
+    local subcommand=${COMP_WORDS[1]}
+
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "add search ls tag exec edit delete clone serve shell-init lock unlock" -- "$cur"))
+        return
+    fi
+
+    case "$subcommand" in
+        exec|edit|delete|clone)
+            if [ "$COMP_CWORD" -eq 2 ]; then
+                COMPREPLY=($(compgen -W "$(command-vault --complete-ids 2>/dev/null | cut -d: -f1)" -- "$cur"))
+            fi
+            ;;
+    esac
+}
+
+complete -F _command_vault_complete command-vault
@@ -24,6 +24,10 @@ function _command_vault_log_command --on-event fish_postexec
     command command-vault add --exit-code $exit_code "$cmd" &>/dev/null
 end
 
+# Complete command IDs for the subcommands that take one
+complete -c command-vault -f -n '__fish_seen_subcommand_from exec edit delete clone' \
+    -a '(command command-vault --complete-ids 2>/dev/null | string replace ":" \t)'
+
 # Initialize command-vault integration
 if status is-interactive
     # Register the event handler
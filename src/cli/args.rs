@@ -1,4 +1,12 @@
-use clap::{Parser, Subcommand};
+use std::fmt;
+use std::str::FromStr;
+use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::db::models::SortOrder;
+
+/// Number of commands `ls` shows when neither `--limit` nor the config sets it.
+pub const DEFAULT_LS_LIMIT: usize = 50;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -9,6 +17,14 @@ pub struct Cli {
     /// Enable debug mode to see detailed command execution information
     #[arg(short, long)]
     pub debug: bool,
+
+    /// Print extra information, such as where the database is stored
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Encrypt the database with a passphrase when it is first created
+    #[arg(long)]
+    pub encrypt: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -20,24 +36,165 @@ pub enum Commands {
     ///   - Basic parameter: @filename
     ///   - With description: @filename:Name of file to create
     ///   - With default: @filename:Name of file to create=test.txt
+    ///   - With a pattern to match: @port:/^[0-9]+$/
+    ///
+    /// In a terminal, you're asked for a description and default value for each parameter.
     Add {
         /// Tags to add to the command
         #[arg(short, long)]
         tags: Vec<String>,
+
+        /// Add the command even if the same command already exists in this directory
+        #[arg(long)]
+        allow_duplicate: bool,
+
+        /// Directory to run the command in (defaults to the current directory)
+        #[arg(long)]
+        directory: Option<String>,
+
+        /// Don't check that --directory exists (for paths that will be created later)
+        #[arg(long)]
+        no_validate_dir: bool,
+
+        /// Keep a leading `$ ` or `# ` prompt marker instead of stripping it
+        #[arg(long)]
+        keep_prompt: bool,
+
+        /// Note explaining what the command is for
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Unique short name to run the command by, e.g. `exec deploy`
+        #[arg(long)]
+        alias: Option<String>,
+
+        /// Shell to always execute the command with, e.g. `bash`
+        #[arg(long)]
+        shell: Option<String>,
+
+        /// Don't ask for a description and default value for each parameter
+        #[arg(long)]
+        no_param_prompt: bool,
+
+        /// `true` always prompts for parameters when run; `false` never does, filling in
+        /// only parameters with a default and leaving any other `@word` as written
+        #[arg(long, value_name = "BOOL")]
+        interactive: Option<bool>,
+
+        /// Ask for confirmation each time the command is run (`exec --yes` skips it)
+        #[arg(long)]
+        confirm: bool,
+
+        /// Read the command from stdin instead of the arguments
+        #[arg(long, conflicts_with = "command")]
+        stdin: bool,
         
         /// Command to add
+        #[arg(trailing_var_arg = true, required_unless_present = "stdin")]
+        command: Vec<String>,
+    },
+
+    /// Save a command you've just run, tagged `favorite`, e.g. from a shell hook once it succeeds
+    Promote {
+        /// Tags to add besides `favorite`
+        #[arg(short, long)]
+        tags: Vec<String>,
+
+        /// Command that was run, exactly as typed
         #[arg(trailing_var_arg = true, required = true)]
         command: Vec<String>,
     },
     
-    /// Execute a command by id (in the current shell)
+    /// Execute a command by id or alias (in the current shell)
     Exec {
-        /// Command ID to execute
-        command_id: i64,
+        /// Command ID or alias to execute
+        command_id: CommandRef,
         
         /// Enable debug mode
         #[arg(long)]
         debug: bool,
+
+        /// Create the command's directory if it no longer exists, without asking
+        #[arg(long, conflicts_with = "fallback_cwd")]
+        create_dir: bool,
+
+        /// Run in the current directory if the command's directory no longer exists, without asking
+        #[arg(long)]
+        fallback_cwd: bool,
+
+        /// Save the command's output so it can be read back with `get --field output`
+        #[arg(long)]
+        save_output: bool,
+
+        /// Shell to execute with, overriding the command's shell and `$SHELL`
+        #[arg(long)]
+        shell: Option<String>,
+
+        /// Use parameter defaults without prompting; only parameters without one are asked for
+        #[arg(long)]
+        use_defaults: bool,
+
+        /// Print the command, exit code, stdout and stderr as JSON instead of the usual output
+        #[arg(long)]
+        capture: bool,
+
+        /// Run without asking, even if the command was saved with --confirm
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Execute the most recently run command again (or the newest, if none has been run)
+    Last {
+        /// Enable debug mode
+        #[arg(long)]
+        debug: bool,
+
+        /// Use parameter defaults without prompting; only parameters without one are asked for
+        #[arg(long)]
+        use_defaults: bool,
+
+        /// Run without asking, even if the command was saved with --confirm
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Edit a command in the edit form, or in $EDITOR with --editor
+    Edit {
+        /// Command ID or alias
+        command_id: CommandRef,
+
+        /// Edit the command text in $VISUAL/$EDITOR instead of the form
+        #[arg(long)]
+        editor: bool,
+
+        /// Turn asking before running on (`--confirm`) or off (`--confirm false`),
+        /// without opening the form
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true", conflicts_with = "editor")]
+        confirm: Option<bool>,
+
+        /// Set the command's alias, or remove it with `--alias ""`, without opening the form
+        #[arg(long, conflicts_with = "editor")]
+        alias: Option<String>,
+
+        /// Set the command's note, or remove it with `--note ""`, without opening the form
+        #[arg(long, conflicts_with = "editor")]
+        note: Option<String>,
+    },
+    /// Print a single field of a command, for use in scripts
+    Get {
+        /// Command ID or alias
+        command_id: CommandRef,
+
+        /// Which field to print
+        #[arg(short, long, value_enum, default_value = "command")]
+        field: Field,
+
+        /// Print a `command-vault exec` line that runs the command, instead of a field
+        #[arg(long, conflicts_with = "field")]
+        as_exec: bool,
+    },
+    /// List a command's parameters with their descriptions, defaults and patterns
+    Params {
+        /// Command ID or alias
+        command_id: CommandRef,
     },
     /// Search through command history
     Search {
@@ -45,19 +202,79 @@ pub enum Commands {
         #[arg(required = true)]
         query: String,
         
-        /// Maximum number of results to show
+        /// Maximum number of results to show. Use 0 to show all matches.
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Match case exactly instead of ignoring it
+        #[arg(long)]
+        case_sensitive: bool,
+
+        /// Order of the results
+        #[arg(long, value_enum, default_value_t = SortOrder::Newest)]
+        sort: SortOrder,
+
+        /// Print the SQL query instead of running the search
+        #[arg(long)]
+        explain: bool,
+
+        /// Return to the list after executing a command instead of exiting
+        #[arg(long)]
+        stay: bool,
+
+        /// Print only the number of matching commands, ignoring --limit
+        #[arg(long, conflicts_with_all = ["explain", "stay"])]
+        count: bool,
     },
     /// List all commands in chronological order
     Ls {
-        /// Maximum number of results to show. Use 0 to show all commands.
-        #[arg(short, long, default_value = "50")]
-        limit: usize,
+        /// Maximum number of results to show (default 50, or $COMMAND_VAULT_LS_LIMIT). Use 0 to show all commands.
+        #[arg(short, long)]
+        limit: Option<usize>,
         
         /// Sort in ascending order (oldest first)
         #[arg(short = 'a', long)]
         asc: bool,
+
+        /// Order by when commands were last executed (never-run commands last)
+        #[arg(long, conflicts_with = "asc")]
+        recent: bool,
+
+        /// Print the SQL query instead of listing commands
+        #[arg(long)]
+        explain: bool,
+
+        /// Print one `<id> <command>` line per command instead of starting the TUI
+        #[arg(long)]
+        oneline: bool,
+
+        /// Return to the list after executing a command instead of exiting
+        #[arg(long)]
+        stay: bool,
+
+        /// List commands with an ID greater than this one, in ID order. Pass the last ID
+        /// listed to get the next page.
+        #[arg(long, value_name = "ID", conflicts_with_all = ["asc", "recent"])]
+        after_id: Option<i64>,
+
+        /// List commands with an ID smaller than this one, highest first (in ID order
+        /// when combined with --after-id)
+        #[arg(long, value_name = "ID", conflicts_with_all = ["asc", "recent"])]
+        before_id: Option<i64>,
+
+        /// Only list commands added on this machine (see `get --field host`)
+        #[arg(long, conflicts_with = "explain")]
+        host: Option<String>,
+
+        /// Print only the number of commands, ignoring --limit
+        #[arg(long, conflicts_with_all = ["explain", "oneline", "stay"])]
+        count: bool,
+    },
+    /// Print the most recently added commands as `id<TAB>command` lines, without the TUI
+    Recent {
+        /// Maximum number of commands to show. Use 0 to show all commands.
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
     },
     /// Tag related operations
     Tag {
@@ -76,6 +293,76 @@ pub enum Commands {
         #[arg(required = true)]
         command_id: i64,
     },
+    /// List commands that are stored more than once, and with --apply delete the extra copies
+    Dedupe {
+        /// Only list the duplicates (the default)
+        #[arg(long, conflicts_with = "apply")]
+        dry_run: bool,
+
+        /// Delete all but one of each group: the one tagged `favorite`, or else the newest
+        #[arg(long)]
+        apply: bool,
+
+        /// Count the same command in different directories as a duplicate
+        #[arg(long)]
+        ignore_directory: bool,
+    },
+    /// Point commands at a project's new location after it moves, e.g.
+    /// `rehome --from ~/old/project --to ~/new/project`
+    Rehome {
+        /// Directory the commands were stored with; commands in directories below it move too
+        #[arg(long, required = true)]
+        from: String,
+
+        /// Directory that replaces it
+        #[arg(long, required = true)]
+        to: String,
+    },
+    /// Serve the vault over a local HTTP JSON API for editor integrations
+    Serve {
+        /// Address to bind to (localhost only by default)
+        #[arg(short, long, default_value = crate::server::DEFAULT_ADDR)]
+        addr: String,
+
+        /// Enable the command execution endpoint
+        #[arg(long)]
+        allow_exec: bool,
+    },
+    /// Duplicate a command (including its tags and parameters)
+    Clone {
+        /// Command ID to clone
+        #[arg(required = true)]
+        command_id: i64,
+    },
+    /// Import commands from a bash or zsh history file
+    ImportHistory {
+        /// History file to read, e.g. ~/.zsh_history or ~/.bash_history
+        #[arg(long, required = true)]
+        file: PathBuf,
+
+        /// Tags to add to every imported command
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+
+        /// Import at most this many of the most recent commands
+        #[arg(short, long)]
+        limit: Option<usize>,
+
+        /// Also import trivial commands such as `ls` and `cd`
+        #[arg(long)]
+        all: bool,
+    },
+    /// Encrypt the database with a passphrase (or change the passphrase)
+    Lock,
+    /// Remove encryption from the database
+    Unlock,
+    /// Compact the database and refresh its statistics
+    Vacuum,
+    /// Check the shell integration, clipboard and database setup, e.g. for a bug report
+    Doctor,
+    /// List command IDs with a short preview, for shell completion scripts
+    #[command(long_flag = "complete-ids", hide = true)]
+    CompleteIds,
 }
 
 #[derive(Subcommand, Debug)]
@@ -100,16 +387,120 @@ pub enum TagCommands {
         #[arg(required = true)]
         tag: String,
     },
+    /// Add a tag to every command whose text or note contains the query
+    AddMatching {
+        /// Tag to add
+        #[arg(required = true)]
+        tag: String,
+
+        /// Text to search for, as in `search`
+        #[arg(short, long, required = true)]
+        query: String,
+    },
+    /// Remove a tag from every command whose text or note contains the query
+    RemoveMatching {
+        /// Tag to remove
+        #[arg(required = true)]
+        tag: String,
+
+        /// Text to search for, as in `search`
+        #[arg(short, long, required = true)]
+        query: String,
+    },
+    /// Fold a tag into another, e.g. `tag merge k8s kubernetes`, deleting the first
+    Merge {
+        /// Tag to fold away
+        #[arg(required = true)]
+        from: String,
+
+        /// Tag to keep; commands tagged with `from` get this tag instead
+        #[arg(required = true)]
+        into: String,
+    },
     /// List all tags and their usage count
-    List,
+    List {
+        /// Only list tags starting with this prefix
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Print `name<TAB>count` lines with no header or colors, for scripts
+        #[arg(long)]
+        porcelain: bool,
+    },
+    /// Set the color a tag is shown in, e.g. `tag color prod red`
+    Color {
+        /// Tag to color
+        #[arg(required = true)]
+        tag: String,
+
+        /// Color name (`red`, `light-blue`, ...), 256-color index or `#rrggbb`
+        #[arg(required = true)]
+        color: String,
+    },
     /// Search commands by tag
     Search {
-        /// Tag to search for
+        /// Tags to search for (matches any of them unless --all is given)
         #[arg(required = true)]
-        tag: String,
+        tags: Vec<String>,
+
+        /// Only show commands that have all of the given tags
+        #[arg(long)]
+        all: bool,
         
-        /// Maximum number of results to show
+        /// Maximum number of results to show. Use 0 to show all matches.
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Print only the number of matching commands, ignoring --limit
+        #[arg(long)]
+        count: bool,
     },
 }
+
+/// A field of a command that `get` can print.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Command,
+    Directory,
+    Note,
+    Alias,
+    Shell,
+    /// Output saved by `exec --save-output`
+    Output,
+    /// Machine the command was added on
+    Host,
+}
+
+/// A command given on the command line, either by ID or by alias.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandRef {
+    Id(i64),
+    Alias(String),
+}
+
+impl FromStr for CommandRef {
+    type Err = std::convert::Infallible;
+
+    /// Numbers are IDs; anything else is an alias.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(id) => CommandRef::Id(id),
+            Err(_) => CommandRef::Alias(s.to_string()),
+        })
+    }
+}
+
+impl From<i64> for CommandRef {
+    fn from(id: i64) -> Self {
+        CommandRef::Id(id)
+    }
+}
+
+impl fmt::Display for CommandRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandRef::Id(id) => write!(f, "{}", id),
+            CommandRef::Alias(alias) => write!(f, "{}", alias),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Result, anyhow};
 use chrono::{Local, Utc};
-use std::io::{self, Stdout};
+use std::io::{self, Read, Stdout};
+use std::path::{Component, Path, PathBuf};
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -14,39 +15,124 @@ use ratatui::{
     Terminal,
 };
 use colored::*;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password};
+use regex::Regex;
 
+use crate::config;
 use crate::db::{Command, Database};
-use crate::ui::App;
-use crate::utils::params::parse_parameters;
-use crate::utils::params::substitute_parameters;
-use crate::exec::{ExecutionContext, execute_shell_command};
+use crate::db::models::Parameter;
+use crate::db::path::default_db_path;
+use crate::db::store::KEY_ENV_VAR;
+use crate::ui::{AddCommandApp, App};
+use crate::ui::editor::edit_in_editor;
+use crate::ui::picker::{run_picker, simple_ui_requested};
+use crate::ui::display::{collapse_lines, count_summary, indent_continuation, parse_color};
+use crate::utils::params::{parse_parameters, reconcile_parameters};
+use crate::utils::params::{substitute_parameters_using_defaults, substitute_parameters_with_history};
+use crate::utils::prompt::{split_prompt_marker, strip_prompt_marker};
+use crate::utils::quote::join_args;
+use crate::utils::tags::{extract_inline_tags, merge_tags, split_inline_tag_args};
+use crate::utils::history::{parse_history, select_commands};
+use crate::utils::host::hostname;
+use crate::utils::limits::{check_command_length, MAX_OUTPUT_LENGTH};
+use crate::utils::time::humanize_ago;
+use crate::exec::{capture_shell_command, confirm_run, exec_invocation, execute_shell_command, prompt_missing_directory, resolve_directory, ExecutionContext, MissingDirectory};
+use crate::server::Server;
 
-use super::args::{Commands, TagCommands};
+use super::args::{CommandRef, Commands, Field, TagCommands, DEFAULT_LS_LIMIT};
+use super::doctor;
 
-fn print_commands(commands: &[Command]) -> Result<()> {
+/// Tag `promote` marks commands with.
+pub const PROMOTED_TAG: &str = "favorite";
+
+/// The ID of the command `dedupe` keeps from a group of duplicates: the one
+/// tagged `favorite` (see `promote`), or else the newest.
+/// 
+/// `group` is ordered newest first, as `find_duplicates` returns it.
+pub fn duplicate_to_keep(group: &[Command]) -> i64 {
+    group
+        .iter()
+        .find(|cmd| cmd.tags.iter().any(|tag| tag == PROMOTED_TAG))
+        .or_else(|| group.first())
+        .and_then(|cmd| cmd.id)
+        .unwrap_or(0)
+}
+
+/// Shows `commands` in the TUI, or in the line-based picker if it was asked for
+/// with `COMMAND_VAULT_SIMPLE_UI` or the TUI can't start. Without a terminal to
+/// pick in, they're printed instead.
+fn browse_commands(commands: Vec<Command>, db: &mut Database, debug: bool, stay: bool, case_sensitive: bool) -> Result<()> {
+    if simple_ui_requested() && is_interactive() {
+        return run_picker(&commands, db, debug, stay);
+    }
+    let mut app = App::new(commands.clone(), db, debug);
+    app.stay_after_exec = stay;
+    app.set_case_sensitive(case_sensitive);
+    match app.run() {
+        Ok(_) => Ok(()),
+        Err(e) if e.to_string() == "Operation cancelled by user" => {
+            print!("\n{}", "Operation cancelled.".yellow());
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Failed to start TUI mode: {}", e);
+            if is_interactive() {
+                return run_picker(&commands, db, debug, stay);
+            }
+            print_commands(&commands, Some(db.count_commands()?))
+        }
+    }
+}
+
+/// Prints `commands`, headed by how many of the `total` stored commands they are (if given).
+fn print_commands(commands: &[Command], total: Option<i64>) -> Result<()> {
+    let header = match total {
+        Some(total) => format!("Command History ({}):", count_summary(commands, total)),
+        None => "Command History:".to_string(),
+    };
     let terminal_result = setup_terminal();
     
     match terminal_result {
         Ok(mut terminal) => {
-            let res = print_commands_ui(&mut terminal, commands);
+            let res = print_commands_ui(&mut terminal, commands, &header);
             restore_terminal(&mut terminal)?;
             res
         }
         Err(_) => {
             // Fallback to simple text output
-            println!("Command History:");
+            println!("{}", header);
             println!("─────────────────────────────────────────────");
             for cmd in commands {
                 let local_time = cmd.timestamp.with_timezone(&Local);
-                println!("{} │ {}", local_time.format("%Y-%m-%d %H:%M:%S"), cmd.command);
+                let prefix = format!("{} ({}) │ ", local_time.format("%Y-%m-%d %H:%M:%S"), humanize_ago(cmd.timestamp));
+                for (i, line) in indent_continuation(&cmd.command, prefix.chars().count()).iter().enumerate() {
+                    if i == 0 {
+                        println!("{}{}", prefix, line);
+                    } else {
+                        println!("{}", line);
+                    }
+                }
                 if !cmd.tags.is_empty() {
                     println!("    Tags: {}", cmd.tags.join(", "));
                 }
+                if let Some(alias) = &cmd.alias {
+                    println!("    Alias: {}", alias);
+                }
+                if let Some(shell) = &cmd.shell {
+                    println!("    Shell: {}", shell);
+                }
+                if let Some(host) = &cmd.hostname {
+                    println!("    Host: {}", host);
+                }
+                if let Some(note) = &cmd.note {
+                    println!("    Note: {}", note);
+                }
                 if !cmd.parameters.is_empty() {
                     println!("    Parameters:");
                     for param in &cmd.parameters {
                         let desc = param.description.as_deref().unwrap_or("None");
-                        println!("      - {}: {} (default: {})", param.name, desc, "None");
+                        let default = param.default_value.as_deref().unwrap_or("None");
+                        println!("      - {}: {} (default: {})", param.name, desc, default);
                     }
                 }
                 println!("    Directory: {}", cmd.directory);
@@ -57,7 +143,7 @@ fn print_commands(commands: &[Command]) -> Result<()> {
     }
 }
 
-fn print_commands_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, commands: &[Command]) -> Result<()> {
+fn print_commands_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, commands: &[Command], header: &str) -> Result<()> {
     terminal.draw(|f| {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -67,18 +153,25 @@ fn print_commands_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, commands
 
         let mut lines = vec![];
         lines.push(Line::from(Span::styled(
-            "Command History:",
+            header,
             Style::default().fg(Color::Cyan),
         )));
         lines.push(Line::from(Span::raw("─────────────────────────────────────────────")));
 
         for cmd in commands {
             let local_time = cmd.timestamp.with_timezone(&Local);
+            let time = local_time.format("%Y-%m-%d %H:%M:%S").to_string();
+            let ago = format!(" ({})", humanize_ago(cmd.timestamp));
+            // Continuation lines of a multi-line command start under its first line
+            let indent = time.chars().count() + ago.chars().count() + " │ ".chars().count();
+            let mut command_lines = indent_continuation(&cmd.command, indent).into_iter();
             lines.push(Line::from(vec![
-                Span::styled(local_time.format("%Y-%m-%d %H:%M:%S").to_string(), Style::default().fg(Color::Yellow)),
+                Span::styled(time, Style::default().fg(Color::Yellow)),
+                Span::styled(ago, Style::default().fg(Color::DarkGray)),
                 Span::raw(" │ "),
-                Span::raw(&cmd.command),
+                Span::raw(command_lines.next().unwrap_or_default()),
             ]));
+            lines.extend(command_lines.map(|line| Line::from(Span::raw(line))));
             lines.push(Line::from(vec![
                 Span::raw("    Directory: "),
                 Span::raw(&cmd.directory),
@@ -89,6 +182,30 @@ fn print_commands_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, commands
                     Span::raw(cmd.tags.join(", ")),
                 ]));
             }
+            if let Some(alias) = &cmd.alias {
+                lines.push(Line::from(vec![
+                    Span::raw("    Alias: "),
+                    Span::raw(alias),
+                ]));
+            }
+            if let Some(shell) = &cmd.shell {
+                lines.push(Line::from(vec![
+                    Span::raw("    Shell: "),
+                    Span::raw(shell),
+                ]));
+            }
+            if let Some(host) = &cmd.hostname {
+                lines.push(Line::from(vec![
+                    Span::raw("    Host: "),
+                    Span::raw(host),
+                ]));
+            }
+            if let Some(note) = &cmd.note {
+                lines.push(Line::from(Span::styled(
+                    format!("    Note: {}", note),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
             lines.push(Line::from(Span::raw("─────────────────────────────────────────────")));
         }
 
@@ -98,6 +215,181 @@ fn print_commands_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, commands
     Ok(())
 }
 
+/// Reads a command for `add --stdin`, dropping the trailing newline.
+pub fn read_command(mut reader: impl Read) -> Result<String> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(input.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Formats a SQL query and its bound parameters for `--explain` output.
+pub fn format_explain(sql: &str, params: &[String]) -> String {
+    let mut output = String::from("SQL:\n");
+    for line in sql.lines() {
+        output.push_str(&format!("  {}\n", line.trim()));
+    }
+    output.push_str("Parameters:");
+    if params.is_empty() {
+        output.push_str(" (none)");
+    }
+    for (i, param) in params.iter().enumerate() {
+        output.push_str(&format!("\n  ?{} = {:?}", i + 1, param));
+    }
+    output
+}
+
+/// Formats tags as `name<TAB>count` lines for `tag list --porcelain`.
+pub fn format_tags_porcelain(tags: &[(String, i64)]) -> Vec<String> {
+    tags.iter().map(|(tag, count)| format!("{}\t{}", tag, count)).collect()
+}
+
+/// Formats a command's parameters for `params`: one line per parameter with its
+/// description, default value and pattern, names aligned.
+pub fn format_parameters(parameters: &[Parameter]) -> Vec<String> {
+    let width = parameters.iter().map(|param| param.name.chars().count() + 1).max().unwrap_or(0);
+    parameters
+        .iter()
+        .map(|param| {
+            let mut line = format!("{:<width$}", format!("@{}", param.name), width = width);
+            line.push_str("  ");
+            line.push_str(param.description.as_deref().unwrap_or("-"));
+            if let Some(default) = &param.default_value {
+                line.push_str(&format!("  default: {}", default));
+            }
+            if let Some(pattern) = &param.pattern {
+                line.push_str(&format!("  pattern: /{}/", pattern));
+            }
+            if param.secret {
+                line.push_str("  secret");
+            }
+            line
+        })
+        .collect()
+}
+
+/// Maximum number of characters of a command shown in completion previews.
+const COMPLETION_PREVIEW_LENGTH: usize = 40;
+
+/// Formats commands as `id:preview` lines for shell completion.
+/// 
+/// Previews are kept on one line and truncated so completion menus stay readable.
+pub fn format_completion_ids(commands: &[(i64, String)]) -> Vec<String> {
+    commands
+        .iter()
+        .map(|(id, command)| {
+            let preview = command.split_whitespace().collect::<Vec<_>>().join(" ");
+            let preview = if preview.chars().count() > COMPLETION_PREVIEW_LENGTH {
+                let truncated: String = preview.chars().take(COMPLETION_PREVIEW_LENGTH - 3).collect();
+                format!("{}...", truncated.trim_end())
+            } else {
+                preview
+            };
+            format!("{}:{}", id, preview)
+        })
+        .collect()
+}
+
+/// Resolves the directory a new command is stored with.
+/// 
+/// Without an explicit directory the current directory is used. An explicit
+/// directory has `~` and `$VAR`s expanded, so the stored path is absolute and
+/// doesn't depend on the environment it's later run in, and must exist (unless
+/// `validate` is false) so typos are caught at add time rather than when the
+/// command is executed.
+fn resolve_add_directory(directory: Option<&str>, validate: bool) -> Result<String> {
+    let current_dir = std::env::current_dir()?;
+    let directory = match directory {
+        Some(dir) => current_dir.join(resolve_directory(dir)),
+        None => return Ok(current_dir.to_string_lossy().to_string()),
+    };
+
+    if validate {
+        if !directory.exists() {
+            return Err(anyhow!("Directory does not exist: {}", directory.display()));
+        }
+        if !directory.is_dir() {
+            return Err(anyhow!("Not a directory: {}", directory.display()));
+        }
+        return Ok(directory.canonicalize()?.to_string_lossy().to_string());
+    }
+    Ok(directory.to_string_lossy().to_string())
+}
+
+/// Resolves a `rehome` directory the way `add` stores directories, so `.`,
+/// `~/old` or `$HOME/old` match what was stored.
+/// 
+/// A directory that still exists is canonicalized like a validated `add`
+/// directory. The old one is usually gone, so otherwise `.` and `..` are only
+/// resolved lexically.
+fn resolve_rehome_directory(directory: &str) -> Result<String> {
+    if directory.is_empty() {
+        return Err(anyhow!("Directories can't be empty"));
+    }
+    let path = resolve_add_directory(Some(directory), false)?;
+    if Path::new(&path).is_dir() {
+        return resolve_add_directory(Some(directory), true);
+    }
+    Ok(normalize_lexically(Path::new(&path)).to_string_lossy().to_string())
+}
+
+/// Removes `.` and `..` from `path` without touching the filesystem.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Looks up a command by ID or alias, failing if there is none.
+fn find_command(db: &Database, command_ref: &CommandRef) -> Result<Command> {
+    match command_ref {
+        CommandRef::Id(id) => db.get_command(*id)?
+            .ok_or_else(|| anyhow!("Command not found with ID: {}", id)),
+        CommandRef::Alias(alias) => db.get_command_by_alias(alias)?
+            .ok_or_else(|| anyhow!("Command not found with alias: {}", alias)),
+    }
+}
+
+/// Asks for a description (unless the command text gives one) and a default value for each parameter.
+fn prompt_parameter_details(parameters: &mut [Parameter]) -> Result<()> {
+    let theme = ColorfulTheme::default();
+    for param in parameters {
+        if param.description.is_none() {
+            let description: String = Input::with_theme(&theme)
+                .with_prompt(format!("Description for @{} (optional)", param.name))
+                .allow_empty(true)
+                .interact_text()?;
+            param.description = Some(description.trim().to_string()).filter(|d| !d.is_empty());
+        }
+
+        let pattern = param.pattern.as_deref().and_then(|pattern| Regex::new(pattern).ok());
+        let default: String = Input::with_theme(&theme)
+            .with_prompt(format!("Default value for @{} (optional)", param.name))
+            .allow_empty(true)
+            .validate_with(|value: &String| match &pattern {
+                Some(regex) if !value.is_empty() && !regex.is_match(value) => {
+                    Err(format!("Must match /{}/", regex.as_str()))
+                }
+                _ => Ok(()),
+            })
+            .interact_text()?;
+        param.default_value = Some(default).filter(|d| !d.is_empty());
+    }
+    Ok(())
+}
+
+/// Whether we can prompt the user (stdin is a terminal and we're not under test).
+fn is_interactive() -> bool {
+    std::env::var("COMMAND_VAULT_TEST").is_err() && atty::is(atty::Stream::Stdin)
+}
+
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -115,35 +407,74 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result
 
 pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Result<()> {
     match command {
-        Commands::Add { command, tags } => {
-            // Process command parts with special handling for git format strings
-            let command_str = command.iter().enumerate().fold(String::new(), |mut acc, (i, arg)| {
-                if i > 0 {
-                    acc.push(' ');
-                }
-                // Special case for git format strings
-                if arg.starts_with("--pretty=format:") {
-                    acc.push_str(&format!("\"{}\"", arg));
-                } else {
-                    acc.push_str(arg);
+        Commands::Add { command, mut tags, allow_duplicate, directory, no_validate_dir, keep_prompt, note, alias, shell, no_param_prompt, interactive, stdin, confirm } => {
+            // Piped input is taken as a single, already-quoted command
+            let command = if stdin { vec![read_command(io::stdin().lock())?] } else { command };
+            let (marker, args) = split_prompt_marker(&command);
+            
+            // Trailing `#tag` arguments are tags rather than part of the command
+            let (args, inline_tags) = split_inline_tag_args(args);
+            merge_tags(&mut tags, inline_tags);
+            
+            // Rebuild the shell command, quoting arguments only where needed
+            let mut command_str = join_args(args)?;
+            
+            // Drop a `$ `/`# ` prompt marker pasted along with the command
+            let stripped = match marker {
+                Some(marker) if keep_prompt => {
+                    command_str = format!("{} {}", marker, command_str);
+                    false
                 }
-                acc
-            });
+                Some(_) => true,
+                None if keep_prompt => false,
+                None => match strip_prompt_marker(&command_str) {
+                    Some(rest) => {
+                        command_str = rest.to_string();
+                        true
+                    }
+                    None => false,
+                },
+            };
+            if stripped {
+                println!("{}", "Note: stripped leading prompt marker (use --keep-prompt to keep it)".yellow());
+            }
+            
+            // The same, for a command given as a single string
+            let (without_tags, inline_tags) = extract_inline_tags(&command_str);
+            command_str = without_tags;
+            merge_tags(&mut tags, inline_tags);
             
             // Don't allow empty commands
             if command_str.trim().is_empty() {
                 return Err(anyhow!("Cannot add empty command"));
             }
+            check_command_length(&command_str)?;
+            
+            let directory = resolve_add_directory(directory.as_deref(), !no_validate_dir)?;
             
-            // Get the current directory
-            let directory = std::env::current_dir()?
-                .to_string_lossy()
-                .to_string();
+            // Don't silently store the same command twice
+            let mut copy = allow_duplicate;
+            if !allow_duplicate {
+                if let Some(existing_id) = db.find_exact(&command_str, &directory)? {
+                    let add_anyway = is_interactive() && Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!("Command already exists with ID {}. Add it anyway?", existing_id))
+                        .default(false)
+                        .interact()?;
+                    if !add_anyway {
+                        println!("Command already exists with ID {}", existing_id);
+                        return Ok(());
+                    }
+                    copy = true;
+                }
+            }
             
             let timestamp = Local::now().with_timezone(&Utc);
             
             // Parse parameters from command string
-            let parameters = parse_parameters(&command_str);
+            let mut parameters = parse_parameters(&command_str);
+            if !no_param_prompt && interactive != Some(false) && is_interactive() {
+                prompt_parameter_details(&mut parameters)?;
+            }
             
             let cmd = Command {
                 id: None,
@@ -152,8 +483,15 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
                 directory,
                 tags,
                 parameters,
+                note: note.filter(|n| !n.trim().is_empty()),
+                alias,
+                shell,
+                interactive,
+                hostname: hostname(),
+                created_at: None,
+                confirm_before_run: confirm,
             };
-            let id = db.add_command(&cmd)?;
+            let id = if copy { db.add_copy(&cmd)? } else { db.add_command(&cmd)? };
             println!("Command added to history with ID: {}", id);
             
             // If command has parameters, show them
@@ -161,51 +499,138 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
                 println!("\nDetected parameters:");
                 for param in &cmd.parameters {
                     let desc = param.description.as_deref().unwrap_or("None");
-                    println!("  {} - Description: {}", param.name.yellow(), desc);
+                    match &param.default_value {
+                        Some(default) => println!("  {} - Description: {}, Default: {}", param.name.yellow(), desc, default),
+                        None => println!("  {} - Description: {}", param.name.yellow(), desc),
+                    }
                 }
             }
         }
-        Commands::Search { query, limit } => {
-            let commands = db.search_commands(&query, limit)?;
-            let mut app = App::new(commands.clone(), db, debug);
-            match app.run() {
-                Ok(_) => (),
-                Err(e) => {
-                    if e.to_string() == "Operation cancelled by user" {
-                        print!("\n{}", "Operation cancelled.".yellow());
-                        return Ok(());
+        Commands::Promote { command, mut tags } => {
+            let (args, inline_tags) = split_inline_tag_args(&command);
+            merge_tags(&mut tags, inline_tags);
+            merge_tags(&mut tags, vec![PROMOTED_TAG.to_string()]);
+
+            // The command has already run, so `@word` in it isn't a parameter
+            let command_str = join_args(args)?.replace('@', "@@");
+            if command_str.trim().is_empty() {
+                return Err(anyhow!("Cannot add empty command"));
+            }
+            check_command_length(&command_str)?;
+            let directory = resolve_add_directory(None, false)?;
+
+            // Promoting a stored command again just marks it
+            if let Some(existing_id) = db.find_exact(&command_str, &directory)? {
+                db.add_tags_to_command(existing_id, &tags)?;
+                println!("Command already exists with ID {}, tagged '{}'", existing_id, PROMOTED_TAG);
+                return Ok(());
+            }
+            let id = db.add_command(&Command {
+                id: None,
+                command: command_str,
+                timestamp: Local::now().with_timezone(&Utc),
+                directory,
+                tags,
+                parameters: Vec::new(),
+                note: None,
+                alias: None,
+                shell: None,
+                interactive: None,
+                hostname: hostname(),
+                created_at: None,
+                confirm_before_run: false,
+            })?;
+            println!("Command promoted with ID: {}", id);
+        }
+        Commands::Search { query, limit, case_sensitive, sort, explain, stay, count } => {
+            if count {
+                println!("{}", db.search_commands_sorted(&query, 0, sort, case_sensitive)?.len());
+                return Ok(());
+            }
+            if explain {
+                let (sql, params) = Database::search_commands_sorted_query(&query, limit, sort, case_sensitive);
+                println!("{}", format_explain(&sql, &params));
+                return Ok(());
+            }
+            let commands = db.search_commands_sorted(&query, limit, sort, case_sensitive)?;
+            browse_commands(commands, db, debug, stay, case_sensitive)?;
+        }
+        Commands::Ls { limit, asc, recent, explain, oneline, stay, after_id, before_id, host, count } => {
+            let limit = limit.unwrap_or(DEFAULT_LS_LIMIT);
+            let paged = after_id.is_some() || before_id.is_some();
+            if explain {
+                let (sql, params) = if paged {
+                    Database::list_commands_paged_query(after_id, before_id, limit)
+                } else if recent {
+                    Database::list_by_last_run_query(limit)
+                } else {
+                    Database::list_commands_query(limit, asc)
+                };
+                println!("{}", format_explain(&sql, &params));
+                return Ok(());
+            }
+
+            let load = |db: &Database, limit| {
+                if paged {
+                    db.list_commands_paged(after_id, before_id, limit)
+                } else if recent {
+                    db.list_by_last_run(limit)
+                } else {
+                    db.list_commands(limit, asc)
+                }
+            };
+            if count {
+                let total = match &host {
+                    Some(host) => load(db, 0)?.iter().filter(|cmd| cmd.hostname.as_deref() == Some(host.as_str())).count(),
+                    None if !paged => db.count_commands()? as usize,
+                    None => load(db, 0)?.len(),
+                };
+                println!("{}", total);
+                return Ok(());
+            }
+            let commands = match &host {
+                // Filtered after loading, so the limit applies to the matches
+                Some(host) => {
+                    let mut commands = load(db, 0)?;
+                    commands.retain(|cmd| cmd.hostname.as_deref() == Some(host.as_str()));
+                    if limit != 0 {
+                        commands.truncate(limit);
+                    }
+                    commands
+                }
+                None if oneline && !paged && !recent => {
+                    for (id, command) in db.list_command_summaries(limit, asc)? {
+                        println!("{} {}", id, command.replace('\n', " "));
                     }
-                    eprintln!("Failed to start TUI mode: {}", e);
-                    print_commands(&commands)?;
+                    return Ok(());
                 }
+                None => load(db, limit)?,
+            };
+
+            if oneline {
+                for cmd in commands {
+                    println!("{} {}", cmd.id.unwrap_or(0), cmd.command.replace('\n', " "));
+                }
+                return Ok(());
             }
-        }
-        Commands::Ls { limit, asc } => {
-            let commands = db.list_commands(limit, asc)?;
             if commands.is_empty() {
                 print!("No commands found.");
                 return Ok(());
             }
 
             // Check if TUI should be disabled (useful for testing or non-interactive environments)
-            if std::env::var("COMMAND_VAULT_NO_TUI").is_ok() {
+            if std::env::var("COMMAND_VAULT_NO_TUI").is_ok() || config::get().no_tui {
                 for cmd in commands {
                     print!("{}: {} ({})", cmd.id.unwrap_or(0), cmd.command, cmd.directory);
                 }
                 return Ok(());
             }
 
-            let mut app = App::new(commands.clone(), db, debug);
-            match app.run() {
-                Ok(_) => (),
-                Err(e) => {
-                    if e.to_string() == "Operation cancelled by user" {
-                        print!("\n{}", "Operation cancelled.".yellow());
-                        return Ok(());
-                    }
-                    eprintln!("Failed to start TUI mode: {}", e);
-                    print_commands(&commands)?;
-                }
+            browse_commands(commands, db, debug, stay, false)?;
+        }
+        Commands::Recent { limit } => {
+            for cmd in db.list_commands(limit, false)? {
+                println!("{}\t{}", cmd.id.unwrap_or(0), cmd.command.replace('\n', " "));
             }
         }
         Commands::Tag { action } => match action {
@@ -221,8 +646,31 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
                     Err(e) => eprintln!("Failed to remove tag: {}", e),
                 }
             }
-            TagCommands::List => {
-                match db.list_tags() {
+            TagCommands::AddMatching { tag, query } => {
+                let ids: Vec<i64> = db.search_commands(&query, 0)?.iter().filter_map(|cmd| cmd.id).collect();
+                let count = db.add_tag_to_commands(&ids, &tag)?;
+                println!("Tagged {} command{} with '{}'", count, if count == 1 { "" } else { "s" }, tag);
+            }
+            TagCommands::RemoveMatching { tag, query } => {
+                let ids: Vec<i64> = db.search_commands(&query, 0)?.iter().filter_map(|cmd| cmd.id).collect();
+                let count = db.remove_tag_from_commands(&ids, &tag)?;
+                println!("Removed '{}' from {} command{}", tag, count, if count == 1 { "" } else { "s" });
+            }
+            TagCommands::Merge { from, into } => {
+                let count = db.merge_tags(&from, &into)?;
+                println!("Merged '{}' into '{}' ({} command{})", from, into, count, if count == 1 { "" } else { "s" });
+            }
+            TagCommands::List { prefix, porcelain } => {
+                let tags = match prefix {
+                    Some(prefix) => db.list_tags_with_prefix(&prefix),
+                    None => db.list_tags(),
+                };
+                match tags {
+                    Ok(tags) if porcelain => {
+                        for line in format_tags_porcelain(&tags) {
+                            println!("{}", line);
+                        }
+                    }
                     Ok(tags) => {
                         if tags.is_empty() {
                             print!("No tags found");
@@ -238,38 +686,230 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
                     Err(e) => eprintln!("Failed to list tags: {}", e),
                 }
             }
-            TagCommands::Search { tag, limit } => {
-                match db.search_by_tag(&tag, limit) {
-                    Ok(commands) => print_commands(&commands)?,
+            TagCommands::Color { tag, color } => {
+                if parse_color(&color).is_none() {
+                    return Err(anyhow!("Unknown color '{}'", color));
+                }
+                db.set_tag_color(&tag, &color)?;
+                print!("Tag color set");
+            }
+            TagCommands::Search { tags, all, limit, count } => {
+                let limit = if count { 0 } else { limit };
+                let result = match tags.as_slice() {
+                    [tag] => db.search_by_tag(tag, limit),
+                    _ => db.search_by_tags(&tags, all, limit),
+                };
+                match result {
+                    Ok(commands) if count => println!("{}", commands.len()),
+                    Ok(commands) if commands.is_empty() => print!("No commands found."),
+                    Ok(commands) if std::env::var("COMMAND_VAULT_NO_TUI").is_ok() || config::get().no_tui => {
+                        print_commands(&commands, Some(db.count_commands()?))?
+                    }
+                    // Browsable like `search` and `ls`, falling back to printing without a terminal
+                    Ok(commands) => browse_commands(commands, db, debug, false, false)?,
                     Err(e) => eprintln!("Failed to search by tag: {}", e),
                 }
             }
         },
-        Commands::Exec { command_id, debug } => {
-            let command = db.get_command(command_id)?
-                .ok_or_else(|| anyhow!("Command not found with ID: {}", command_id))?;
+        Commands::Exec { command_id, debug, create_dir, fallback_cwd, save_output, shell, use_defaults, capture, yes } => {
+            let command = find_command(db, &command_id)?;
+            let command_id = command.id.unwrap_or_default();
             
-            // Create the directory if it doesn't exist
-            if !std::path::Path::new(&command.directory).exists() {
-                std::fs::create_dir_all(&command.directory)?;
+            // Only recreate a missing directory when asked to: it may have been
+            // removed on purpose, or the stored path may be a typo
+            let mut working_dir = command.directory.clone();
+            let directory = resolve_directory(&command.directory);
+            if !directory.exists() {
+                let choice = if create_dir {
+                    MissingDirectory::Create
+                } else if fallback_cwd {
+                    MissingDirectory::UseCurrent
+                } else if is_interactive() {
+                    prompt_missing_directory(&command.directory)?
+                } else {
+                    MissingDirectory::Cancel
+                };
+                match choice {
+                    MissingDirectory::Create => {
+                        eprintln!("{}", format!("Creating missing directory {}", command.directory).yellow());
+                        std::fs::create_dir_all(&directory)?;
+                    }
+                    MissingDirectory::UseCurrent => {
+                        working_dir = std::env::current_dir()?.to_string_lossy().to_string();
+                        eprintln!("{}", format!(
+                            "Warning: directory {} no longer exists, running in {}",
+                            command.directory, working_dir
+                        ).yellow());
+                    }
+                    MissingDirectory::Cancel => {
+                        return Err(anyhow!(
+                            "Directory does not exist: {} (use --fallback-cwd to run in the current directory, or --create-dir to create it)",
+                            command.directory
+                        ));
+                    }
+                }
             }
             
-            let current_params = parse_parameters(&command.command);
-            let final_command = substitute_parameters(&command.command, &current_params, None)?;
+            // A command saved with `--interactive true` always prompts
+            let final_command = if use_defaults && command.interactive != Some(true) {
+                substitute_parameters_using_defaults(db, command.id, &command.command, None)?
+            } else {
+                substitute_parameters_with_history(db, command.id, &command.command, None)?
+            };
+
+            // Without a terminal there's no one to ask, so only --yes runs it
+            if command.confirm_before_run && !yes {
+                if !is_interactive() {
+                    return Err(anyhow!("Command {} asks for confirmation before running; pass --yes to run it", command_id));
+                }
+                if !confirm_run(&final_command.display)? {
+                    println!("Command not run");
+                    return Ok(());
+                }
+            }
 
             let ctx = ExecutionContext {
-                command: final_command.clone(),
-                directory: command.directory.clone(),
+                command: final_command.command.clone(),
+                directory: working_dir.clone(),
                 test_mode: std::env::var("COMMAND_VAULT_TEST").is_ok(),
                 debug_mode: debug,
+                shell: shell.or(command.shell.clone()),
             };
 
-            println!("\n─────────────────────────────────────────────");
-            println!("Command to execute: {}", final_command);
-            println!("Working directory: {}", command.directory);
-            println!();  // Add extra newline before command output
+            // Only the JSON goes to stdout, so scripts can parse it
+            let output = if capture {
+                db.record_run(command_id)?;
+                let mut result = capture_shell_command(&ctx)?;
+                result.command = final_command.display.clone();
+                println!("{}", serde_json::to_string_pretty(&result)?);
+                result.stdout
+            } else {
+                println!("\n─────────────────────────────────────────────");
+                println!("Command to execute: {}", final_command.display);
+                println!("Working directory: {}", working_dir);
+                println!();  // Add extra newline before command output
 
-            execute_shell_command(&ctx)?;
+                db.record_run(command_id)?;
+                execute_shell_command(&ctx)?
+            };
+            if save_output {
+                if output.len() > MAX_OUTPUT_LENGTH {
+                    eprintln!("{}", format!("Note: saved output truncated to {} bytes", MAX_OUTPUT_LENGTH).yellow());
+                }
+                db.record_output(command_id, &output)?;
+            }
+        }
+        Commands::Last { debug, use_defaults, yes } => {
+            let Some(command) = db.list_by_last_run(1)?.into_iter().next() else {
+                return Err(anyhow!("No commands in the vault yet; add one with `add`"));
+            };
+            return handle_command(Commands::Exec {
+                command_id: command.id.unwrap_or_default().into(),
+                debug,
+                create_dir: false,
+                fallback_cwd: false,
+                save_output: false,
+                shell: None,
+                use_defaults,
+                capture: false,
+                yes,
+            }, db, debug);
+        }
+        Commands::Edit { command_id, editor, confirm, alias, note } => {
+            let cmd = find_command(db, &command_id)?;
+            // Settings given as flags are changed without opening the form
+            if confirm.is_some() || alias.is_some() || note.is_some() {
+                let mut updated = cmd.clone();
+                if let Some(confirm) = confirm {
+                    updated.confirm_before_run = confirm;
+                }
+                if let Some(alias) = &alias {
+                    updated.alias = Some(alias.clone()).filter(|alias| !alias.is_empty());
+                }
+                if let Some(note) = &note {
+                    updated.note = Some(note.clone()).filter(|n| !n.trim().is_empty());
+                }
+                db.update_command(&updated)?;
+
+                if let Some(confirm) = confirm {
+                    let state = if confirm { "asks" } else { "no longer asks" };
+                    println!("Command {} {} for confirmation before running", command_id, state);
+                }
+                if alias.is_some() {
+                    match &updated.alias {
+                        Some(alias) => println!("Command {} has the alias {}", command_id, alias),
+                        None => println!("Command {} no longer has an alias", command_id),
+                    }
+                }
+                if note.is_some() {
+                    let state = if updated.note.is_some() { "Updated the note of" } else { "Removed the note from" };
+                    println!("{} command {}", state, command_id);
+                }
+                return Ok(());
+            }
+            let edited = if editor {
+                edit_in_editor(&cmd.command)?
+                    .map(|command| (command, cmd.tags.clone(), cmd.directory.clone()))
+            } else {
+                let mut add_app = AddCommandApp::new();
+                add_app.set_command(cmd.command.clone());
+                add_app.set_tags(cmd.tags.clone());
+                add_app.set_directory(cmd.directory.clone());
+                add_app.set_known_tags(db.list_tags()?.into_iter().map(|(tag, _)| tag).collect());
+                add_app.run()?.map(|(command, tags, directory, _)| (command, tags, directory))
+            };
+            let Some((command, tags, directory)) = edited else {
+                println!("Edit cancelled");
+                return Ok(());
+            };
+
+            if command.trim().is_empty() {
+                return Err(anyhow!("Cannot save an empty command"));
+            }
+            check_command_length(&command)?;
+            // Keep the old directory if the field was cleared
+            let directory = if directory.trim().is_empty() { cmd.directory.clone() } else { directory };
+            // Parameters still in the text keep their descriptions and defaults
+            let parameters = reconcile_parameters(&command, &cmd.parameters);
+            db.update_command(&Command { command, tags, directory, parameters, timestamp: Utc::now(), ..cmd })?;
+            println!("Command updated");
+        }
+        Commands::Get { command_id, field, as_exec } => {
+            let command = find_command(db, &command_id)?;
+            if as_exec {
+                println!("{}", exec_invocation(&command));
+                return Ok(());
+            }
+            let value = match field {
+                Field::Command => Some(command.command),
+                Field::Directory => Some(command.directory),
+                Field::Note => command.note,
+                Field::Alias => command.alias,
+                Field::Shell => command.shell,
+                Field::Output => db.last_output(command.id.unwrap_or_default())?,
+                Field::Host => command.hostname,
+            };
+            match value {
+                // Output keeps its own trailing newline
+                Some(value) if field == Field::Output => print!("{}", value),
+                Some(value) => println!("{}", value),
+                None => return Err(anyhow!("Command {} has no {}", command_id, format!("{:?}", field).to_lowercase())),
+            }
+        }
+        Commands::Params { command_id } => {
+            let command = find_command(db, &command_id)?;
+            let parameters = reconcile_parameters(&command.command, &command.parameters);
+            if parameters.is_empty() {
+                println!("Command {} has no parameters", command_id);
+            }
+            for line in format_parameters(&parameters) {
+                println!("{}", line);
+            }
+        }
+        Commands::CompleteIds => {
+            for line in format_completion_ids(&db.list_command_summaries(0, false)?) {
+                println!("{}", line);
+            }
         }
         Commands::ShellInit { shell } => {
             let script_path = crate::shell::hooks::init_shell(shell)?;
@@ -284,7 +924,7 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
             if let Some(command) = db.get_command(command_id)? {
                 // Show the command that will be deleted
                 println!("Deleting command:");
-                print_commands(&[command])?;
+                print_commands(&[command], None)?;
                 
                 // Delete the command
                 db.delete_command(command_id)?;
@@ -293,6 +933,177 @@ pub fn handle_command(command: Commands, db: &mut Database, debug: bool) -> Resu
                 return Err(anyhow!("Command with ID {} not found", command_id));
             }
         }
+        Commands::Dedupe { dry_run: _, apply, ignore_directory } => {
+            let groups = db.find_duplicates(ignore_directory)?;
+            if groups.is_empty() {
+                println!("No duplicate commands found");
+                return Ok(());
+            }
+
+            let mut extra = Vec::new();
+            for group in &groups {
+                let keep = duplicate_to_keep(group);
+                println!("{} ({} copies)", collapse_lines(&group[0].command).bold(), group.len());
+                for cmd in group {
+                    let id = cmd.id.unwrap_or(0);
+                    let marker = if id == keep { "keep  ".green() } else { "delete".red() };
+                    println!("  {} {:>5}  {}  ({})", marker, id, cmd.directory, humanize_ago(cmd.timestamp));
+                    if id != keep {
+                        extra.push(id);
+                    }
+                }
+            }
+
+            let summary = format!(
+                "{} group{}, {} extra command{}",
+                groups.len(),
+                if groups.len() == 1 { "" } else { "s" },
+                extra.len(),
+                if extra.len() == 1 { "" } else { "s" },
+            );
+            if apply {
+                for id in &extra {
+                    db.delete_command(*id)?;
+                }
+                println!("\nDeleted {} ({})", extra.len(), summary);
+            } else {
+                println!("\nFound {}. Run with --apply to delete them.", summary);
+            }
+        }
+        Commands::Rehome { from, to } => {
+            let from = resolve_rehome_directory(&from)?;
+            let to = resolve_rehome_directory(&to)?;
+            let count = db.rehome_directories(&from, &to)?;
+            println!("Moved {} command{} from {} to {}", count, if count == 1 { "" } else { "s" }, from, to);
+        }
+        Commands::Serve { addr, allow_exec } => {
+            let server = Server::bind(&addr, allow_exec)?;
+            if let Some(local_addr) = server.local_addr() {
+                if !local_addr.ip().is_loopback() {
+                    eprintln!("{}", "Warning: server is reachable from other machines".yellow());
+                }
+                println!("Serving command vault on http://{}", local_addr);
+            }
+            if allow_exec {
+                println!("Exec requests need the header {}: {}", crate::server::TOKEN_HEADER, server.token());
+            }
+            server.run(db)?;
+        }
+        Commands::ImportHistory { file, tags, limit, all } => {
+            let contents = std::fs::read(&file)
+                .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+            // zsh may store some characters in its own encoding
+            let history = parse_history(&String::from_utf8_lossy(&contents));
+            let commands = select_commands(history, limit, all);
+
+            // Where a history entry ran isn't recorded
+            let directory = "~";
+            let total = commands.len();
+            let commands: Vec<Command> = commands
+                .into_iter()
+                // History is literal: `user@host` isn't a parameter
+                .map(|command| command.replace('@', "@@"))
+                .filter(|command| check_command_length(command).is_ok())
+                .map(|command| Command {
+                    id: None,
+                    command,
+                    timestamp: Local::now().with_timezone(&Utc),
+                    directory: directory.to_string(),
+                    tags: tags.clone(),
+                    parameters: Vec::new(),
+                    note: None,
+                    alias: None,
+                    shell: None,
+                    interactive: None,
+                    hostname: hostname(),
+                    created_at: None,
+                    confirm_before_run: false,
+                })
+                .collect();
+            // Commands that are already stored are skipped
+            let imported = db.import_commands(&commands)?;
+            let skipped = total - imported;
+            println!("Imported {} command{} ({} skipped)", imported, if imported == 1 { "" } else { "s" }, skipped);
+        }
+        Commands::Clone { command_id } => {
+            let source = db.get_command(command_id)?
+                .ok_or_else(|| anyhow!("Command with ID {} not found", command_id))?;
+
+            let cmd = Command {
+                id: None,
+                timestamp: Local::now().with_timezone(&Utc),
+                // Aliases are unique, so the copy starts without one
+                alias: None,
+                ..source
+            };
+            let id = db.add_copy(&cmd)?;
+            println!("Command cloned with ID: {}", id);
+        }
+        Commands::Lock => {
+            let passphrase = read_passphrase(true)?;
+            db.set_passphrase(Some(&passphrase))?;
+            println!("Database encrypted. Set {} or enter the passphrase to open it.", KEY_ENV_VAR);
+        }
+        Commands::Unlock => {
+            db.set_passphrase(None)?;
+            println!("Database encryption removed");
+        }
+        Commands::Doctor => {
+            let checks = doctor::run_checks(&default_db_path());
+            for check in &checks {
+                println!("{}", doctor::format_check(check));
+            }
+            let problems = checks.iter().filter(|check| check.status != doctor::Status::Ok).count();
+            if problems > 0 {
+                println!("\n{} problem{} found", problems, if problems == 1 { "" } else { "s" });
+            }
+        }
+        Commands::Vacuum => {
+            let (before, after) = db.maintain()?;
+            println!(
+                "Database compacted: {} -> {} (saved {})",
+                format_size(before),
+                format_size(after),
+                format_size(before.saturating_sub(after))
+            );
+        }
     }
     Ok(())
 }
+
+/// Formats a size in bytes for display, e.g. `1.5 MiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Reads the database passphrase from `COMMAND_VAULT_KEY`, or prompts for it.
+/// 
+/// # Arguments
+/// * `confirm` - Whether to ask for the passphrase twice (when setting a new one)
+pub fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(key) = std::env::var(KEY_ENV_VAR) {
+        if !key.is_empty() {
+            return Ok(key);
+        }
+    }
+    if !is_interactive() {
+        return Err(anyhow!("Database passphrase required; set {}", KEY_ENV_VAR));
+    }
+
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme).with_prompt("Database passphrase");
+    if confirm {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases don't match");
+    }
+    Ok(prompt.interact()?)
+}
@@ -0,0 +1,122 @@
+//! `doctor`: checks the environment for the usual causes of trouble.
+
+use std::fs::OpenOptions;
+use std::path::Path;
+use colored::Colorize;
+
+use crate::config;
+use crate::exec::find_shell;
+use crate::shell::hooks::{detect_current_shell, get_shell_integration_script};
+use crate::ui::app::{is_ssh_session, OSC52_CLIPBOARD};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Check {
+        Check { name, status, detail: detail.into() }
+    }
+}
+
+/// Runs every check, for the database at `db_path`.
+pub fn run_checks(db_path: &Path) -> Vec<Check> {
+    let shell = detect_current_shell();
+    vec![
+        check_shell(&shell),
+        check_integration(&shell),
+        check_clipboard(),
+        check_database(db_path),
+        check_sqlite(),
+    ]
+}
+
+/// Formats a check as one line of the checklist, e.g. `[ OK ] Shell: zsh`.
+pub fn format_check(check: &Check) -> String {
+    let label = match check.status {
+        Status::Ok => "[ OK ]".green(),
+        Status::Warn => "[WARN]".yellow(),
+        Status::Fail => "[FAIL]".red(),
+    };
+    format!("{} {}: {}", label, check.name, check.detail)
+}
+
+fn check_shell(shell: &str) -> Check {
+    match std::env::var("SHELL").ok().filter(|s| !s.trim().is_empty()) {
+        Some(path) => Check::new("Shell", Status::Ok, format!("{} ($SHELL={})", shell, path)),
+        None => Check::new("Shell", Status::Warn, format!("$SHELL is not set, assuming {}", shell)),
+    }
+}
+
+fn check_integration(shell: &str) -> Check {
+    let path = match get_shell_integration_script(shell) {
+        Ok(path) => path,
+        Err(e) => return Check::new("Shell integration", Status::Fail, e.to_string()),
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(_) => Check::new("Shell integration", Status::Ok, path.display().to_string()),
+        Err(e) => Check::new("Shell integration", Status::Fail, format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn check_clipboard() -> Check {
+    let configured = config::get().clipboard.as_deref();
+    if configured == Some(OSC52_CLIPBOARD) {
+        return Check::new("Clipboard", Status::Ok, "OSC 52 (set in the config file); needs terminal support");
+    }
+    if configured.is_none() && is_ssh_session() {
+        return Check::new("Clipboard", Status::Ok, "OSC 52 over SSH; needs terminal support");
+    }
+
+    let (program, source) = match configured {
+        Some(command) => (shlex::split(command).and_then(|parts| parts.into_iter().next()), "config file"),
+        None if cfg!(target_os = "macos") => (Some("pbcopy".to_string()), "default"),
+        None if cfg!(target_os = "linux") => (Some("xclip".to_string()), "default"),
+        None => {
+            return Check::new("Clipboard", Status::Warn, "no clipboard tool for this platform; set `clipboard` in the config file");
+        }
+    };
+    match program {
+        Some(program) if find_shell(&program).is_some() => {
+            Check::new("Clipboard", Status::Ok, format!("{} ({})", program, source))
+        }
+        Some(program) => Check::new(
+            "Clipboard",
+            Status::Warn,
+            format!("{} ({}) not found on $PATH; copying will fail", program, source),
+        ),
+        None => Check::new("Clipboard", Status::Fail, "invalid `clipboard` command in the config file"),
+    }
+}
+
+fn check_database(db_path: &Path) -> Check {
+    if !db_path.exists() {
+        return Check::new("Database", Status::Warn, format!("{} does not exist yet", db_path.display()));
+    }
+    match OpenOptions::new().append(true).open(db_path) {
+        Ok(_) => Check::new("Database", Status::Ok, format!("{} (writable)", db_path.display())),
+        Err(e) => Check::new("Database", Status::Fail, format!("{} is not writable: {}", db_path.display(), e)),
+    }
+}
+
+fn check_sqlite() -> Check {
+    let fts5 = rusqlite::Connection::open_in_memory()
+        .and_then(|conn| conn.query_row("SELECT sqlite_compileoption_used('ENABLE_FTS5')", [], |row| row.get::<_, bool>(0)))
+        .unwrap_or(false);
+    if fts5 {
+        Check::new("SQLite", Status::Ok, format!("{} with FTS5", rusqlite::version()))
+    } else {
+        Check::new("SQLite", Status::Warn, format!("{} without FTS5", rusqlite::version()))
+    }
+}
@@ -0,0 +1,211 @@
+//! User configuration file
+//!
+//! Settings are read from `config.toml` in the first available location:
+//! 1. `$COMMAND_VAULT_CONFIG` - full path to the config file
+//! 2. `$XDG_CONFIG_HOME/command-vault/config.toml`
+//! 3. `~/.config/command-vault/config.toml`
+//!
+//! Command line flags win over the file, and the older environment variable
+//! switches (`COMMAND_VAULT_NO_TUI`, ...) keep working alongside it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::args::Commands;
+use crate::ui::keys::KeyBindings;
+
+/// Environment variable that overrides the config file location.
+pub const CONFIG_ENV_VAR: &str = "COMMAND_VAULT_CONFIG";
+
+/// Environment variable with the default for `ls --limit`, taking precedence over `ls_limit`.
+pub const LS_LIMIT_ENV_VAR: &str = "COMMAND_VAULT_LS_LIMIT";
+
+/// Written on first run so the available settings are easy to discover.
+pub const SAMPLE_CONFIG: &str = r#"# command-vault configuration
+# Uncomment a setting to change it. Command line flags take precedence.
+
+# Number of commands `ls` shows (0 for all), like COMMAND_VAULT_LS_LIMIT
+# ls_limit = 50
+
+# Order `ls` lists commands in: "newest", "oldest" or "recent" (last executed)
+# ls_sort = "newest"
+
+# Print plain output instead of starting the TUI (like COMMAND_VAULT_NO_TUI)
+# no_tui = false
+
+# Remember the TUI filter and columns between sessions (like COMMAND_VAULT_REMEMBER_FILTER)
+# remember_filter = false
+
+# Moving down from the last command in the TUI goes to the first, and up from the first to the last
+# wrap_navigation = false
+
+# Command that receives copied text on stdin, instead of pbcopy/xclip, or "osc52" to
+# copy through the terminal (the default over SSH)
+# clipboard = "wl-copy"
+
+# Colored output
+# color = true
+
+# Lowercase tags as they're added, so "Git" and "git" are the same tag
+# lowercase_tags = true
+
+# Run commands in an interactive shell that loads your rc file (~/.zshrc, ~/.bashrc, ...),
+# so aliases and functions work. Turn off if your rc file is slow or prints output
+# load_shell_rc = true
+
+# Only run commands whose directory is inside this one (after resolving `..` and symlinks)
+# allowed_root = "~"
+
+# Keys in the command list. Each action takes a list of single characters or key
+# names: enter, up, down, left, right, tab, space, home, end, pageup, pagedown, delete
+# [keys]
+# next = ["down", "j"]
+# prev = ["up", "k"]
+# delete = ["d"]
+# edit = ["e"]
+# copy = ["c", "y"]
+# execute = ["enter"]
+# filter = ["/"]
+# help = ["?"]
+"#;
+
+/// Order `ls` lists commands in when no flag is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LsSort {
+    Newest,
+    Oldest,
+    Recent,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Default for `ls --limit`
+    pub ls_limit: Option<usize>,
+    /// Default `ls` order
+    pub ls_sort: Option<LsSort>,
+    /// Never start the TUI
+    pub no_tui: bool,
+    /// Remember the TUI filter and columns between sessions
+    pub remember_filter: bool,
+    /// Wrap around at the ends of the TUI list
+    pub wrap_navigation: bool,
+    /// Clipboard command, e.g. `wl-copy`, or `osc52`
+    pub clipboard: Option<String>,
+    /// Whether output is colored
+    pub color: Option<bool>,
+    /// Whether tags are lowercased as they're added
+    pub lowercase_tags: Option<bool>,
+    /// Whether commands run in an interactive shell that loads the user's rc file
+    pub load_shell_rc: Option<bool>,
+    /// Directory that commands must run inside of
+    pub allowed_root: Option<String>,
+    /// Key bindings for the TUI
+    pub keys: KeyBindings,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+impl Config {
+    /// Parses a config file's contents.
+    pub fn parse(contents: &str) -> Result<Config> {
+        let config: Config = toml::from_str(contents)?;
+        config.keys.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config from `path`, using the defaults if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Config> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Config::parse(&contents)
+                .map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Fills in the settings of `command` that weren't given on the command line.
+    pub fn apply_to(&self, command: &mut Commands) {
+        if let Commands::Ls { limit, asc, recent, .. } = command {
+            if limit.is_none() {
+                *limit = self.ls_limit;
+            }
+            // Either flag replaces the configured order
+            if !*asc && !*recent {
+                match self.ls_sort {
+                    Some(LsSort::Oldest) => *asc = true,
+                    Some(LsSort::Recent) => *recent = true,
+                    Some(LsSort::Newest) | None => {}
+                }
+            }
+        }
+    }
+}
+
+/// Fills in the settings of `command` that weren't given on the command line from
+/// the environment. Called before `Config::apply_to`, so the environment wins over the file.
+pub fn apply_env(command: &mut Commands) -> Result<()> {
+    if let Commands::Ls { limit, .. } = command {
+        let value = std::env::var(LS_LIMIT_ENV_VAR).ok().filter(|v| !v.trim().is_empty());
+        if let (None, Some(value)) = (&limit, value) {
+            *limit = Some(value.trim().parse().map_err(|_| {
+                anyhow!("Invalid {}: '{}' (expected a number, 0 for all)", LS_LIMIT_ENV_VAR, value)
+            })?);
+        }
+    }
+    Ok(())
+}
+
+/// Returns where the config file is read from, if a location can be determined.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var(CONFIG_ENV_VAR).ok().filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+    Some(config_dir.join("command-vault").join("config.toml"))
+}
+
+/// Writes the sample config to `path` unless a file is already there.
+pub fn write_sample(path: &Path) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, SAMPLE_CONFIG)?;
+    Ok(())
+}
+
+/// Makes `config` the one returned by `get`. Only the first call has an effect.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// Returns the loaded config, or the defaults if none was loaded (e.g. in tests).
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Whether output should be colored.
+pub fn color_enabled() -> bool {
+    get().color.unwrap_or(true)
+}
+
+/// Whether tags are lowercased as they're added.
+pub fn lowercase_tags() -> bool {
+    get().lowercase_tags.unwrap_or(true)
+}
+
+/// Whether commands run in an interactive shell that loads the user's rc file.
+pub fn load_shell_rc() -> bool {
+    get().load_shell_rc.unwrap_or(true)
+}
@@ -1,4 +1,5 @@
 pub mod models;
+pub mod path;
 pub mod store;
 
 pub use models::Command;
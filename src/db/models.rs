@@ -3,6 +3,7 @@
 //! This module defines the core data structures used throughout the application.
 
 use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
 /// Represents a command with its metadata.
@@ -22,6 +23,13 @@ use serde::{Deserialize, Serialize};
 ///     directory: "/project".to_string(),
 ///     tags: vec!["git".to_string()],
 ///     parameters: vec![],
+///     note: None,
+///     alias: None,
+///     shell: None,
+///     interactive: None,
+///     hostname: None,
+///     created_at: None,
+///     confirm_before_run: false,
 /// };
 /// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,7 +40,7 @@ pub struct Command {
     /// The actual command string
     pub command: String,
     
-    /// When the command was created or last modified
+    /// When the command was added or last edited
     pub timestamp: DateTime<Utc>,
     
     /// Directory where the command should be executed
@@ -45,6 +53,36 @@ pub struct Command {
     /// Parameters that can be substituted in the command
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub parameters: Vec<Parameter>,
+
+    /// Free-form note explaining what the command is for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub note: Option<String>,
+
+    /// Unique short name that `exec` accepts in place of the ID
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub alias: Option<String>,
+
+    /// Shell to execute the command with instead of `$SHELL`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub shell: Option<String>,
+
+    /// `Some(true)` always prompts for parameters, `Some(false)` never does
+    /// (only defaults are filled in); `None` prompts when there are parameters
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub interactive: Option<bool>,
+
+    /// Machine the command was added on
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub hostname: Option<String>,
+
+    /// When the command was first added, while `timestamp` moves with each
+    /// edit. `None` until the command is stored, which sets it to `timestamp`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// Ask before running the command, e.g. for one that deletes things
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub confirm_before_run: bool,
 }
 
 /// Represents a parameter that can be substituted in a command.
@@ -59,6 +97,9 @@ pub struct Command {
 /// let param = Parameter {
 ///     name: "branch".to_string(),
 ///     description: Some("Git branch name".to_string()),
+///     pattern: None,
+///     default_value: None,
+///     secret: false,
 /// };
 /// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -68,6 +109,18 @@ pub struct Parameter {
     
     /// Optional description of what the parameter does
     pub description: Option<String>,
+
+    /// Regex that values must match, given as `@name:/regex/`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pattern: Option<String>,
+
+    /// Value the prompt starts with when no value has been used before
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default_value: Option<String>,
+
+    /// Whether the value is masked while it's typed, given as `@name!` or `@name:description!secret`
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub secret: bool,
 }
 
 impl Parameter {
@@ -75,6 +128,9 @@ impl Parameter {
         Self {
             name,
             description: None,
+            pattern: None,
+            default_value: None,
+            secret: false,
         }
     }
 
@@ -82,6 +138,23 @@ impl Parameter {
         Self {
             name,
             description,
+            pattern: None,
+            default_value: None,
+            secret: false,
         }
     }
 }
+
+/// Order search results are returned in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortOrder {
+    /// Most recently added first
+    #[default]
+    Newest,
+    /// Oldest first
+    Oldest,
+    /// Alphabetically by command text
+    Command,
+    /// Commands where the query appears earliest first
+    Relevance,
+}
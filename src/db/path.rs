@@ -0,0 +1,80 @@
+//! Database location resolution
+//!
+//! The database path is resolved from the first available source:
+//! 1. `$COMMAND_VAULT_DB` - full path to the database file
+//! 2. `$COMMAND_VAULT_DATA_DIR/commands.db`
+//! 3. `$XDG_DATA_HOME/command-vault/commands.db`
+//! 4. The platform data directory (`dirs::data_dir()`)`/command-vault/commands.db`
+//! 5. `./command-vault.db` in the current directory
+//!
+//! Steps 2-4 pick the data directory, which also holds the TUI state.
+//! Empty environment variables are treated as unset.
+
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+
+/// Environment variable that overrides the database location.
+pub const DB_ENV_VAR: &str = "COMMAND_VAULT_DB";
+
+/// Environment variable that overrides the directory the vault keeps its files in.
+pub const DATA_DIR_ENV_VAR: &str = "COMMAND_VAULT_DATA_DIR";
+
+/// Resolves the database path from the environment and platform data directory.
+pub fn default_db_path() -> PathBuf {
+    resolve_db_path(std::env::var(DB_ENV_VAR).ok(), resolve_data_dir().ok())
+}
+
+/// Resolves the directory the vault keeps its files in from the environment
+/// and platform data directory.
+pub fn resolve_data_dir() -> Result<PathBuf> {
+    resolve_data_dir_from(
+        std::env::var(DATA_DIR_ENV_VAR).ok(),
+        std::env::var("XDG_DATA_HOME").ok(),
+        dirs::data_dir(),
+    )
+    .ok_or_else(|| anyhow!("Could not determine a data directory; set {}", DATA_DIR_ENV_VAR))
+}
+
+/// Resolves the data directory from explicitly provided sources.
+///
+/// # Arguments
+/// * `data_dir_env` - Value of `$COMMAND_VAULT_DATA_DIR`, if set; used as-is
+/// * `xdg_data_home` - Value of `$XDG_DATA_HOME`, if set
+/// * `data_dir` - The platform data directory, if one could be determined
+///
+/// # Returns
+/// * `Option<PathBuf>` - The data directory, or `None` if no source is available
+pub fn resolve_data_dir_from(
+    data_dir_env: Option<String>,
+    xdg_data_home: Option<String>,
+    data_dir: Option<PathBuf>,
+) -> Option<PathBuf> {
+    if let Some(dir) = data_dir_env.filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    xdg_data_home
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or(data_dir)
+        .map(|dir| dir.join("command-vault"))
+}
+
+/// Resolves the database path from explicitly provided sources.
+///
+/// # Arguments
+/// * `db_env` - Value of `$COMMAND_VAULT_DB`, if set
+/// * `data_dir` - The resolved data directory (see `resolve_data_dir_from`), if any
+///
+/// # Returns
+/// * `PathBuf` - Path to the database file
+pub fn resolve_db_path(db_env: Option<String>, data_dir: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = db_env.filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+
+    match data_dir {
+        Some(dir) => dir.join("commands.db"),
+        None => PathBuf::from("command-vault.db"),
+    }
+}
@@ -4,12 +4,29 @@
 //! It handles all database operations including CRUD operations for commands,
 //! tag management, and search functionality.
 
+use std::collections::HashMap;
+
 use anyhow::{Result, anyhow};
-use rusqlite::Connection;
-use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use chrono::{DateTime, Utc};
 use serde_json;
+use sha2::{Digest, Sha256};
+
+use super::models::{Command, SortOrder};
+use crate::utils::limits::truncate_output;
+use crate::utils::params::reconcile_parameters;
+use crate::config;
+use crate::utils::tags::{normalize_tag, normalize_tags};
+
+/// Environment variable holding the passphrase of an encrypted database.
+pub const KEY_ENV_VAR: &str = "COMMAND_VAULT_KEY";
+
+/// Header every unencrypted SQLite database file starts with.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
 
-use super::models::Command;
+/// `user_version` of databases whose content hashes cover only the command
+/// and directory; earlier versions also hashed the timestamp.
+const CONTENT_HASH_VERSION: i64 = 1;
 
 /// The main database interface for command-vault.
 /// 
@@ -42,12 +59,102 @@ impl Database {
     /// # Returns
     /// * `Result<Database>` - A new database instance
     pub fn new(path: &str) -> Result<Self> {
+        if Self::is_encrypted(path) {
+            return Err(anyhow!(
+                "Database at {} is encrypted; set {} or enter the passphrase to open it",
+                path, KEY_ENV_VAR
+            ));
+        }
         let conn = Connection::open(path)?;
         let db = Database { conn };
         db.init()?;
         Ok(db)
     }
 
+    /// Checks whether the file at `path` is an encrypted database.
+    /// 
+    /// Plain SQLite files start with a fixed header; SQLCipher files don't.
+    /// Missing or empty files (and `:memory:`) are not encrypted.
+    /// 
+    /// # Arguments
+    /// * `path` - Path to the SQLite database file
+    pub fn is_encrypted(path: &str) -> bool {
+        use std::io::Read;
+
+        let mut header = [0u8; 16];
+        match std::fs::File::open(path) {
+            Ok(mut file) => file.read_exact(&mut header).is_ok() && &header != SQLITE_HEADER,
+            Err(_) => false,
+        }
+    }
+
+    /// Opens (or creates) a database encrypted with the given passphrase.
+    /// 
+    /// # Arguments
+    /// * `path` - Path to the SQLite database file
+    /// * `passphrase` - The passphrase the database is encrypted with
+    /// 
+    /// # Returns
+    /// * `Result<Database>` - A new database instance
+    #[cfg(feature = "encryption")]
+    pub fn open_encrypted(path: &str, passphrase: &str) -> Result<Self> {
+        let conn = Self::open_with_key(path, Some(passphrase))?;
+        let db = Database { conn };
+        db.init()?;
+        Ok(db)
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    pub fn open_encrypted(_path: &str, _passphrase: &str) -> Result<Self> {
+        Err(encryption_unsupported())
+    }
+
+    /// Re-encrypts the database in place, or removes encryption when
+    /// `passphrase` is `None`.
+    /// 
+    /// # Arguments
+    /// * `passphrase` - The new passphrase, or `None` to store the database in plain text
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or failure
+    #[cfg(feature = "encryption")]
+    pub fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<()> {
+        let path = self.conn.path()
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| anyhow!("In-memory databases can't be encrypted"))?
+            .to_string();
+        let tmp = format!("{}.rekey", path);
+        let _ = std::fs::remove_file(&tmp);
+
+        // Copy everything into a fresh file with the new key, then swap it in
+        self.conn.execute("ATTACH DATABASE ?1 AS rekeyed KEY ?2", [tmp.as_str(), passphrase.unwrap_or("")])?;
+        self.conn.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(()))?;
+        self.conn.execute("DETACH DATABASE rekeyed", [])?;
+
+        let old = std::mem::replace(&mut self.conn, Connection::open_in_memory()?);
+        old.close().map_err(|(_, e)| e)?;
+        std::fs::rename(&tmp, &path)?;
+        self.conn = Self::open_with_key(&path, passphrase)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    pub fn set_passphrase(&mut self, _passphrase: Option<&str>) -> Result<()> {
+        Err(encryption_unsupported())
+    }
+
+    #[cfg(feature = "encryption")]
+    fn open_with_key(path: &str, passphrase: Option<&str>) -> Result<Connection> {
+        let conn = Connection::open(path)?;
+        if let Some(passphrase) = passphrase {
+            conn.pragma_update(None, "key", passphrase)?;
+        }
+        // The key is only checked on first read
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|_| anyhow!("Incorrect passphrase for database at {}", path))?;
+        Ok(conn)
+    }
+
     /// Initializes the database schema.
     /// 
     /// Creates the following tables if they don't exist:
@@ -98,37 +205,232 @@ impl Database {
             "CREATE INDEX IF NOT EXISTS idx_tags_name ON tags(name)",
             [],
         )?;
+
+        // Values entered for parameters, so prompts can be pre-filled next time
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS parameter_history (
+                id INTEGER PRIMARY KEY,
+                command_id INTEGER NOT NULL,
+                parameter_name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                used_at TEXT NOT NULL,
+                FOREIGN KEY (command_id) REFERENCES commands(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_parameter_history_lookup
+             ON parameter_history(command_id, parameter_name)",
+            [],
+        )?;
+
+        // Display colors for tags, keyed by name so they outlive the tag itself
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS tag_colors (
+                name TEXT PRIMARY KEY,
+                color TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Columns added after the first release, for databases created before them
+        self.migrate_content_hash()?;
+        self.add_column_if_missing("commands", "note", "TEXT")?;
+        self.add_column_if_missing("commands", "last_run", "TEXT")?;
+        self.migrate_alias()?;
+        self.add_column_if_missing("commands", "last_output", "TEXT")?;
+        self.add_column_if_missing("commands", "shell", "TEXT")?;
+        // NULL means the default behavior for parameter prompts
+        self.add_column_if_missing("commands", "interactive", "INTEGER")?;
+        self.add_column_if_missing("commands", "hostname", "TEXT")?;
+        self.migrate_created_at()?;
+        self.add_column_if_missing("commands", "confirm_before_run", "INTEGER NOT NULL DEFAULT 0")?;
         
         Ok(())
     }
 
+    /// Adds `column` to `table` with the type and constraints in `ddl`, unless
+    /// the table already has it. Returns whether the column was added.
+    fn add_column_if_missing(&self, table: &str, column: &str, ddl: &str) -> Result<bool> {
+        let has_column = self.conn
+            .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+            .exists([table, column])?;
+
+        if !has_column {
+            self.conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, ddl), [])?;
+        }
+        Ok(!has_column)
+    }
+
+    /// Adds the `content_hash` column to databases created before it existed
+    /// and backfills it for existing commands.
+    /// 
+    /// Databases whose hashes still include the timestamp (`user_version` 0)
+    /// are rehashed from the command and directory alone.
+    fn migrate_content_hash(&self) -> Result<()> {
+        let added = self.add_column_if_missing("commands", "content_hash", "TEXT")?;
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        if added || version < CONTENT_HASH_VERSION {
+            let rows = self.conn
+                .prepare("SELECT id, command, directory FROM commands ORDER BY id")?
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            self.conn.execute("UPDATE commands SET content_hash = NULL", [])?;
+            // The oldest of several copies keeps the hash; the rest keep a NULL
+            // one rather than failing the migration
+            for (id, command, directory) in rows {
+                self.conn.execute(
+                    "UPDATE OR IGNORE commands SET content_hash = ?1 WHERE id = ?2",
+                    rusqlite::params![hash_content(&command, &directory), id],
+                )?;
+            }
+            self.conn.execute_batch(&format!("PRAGMA user_version = {}", CONTENT_HASH_VERSION))?;
+        }
+
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_commands_content_hash ON commands(content_hash)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Adds the `alias` column to databases created before aliases existed.
+    fn migrate_alias(&self) -> Result<()> {
+        self.add_column_if_missing("commands", "alias", "TEXT")?;
+
+        // SQLite can't add a UNIQUE column, so uniqueness comes from the index
+        self.conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_commands_alias ON commands(alias)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Adds the `created_at` column to databases created before edits moved
+    /// `timestamp`, and backfills it from `timestamp`.
+    fn migrate_created_at(&self) -> Result<()> {
+        if self.add_column_if_missing("commands", "created_at", "TEXT")? {
+            self.conn.execute("UPDATE commands SET created_at = timestamp WHERE created_at IS NULL", [])?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `alias` is usable as an alias for the command with ID `id`.
+    /// 
+    /// Aliases can't be empty, contain whitespace or be a number like `5` or `-5`
+    /// (which `exec` would read as an ID), and can't already belong to another command.
+    fn check_alias(&self, alias: &str, id: Option<i64>) -> Result<()> {
+        if alias.is_empty() || alias.chars().any(char::is_whitespace) {
+            return Err(anyhow!("Invalid alias '{}': aliases can't be empty or contain whitespace", alias));
+        }
+        if alias.parse::<i64>().is_ok() {
+            return Err(anyhow!("Invalid alias '{}': aliases can't be a number, which would be read as an ID", alias));
+        }
+
+        let owner: Option<i64> = self.conn
+            .query_row("SELECT id FROM commands WHERE alias = ?1", [alias], |row| row.get(0))
+            .optional()?;
+        match owner {
+            Some(owner) if Some(owner) != id => {
+                Err(anyhow!("Alias '{}' is already used by command {}", alias, owner))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Adds a new command to the database.
     /// 
+    /// Parameters are reconciled with the command text, which decides which
+    /// parameters exist (see `reconcile_parameters`).
+    /// 
+    /// Commands are keyed on a content hash of their text and directory, so
+    /// adding the same command again (e.g. re-importing it) is a no-op that
+    /// returns the existing ID. Use `add_copy` to store a deliberate copy.
+    /// 
+    /// Fails if the command's alias is invalid or belongs to another command.
+    /// 
     /// # Arguments
     /// * `command` - The command to add
     /// 
     /// # Returns
-    /// * `Result<i64>` - The ID of the newly added command
+    /// * `Result<i64>` - The ID of the newly added (or already existing) command
     pub fn add_command(&mut self, command: &Command) -> Result<i64> {
+        let content_hash = hash_content(&command.command, &command.directory);
+        self.insert_command(command, Some(content_hash))
+    }
+
+    /// Adds a command even if the same command is already stored in the same
+    /// directory, as `clone` and `add --allow-duplicate` do.
+    /// 
+    /// The copy only gets a content hash if no other command has it yet, so
+    /// imports still skip the command.
+    /// 
+    /// # Arguments
+    /// * `command` - The command to add
+    /// 
+    /// # Returns
+    /// * `Result<i64>` - The ID of the newly added command
+    pub fn add_copy(&mut self, command: &Command) -> Result<i64> {
+        let content_hash = available_hash(&self.conn, &command.command, &command.directory, None)?;
+        self.insert_command(command, content_hash)
+    }
+
+    fn insert_command(&mut self, command: &Command, content_hash: Option<String>) -> Result<i64> {
+        // The command text is the source of truth for its parameters
+        let parameters = reconcile_parameters(&command.command, &command.parameters);
+        let timestamp = command.timestamp.to_rfc3339();
+        let tags = normalize_tags(&command.tags, config::lowercase_tags());
+        if let Some(alias) = &command.alias {
+            // Re-adding the same command may keep its own alias
+            let existing: Option<i64> = self.conn
+                .query_row("SELECT id FROM commands WHERE content_hash = ?1", [&content_hash], |row| row.get(0))
+                .optional()?;
+            self.check_alias(alias, existing)?;
+        }
         let tx = self.conn.transaction()?;
         
         // Insert the command
-        tx.execute(
-            "INSERT INTO commands (command, timestamp, directory, tags, parameters)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO commands (command, timestamp, directory, tags, parameters, content_hash, note, alias, shell, interactive, hostname, created_at, confirm_before_run)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             (
                 &command.command,
-                &command.timestamp.to_rfc3339(),
+                &timestamp,
                 &command.directory,
-                &command.tags.join(","),
-                &serde_json::to_string(&command.parameters)?,
+                &tags.join(","),
+                &serde_json::to_string(&parameters)?,
+                &content_hash,
+                &command.note,
+                &command.alias,
+                &command.shell,
+                &command.interactive,
+                &command.hostname,
+                &command.created_at.unwrap_or(command.timestamp).to_rfc3339(),
+                &command.confirm_before_run,
             ),
         )?;
+
+        if inserted == 0 {
+            let existing_id: i64 = tx.query_row(
+                "SELECT id FROM commands WHERE content_hash = ?1",
+                [&content_hash],
+                |row| row.get(0),
+            )?;
+            return Ok(existing_id);
+        }
         
         let command_id = tx.last_insert_rowid();
         
         // Add tags if present
-        for tag in &command.tags {
+        for tag in &tags {
             // Insert or get tag
             tx.execute(
                 "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
@@ -152,6 +454,102 @@ impl Database {
         Ok(command_id)
     }
 
+    /// Adds a batch of commands, skipping any that are already stored in the
+    /// same directory, so importing the same commands again adds nothing.
+    /// 
+    /// # Arguments
+    /// * `commands` - The commands to import
+    /// 
+    /// # Returns
+    /// * `Result<usize>` - The number of commands that were actually added
+    pub fn import_commands(&mut self, commands: &[Command]) -> Result<usize> {
+        let before = self.count_commands()?;
+        for command in commands {
+            self.add_command(command)?;
+        }
+        Ok((self.count_commands()? - before) as usize)
+    }
+
+    /// Returns how many commands are stored.
+    pub fn count_commands(&self) -> Result<i64> {
+        let count = self.conn.query_row("SELECT COUNT(*) FROM commands", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Compacts the database file and refreshes the query planner's statistics.
+    /// 
+    /// Runs `VACUUM` and `ANALYZE`, which is safe on an empty database too.
+    /// 
+    /// # Returns
+    /// * `Result<(u64, u64)>` - The database size in bytes before and after
+    pub fn maintain(&self) -> Result<(u64, u64)> {
+        let before = self.size_in_bytes()?;
+        self.conn.execute_batch("VACUUM; ANALYZE;")?;
+        let after = self.size_in_bytes()?;
+        Ok((before, after))
+    }
+
+    fn size_in_bytes(&self) -> Result<u64> {
+        let page_count: u64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: u64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(page_count * page_size)
+    }
+
+    /// Records a value entered for a command's parameter.
+    /// 
+    /// # Arguments
+    /// * `command_id` - The ID of the command the parameter belongs to
+    /// * `name` - The parameter name
+    /// * `value` - The value that was entered
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or failure
+    pub fn record_param_value(&mut self, command_id: i64, name: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO parameter_history (command_id, parameter_name, value, used_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![command_id, name, value, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the most recently used value for a command's parameter.
+    /// 
+    /// # Arguments
+    /// * `command_id` - The ID of the command the parameter belongs to
+    /// * `name` - The parameter name
+    /// 
+    /// # Returns
+    /// * `Result<Option<String>>` - The last value used, if any
+    pub fn last_param_value(&self, command_id: i64, name: &str) -> Result<Option<String>> {
+        let value = self.conn.query_row(
+            "SELECT value FROM parameter_history
+             WHERE command_id = ?1 AND parameter_name = ?2
+             ORDER BY used_at DESC, id DESC
+             LIMIT 1",
+            rusqlite::params![command_id, name],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(value)
+    }
+
+    /// Finds a command with exactly the given text and directory.
+    /// 
+    /// # Arguments
+    /// * `command` - The exact command string to look for
+    /// * `directory` - The directory the command is stored with
+    /// 
+    /// # Returns
+    /// * `Result<Option<i64>>` - The ID of the matching command, if any
+    pub fn find_exact(&self, command: &str, directory: &str) -> Result<Option<i64>> {
+        let id = self.conn.query_row(
+            "SELECT id FROM commands WHERE command = ?1 AND directory = ?2 ORDER BY id LIMIT 1",
+            [command, directory],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(id)
+    }
+
     /// Adds tags to an existing command.
     /// 
     /// # Arguments
@@ -189,21 +587,21 @@ impl Database {
             }
         }
         
-        for tag in tags {
+        for tag in normalize_tags(tags, config::lowercase_tags()) {
             // Skip if tag already exists
-            if current_tags.contains(tag) {
+            if current_tags.contains(&tag) {
                 continue;
             }
             
             // Insert or get tag
             tx.execute(
                 "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
-                [tag],
+                [&tag],
             )?;
             
             let tag_id: i64 = tx.query_row(
                 "SELECT id FROM tags WHERE name = ?1",
-                [tag],
+                [&tag],
                 |row| row.get(0),
             )?;
             
@@ -214,7 +612,7 @@ impl Database {
             )?;
             
             // Update tags string in commands table
-            current_tags.push(tag.clone());
+            current_tags.push(tag);
         }
         
         // Update the tags string in the commands table
@@ -237,6 +635,7 @@ impl Database {
     /// * `Result<()>` - Success or failure
     pub fn remove_tag_from_command(&mut self, command_id: i64, tag_name: &str) -> Result<()> {
         let tx = self.conn.transaction()?;
+        let tag_name = stored_tag_name(&tx, tag_name)?;
         
         tx.execute(
             "DELETE FROM command_tags 
@@ -249,86 +648,401 @@ impl Database {
         Ok(())
     }
 
-    /// Searches for commands containing a given query string.
+    /// Adds a tag to many commands in a single transaction.
+    /// 
+    /// # Arguments
+    /// * `ids` - The IDs of the commands to tag
+    /// * `tag` - The tag to add
+    /// 
+    /// # Returns
+    /// * `Result<usize>` - How many commands were tagged (missing or already tagged ones are skipped)
+    pub fn add_tag_to_commands(&mut self, ids: &[i64], tag: &str) -> Result<usize> {
+        let tag = &normalize_tag(tag, config::lowercase_tags()).ok_or_else(|| anyhow!("Tag can't be empty"))?;
+        let tx = self.conn.transaction()?;
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+        let tag_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", [tag], |row| row.get(0))?;
+
+        let mut tagged = 0;
+        for &id in ids {
+            let linked = tx.execute(
+                "INSERT OR IGNORE INTO command_tags (command_id, tag_id)
+                 SELECT id, ?2 FROM commands WHERE id = ?1",
+                rusqlite::params![id, tag_id],
+            )?;
+            if linked == 0 {
+                continue;
+            }
+            tx.execute(
+                "UPDATE commands SET tags = CASE WHEN tags = '' THEN ?1 ELSE tags || ',' || ?1 END WHERE id = ?2",
+                rusqlite::params![tag, id],
+            )?;
+            tagged += 1;
+        }
+
+        // Don't leave an unused tag behind when nothing matched
+        tx.execute(
+            "DELETE FROM tags WHERE id = ?1 AND id NOT IN (SELECT tag_id FROM command_tags)",
+            [tag_id],
+        )?;
+        tx.commit()?;
+        Ok(tagged)
+    }
+
+    /// Removes a tag from many commands in a single transaction.
+    /// 
+    /// # Arguments
+    /// * `ids` - The IDs of the commands to untag
+    /// * `tag` - The tag to remove
+    /// 
+    /// # Returns
+    /// * `Result<usize>` - How many commands had the tag removed
+    pub fn remove_tag_from_commands(&mut self, ids: &[i64], tag: &str) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let tag = stored_tag_name(&tx, tag)?;
+        let tag = tag.as_str();
+
+        let mut untagged = 0;
+        for &id in ids {
+            let unlinked = tx.execute(
+                "DELETE FROM command_tags
+                 WHERE command_id = ?1
+                 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+                rusqlite::params![id, tag],
+            )?;
+            if unlinked == 0 {
+                continue;
+            }
+            let tags: String = tx.query_row("SELECT tags FROM commands WHERE id = ?1", [id], |row| row.get(0))?;
+            let remaining: Vec<&str> = tags.split(',').filter(|t| !t.is_empty() && *t != tag).collect();
+            tx.execute(
+                "UPDATE commands SET tags = ?1 WHERE id = ?2",
+                rusqlite::params![remaining.join(","), id],
+            )?;
+            untagged += 1;
+        }
+
+        tx.execute(
+            "DELETE FROM tags WHERE name = ?1 AND id NOT IN (SELECT tag_id FROM command_tags)",
+            [tag],
+        )?;
+        tx.commit()?;
+        Ok(untagged)
+    }
+
+    /// Folds one tag into another in a single transaction: every command tagged
+    /// `from` is tagged `into` instead, and `from` is deleted.
+    /// 
+    /// # Arguments
+    /// * `from` - The tag to fold away
+    /// * `into` - The tag to keep, created if it doesn't exist yet
+    /// 
+    /// # Returns
+    /// * `Result<usize>` - How many commands were tagged `from`
+    pub fn merge_tags(&mut self, from: &str, into: &str) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let from = stored_tag_name(&tx, from)?;
+        let into = normalize_tag(into, config::lowercase_tags()).ok_or_else(|| anyhow!("Tag can't be empty"))?;
+        let (from, into) = (from.as_str(), into.as_str());
+        if from == into {
+            return Err(anyhow!("Cannot merge tag '{}' into itself", from));
+        }
+        let from_id: i64 = tx
+            .query_row("SELECT id FROM tags WHERE name = ?1", [from], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| anyhow!("Tag not found: {}", from))?;
+        tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [into])?;
+        let into_id: i64 = tx.query_row("SELECT id FROM tags WHERE name = ?1", [into], |row| row.get(0))?;
+
+        let ids: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT command_id FROM command_tags WHERE tag_id = ?1")?;
+            let ids = stmt.query_map([from_id], |row| row.get(0))?;
+            ids.collect::<rusqlite::Result<_>>()?
+        };
+        for &id in &ids {
+            // Commands that already had both tags keep a single link
+            tx.execute(
+                "INSERT OR IGNORE INTO command_tags (command_id, tag_id) VALUES (?1, ?2)",
+                rusqlite::params![id, into_id],
+            )?;
+            let tags: String = tx.query_row("SELECT tags FROM commands WHERE id = ?1", [id], |row| row.get(0))?;
+            let mut merged = Vec::new();
+            for tag in tags.split(',').filter(|t| !t.is_empty()) {
+                let tag = if tag == from { into } else { tag };
+                if !merged.contains(&tag) {
+                    merged.push(tag);
+                }
+            }
+            tx.execute(
+                "UPDATE commands SET tags = ?1 WHERE id = ?2",
+                rusqlite::params![merged.join(","), id],
+            )?;
+        }
+
+        tx.execute("DELETE FROM command_tags WHERE tag_id = ?1", [from_id])?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", [from_id])?;
+        tx.commit()?;
+        Ok(ids.len())
+    }
+
+    /// Rewrites the directory of every command stored in `from`, or a directory
+    /// below it, to the same place under `to`, in a single transaction.
+    /// 
+    /// Whole path components are matched, so `/src/app` doesn't move `/src/apple`.
+    /// 
+    /// # Arguments
+    /// * `from` - The directory to move commands out of
+    /// * `to` - The directory that replaces it
+    /// 
+    /// # Returns
+    /// * `Result<usize>` - How many commands were moved
+    pub fn rehome_directories(&mut self, from: &str, to: &str) -> Result<usize> {
+        if from.is_empty() || to.is_empty() {
+            return Err(anyhow!("Directories can't be empty"));
+        }
+        // Without the trailing slash `from` itself matches too; `/` becomes the empty prefix
+        let from = from.trim_end_matches('/');
+        let to = to.trim_end_matches('/');
+        if from == to {
+            return Err(anyhow!("Cannot rehome a directory to itself"));
+        }
+
+        let tx = self.conn.transaction()?;
+        let rows: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare("SELECT id, command, directory FROM commands")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut moved = 0;
+        for (id, command, directory) in rows {
+            let Some(rest) = directory.strip_prefix(from) else { continue };
+            if !rest.is_empty() && !rest.starts_with('/') {
+                continue;
+            }
+            let rehomed = format!("{}{}", to, rest);
+            let rehomed = if rehomed.is_empty() { "/".to_string() } else { rehomed };
+            // The directory is part of the hash that keeps out duplicates
+            let content_hash = available_hash(&tx, &command, &rehomed, Some(id))?;
+            tx.execute(
+                "UPDATE commands SET directory = ?1, content_hash = ?2 WHERE id = ?3",
+                rusqlite::params![rehomed, content_hash, id],
+            )?;
+            moved += 1;
+        }
+
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    /// Builds the SQL and bound parameters used by `search_commands`.
     /// 
     /// # Arguments
     /// * `query` - The query string to search for
-    /// * `limit` - The maximum number of results to return
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn search_commands_query(query: &str, limit: usize) -> (String, Vec<String>) {
+        Self::search_commands_sorted_query(query, limit, SortOrder::Newest, false)
+    }
+
+    /// Builds the SQL and bound parameters used by `search_commands_sorted`.
+    /// 
+    /// # Arguments
+    /// * `query` - The query string to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// * `order` - The order to return results in
+    /// * `case_sensitive` - Whether to match case exactly
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn search_commands_sorted_query(query: &str, limit: usize, order: SortOrder, case_sensitive: bool) -> (String, Vec<String>) {
+        // GLOB is case-sensitive, unlike LIKE; the query's wildcards are escaped so they match literally
+        let (condition, mut params) = if case_sensitive {
+            ("c.command GLOB '*' || ?1 || '*' OR c.note GLOB '*' || ?1 || '*'", vec![glob_escape(query)])
+        } else {
+            ("c.command LIKE '%' || ?1 || '%' OR c.note LIKE '%' || ?1 || '%'", vec![query.to_string()])
+        };
+        let order_by = match order {
+            SortOrder::Newest => "c.timestamp DESC".to_string(),
+            SortOrder::Oldest => "c.timestamp ASC".to_string(),
+            SortOrder::Command => "c.command COLLATE NOCASE ASC, c.timestamp DESC".to_string(),
+            SortOrder::Relevance => {
+                params.push(query.to_string());
+                let position = if case_sensitive {
+                    "instr(c.command, ?2)"
+                } else {
+                    "instr(lower(c.command), lower(?2))"
+                };
+                // Commands that only match through their note come last
+                format!("{0} = 0, {0}, c.timestamp DESC", position)
+            }
+        };
+        let mut sql = format!("SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c
+             WHERE {}
+             ORDER BY {}", condition, order_by);
+
+        if limit != 0 {
+            params.push(limit.to_string());
+            sql.push_str(&format!("\n             LIMIT ?{}", params.len()));
+        }
+        (sql, params)
+    }
+
+    /// Searches for commands whose text or note contains a given query string.
+    /// 
+    /// # Arguments
+    /// * `query` - The query string to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
     /// 
     /// # Returns
     /// * `Result<Vec<Command>>` - A list of matching commands
     pub fn search_commands(&self, query: &str, limit: usize) -> Result<Vec<Command>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
-             FROM commands c
-             WHERE c.command LIKE '%' || ?1 || '%'
-             ORDER BY c.timestamp DESC
-             LIMIT ?2"
-        )?;
+        let (sql, params) = Self::search_commands_query(query, limit);
+        self.query_commands(&sql, params)
+    }
 
-        let mut rows = stmt.query([query, &limit.to_string()])?;
-        let mut commands = Vec::new();
+    /// Builds the SQL and bound parameters used by `search_commands_cased`.
+    /// 
+    /// # Arguments
+    /// * `query` - The query string to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn search_commands_cased_query(query: &str, limit: usize) -> (String, Vec<String>) {
+        Self::search_commands_sorted_query(query, limit, SortOrder::Newest, true)
+    }
 
-        while let Some(row) = rows.next()? {
-            let id: i64 = row.get(0)?;
-            commands.push(Command {
-                id: Some(id),
-                command: row.get(1)?,
-                timestamp: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)?
-                    .with_timezone(&Utc),
-                directory: row.get(3)?,
-                tags: row.get::<_, String>(4)?
-                    .split(',')
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string())
-                    .collect(),
-                parameters: serde_json::from_str(&row.get::<_, String>(5)?)?,
-            });
-        }
+    /// Searches for commands whose text or note contains a given query string,
+    /// matching case exactly.
+    /// 
+    /// # Arguments
+    /// * `query` - The query string to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Command>>` - A list of matching commands
+    pub fn search_commands_cased(&self, query: &str, limit: usize) -> Result<Vec<Command>> {
+        let (sql, params) = Self::search_commands_cased_query(query, limit);
+        self.query_commands(&sql, params)
+    }
 
-        Ok(commands)
+    /// Searches for commands whose text or note contains a given query string,
+    /// in the given order.
+    /// 
+    /// # Arguments
+    /// * `query` - The query string to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// * `order` - The order to return results in
+    /// * `case_sensitive` - Whether to match case exactly
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Command>>` - A list of matching commands
+    pub fn search_commands_sorted(&self, query: &str, limit: usize, order: SortOrder, case_sensitive: bool) -> Result<Vec<Command>> {
+        let (sql, params) = Self::search_commands_sorted_query(query, limit, order, case_sensitive);
+        self.query_commands(&sql, params)
+    }
+
+    /// Builds the SQL and bound parameters used by `search_by_tag`.
+    /// 
+    /// # Arguments
+    /// * `tag` - The tag to search for
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn search_by_tag_query(tag: &str, limit: usize) -> (String, Vec<String>) {
+        let mut sql = "SELECT DISTINCT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c
+             JOIN command_tags ct ON ct.command_id = c.id
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE t.name = ?1
+             ORDER BY c.timestamp DESC".to_string();
+        let mut params = vec![tag.to_string()];
+        if limit != 0 {
+            sql.push_str("\n             LIMIT ?2");
+            params.push(limit.to_string());
+        }
+        (sql, params)
     }
 
     /// Searches for commands with a given tag.
     /// 
     /// # Arguments
     /// * `tag` - The tag to search for
-    /// * `limit` - The maximum number of results to return
+    /// * `limit` - The maximum number of results to return (0 for no limit)
     /// 
     /// # Returns
     /// * `Result<Vec<Command>>` - A list of matching commands
     pub fn search_by_tag(&self, tag: &str, limit: usize) -> Result<Vec<Command>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT DISTINCT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
+        let tag = stored_tag_name(&self.conn, tag)?;
+        let (sql, params) = Self::search_by_tag_query(&tag, limit);
+        self.query_commands(&sql, params)
+    }
+
+    /// Builds the SQL and bound parameters used by `search_by_tags`.
+    /// 
+    /// # Arguments
+    /// * `tags` - The tags to search for; duplicates are ignored
+    /// * `match_all` - Whether a command must have every tag rather than any of them
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn search_by_tags_query(tags: &[String], match_all: bool, limit: usize) -> (String, Vec<String>) {
+        let mut tags: Vec<&String> = tags.iter().collect();
+        tags.sort();
+        tags.dedup();
+        let placeholders = (1..=tags.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let having = if match_all {
+            format!("HAVING COUNT(DISTINCT t.name) = {}", tags.len())
+        } else {
+            String::new()
+        };
+        let mut sql = format!(
+            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
              FROM commands c
              JOIN command_tags ct ON ct.command_id = c.id
              JOIN tags t ON t.id = ct.tag_id
-             WHERE t.name = ?1
-             ORDER BY c.timestamp DESC
-             LIMIT ?2"
-        )?;
-
-        let mut rows = stmt.query([tag, &limit.to_string()])?;
-        let mut commands = Vec::new();
+             WHERE t.name IN ({})
+             GROUP BY c.id
+             {}
+             ORDER BY c.timestamp DESC",
+            placeholders,
+            having
+        );
 
-        while let Some(row) = rows.next()? {
-            let id: i64 = row.get(0)?;
-            commands.push(Command {
-                id: Some(id),
-                command: row.get(1)?,
-                timestamp: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)?
-                    .with_timezone(&Utc),
-                directory: row.get(3)?,
-                tags: row.get::<_, String>(4)?
-                    .split(',')
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_string())
-                    .collect(),
-                parameters: serde_json::from_str(&row.get::<_, String>(5)?)?,
-            });
+        let mut params: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+        if limit != 0 {
+            sql.push_str(&format!("\n             LIMIT ?{}", tags.len() + 1));
+            params.push(limit.to_string());
         }
+        (sql, params)
+    }
 
-        Ok(commands)
+    /// Searches for commands with any or all of the given tags.
+    /// 
+    /// # Arguments
+    /// * `tags` - The tags to search for
+    /// * `match_all` - Whether a command must have every tag rather than any of them
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Command>>` - A list of matching commands
+    pub fn search_by_tags(&self, tags: &[String], match_all: bool, limit: usize) -> Result<Vec<Command>> {
+        if tags.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tags = tags.iter()
+            .map(|tag| stored_tag_name(&self.conn, tag))
+            .collect::<Result<Vec<_>>>()?;
+        let (sql, params) = Self::search_by_tags_query(&tags, match_all, limit);
+        self.query_commands(&sql, params)
     }
 
     /// Lists all tags in the database.
@@ -352,6 +1066,81 @@ impl Database {
         Ok(tags)
     }
 
+    /// Like `list_tags`, but only returns tags starting with `prefix`.
+    pub fn list_tags_with_prefix(&self, prefix: &str) -> Result<Vec<(String, i64)>> {
+        // `%` and `_` in the prefix are matched literally
+        let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let mut stmt = self.conn.prepare(
+            "SELECT t.name, COUNT(ct.command_id) as count
+             FROM tags t
+             LEFT JOIN command_tags ct ON ct.tag_id = t.id
+             WHERE t.name LIKE ? || '%' ESCAPE '\\'
+             GROUP BY t.id, t.name
+             ORDER BY count DESC, t.name"
+        )?;
+
+        let tags = stmt.query_map([escaped], |row| {
+            Ok((row.get::<_, String>(0)?, row.get(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    /// Sets the color a tag is displayed in.
+    /// 
+    /// # Arguments
+    /// * `tag` - The tag to color
+    /// * `color` - The color name, e.g. `red` or `#ff8800`
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or failure
+    pub fn set_tag_color(&mut self, tag: &str, color: &str) -> Result<()> {
+        let tag = stored_tag_name(&self.conn, tag)?;
+        self.conn.execute(
+            "INSERT INTO tag_colors (name, color) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET color = excluded.color",
+            [tag.as_str(), color],
+        )?;
+        Ok(())
+    }
+
+    /// Lists the colors set for tags.
+    /// 
+    /// # Returns
+    /// * `Result<HashMap<String, String>>` - Color names keyed by tag
+    pub fn tag_colors(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT name, color FROM tag_colors")?;
+        let colors = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?;
+        Ok(colors)
+    }
+
+    /// Builds the SQL and bound parameters used by `list_commands`.
+    /// 
+    /// # Arguments
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// * `ascending` - Whether to return results in ascending order
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn list_commands_query(limit: usize, ascending: bool) -> (String, Vec<String>) {
+        let order = if ascending { "ASC" } else { "DESC" };
+        let mut sql = format!(
+            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c
+             ORDER BY c.timestamp {}",
+            order
+        );
+
+        if limit == 0 {
+            (sql, Vec::new())
+        } else {
+            sql.push_str("\n             LIMIT ?1");
+            (sql, vec![limit.to_string()])
+        }
+    }
+
     /// Lists all commands in the database.
     /// 
     /// # Arguments
@@ -361,37 +1150,159 @@ impl Database {
     /// # Returns
     /// * `Result<Vec<Command>>` - A list of commands
     pub fn list_commands(&self, limit: usize, ascending: bool) -> Result<Vec<Command>> {
-        let query = if ascending {
-            if limit == 0 {
-                "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
-                 FROM commands c
-                 ORDER BY c.timestamp ASC"
-            } else {
-                "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
-                 FROM commands c
-                 ORDER BY c.timestamp ASC
-                 LIMIT ?1"
-            }
-        } else {
-            if limit == 0 {
-                "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
-                 FROM commands c
-                 ORDER BY c.timestamp DESC"
-            } else {
-                "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters 
-                 FROM commands c
-                 ORDER BY c.timestamp DESC
-                 LIMIT ?1"
-            }
-        };
+        let (sql, params) = Self::list_commands_query(limit, ascending);
+        self.query_commands(&sql, params)
+    }
+
+    /// Builds the SQL and bound parameters used by `list_commands_paged`.
+    /// 
+    /// # Arguments
+    /// * `after_id` - Only list commands with a greater ID
+    /// * `before_id` - Only list commands with a smaller ID
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn list_commands_paged_query(after_id: Option<i64>, before_id: Option<i64>, limit: usize) -> (String, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(id) = after_id {
+            params.push(id.to_string());
+            conditions.push(format!("c.id > ?{}", params.len()));
+        }
+        if let Some(id) = before_id {
+            params.push(id.to_string());
+            conditions.push(format!("c.id < ?{}", params.len()));
+        }
 
-        let mut stmt = self.conn.prepare(query)?;
-        let mut rows = if limit == 0 {
-            stmt.query([])?
+        let mut sql = String::from(
+            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c"
+        );
+        if !conditions.is_empty() {
+            sql.push_str(&format!("\n             WHERE {}", conditions.join(" AND ")));
+        }
+        // Walking backwards from `before_id` alone takes the commands just below it
+        let order = if before_id.is_some() && after_id.is_none() { "DESC" } else { "ASC" };
+        sql.push_str(&format!("\n             ORDER BY c.id {}", order));
+
+        if limit != 0 {
+            params.push(limit.to_string());
+            sql.push_str(&format!("\n             LIMIT ?{}", params.len()));
+        }
+        (sql, params)
+    }
+
+    /// Lists commands by ID, for walking through a large vault page by page.
+    /// 
+    /// Unlike `LIMIT`/`OFFSET`, a page starts from the last ID seen, so it is
+    /// cheap however deep it goes and stays stable while commands are added
+    /// or deleted. Results are in ascending ID order, except when only
+    /// `before_id` is given: then they run downwards from it, so the next
+    /// page starts before the last ID returned.
+    /// 
+    /// # Arguments
+    /// * `after_id` - Only list commands with a greater ID
+    /// * `before_id` - Only list commands with a smaller ID
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Command>>` - A list of commands
+    pub fn list_commands_paged(&self, after_id: Option<i64>, before_id: Option<i64>, limit: usize) -> Result<Vec<Command>> {
+        let (sql, params) = Self::list_commands_paged_query(after_id, before_id, limit);
+        self.query_commands(&sql, params)
+    }
+
+    /// Records that a command was just executed.
+    /// 
+    /// # Arguments
+    /// * `id` - The ID of the command that was run
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or an error
+    pub fn record_run(&mut self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE commands SET last_run = ?1 WHERE id = ?2",
+            rusqlite::params![Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Saves the output of a command's latest execution, replacing the previous one.
+    /// 
+    /// Output longer than `MAX_OUTPUT_LENGTH` is truncated so it can't bloat the database.
+    /// 
+    /// # Arguments
+    /// * `id` - The ID of the command that was run
+    /// * `output` - What the command printed to stdout
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or an error
+    pub fn record_output(&mut self, id: i64, output: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE commands SET last_output = ?1 WHERE id = ?2",
+            rusqlite::params![truncate_output(output), id],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the output saved from a command's latest execution.
+    /// 
+    /// # Arguments
+    /// * `id` - The ID of the command
+    /// 
+    /// # Returns
+    /// * `Result<Option<String>>` - The saved output, if any
+    pub fn last_output(&self, id: i64) -> Result<Option<String>> {
+        let output = self.conn
+            .query_row("SELECT last_output FROM commands WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        Ok(output.flatten())
+    }
+
+    /// Builds the SQL and bound parameters used by `list_by_last_run`.
+    /// 
+    /// # Arguments
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `(String, Vec<String>)` - The SQL query and its parameters, in order
+    pub fn list_by_last_run_query(limit: usize) -> (String, Vec<String>) {
+        let mut sql = String::from(
+            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c
+             ORDER BY c.last_run DESC NULLS LAST, c.timestamp DESC"
+        );
+
+        if limit == 0 {
+            (sql, Vec::new())
         } else {
-            stmt.query([limit])?
-        };
-        
+            sql.push_str("\n             LIMIT ?1");
+            (sql, vec![limit.to_string()])
+        }
+    }
+
+    /// Lists commands, most recently executed first.
+    /// 
+    /// Commands that have never been run come after the ones that have,
+    /// newest first.
+    /// 
+    /// # Arguments
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Command>>` - A list of commands
+    pub fn list_by_last_run(&self, limit: usize) -> Result<Vec<Command>> {
+        let (sql, params) = Self::list_by_last_run_query(limit);
+        self.query_commands(&sql, params)
+    }
+
+    /// Runs a query selecting the usual command columns, in order, and
+    /// collects the rows.
+    fn query_commands(&self, sql: &str, params: Vec<String>) -> Result<Vec<Command>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+
         let mut commands = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -408,12 +1319,47 @@ impl Database {
                     .map(|s| s.to_string())
                     .collect(),
                 parameters: serde_json::from_str(&row.get::<_, String>(5)?)?,
+                note: row.get(6)?,
+                alias: row.get(7)?,
+                shell: row.get(8)?,
+                interactive: row.get(9)?,
+                hostname: row.get(10)?,
+                created_at: parse_created_at(row.get(11)?)?,
+                confirm_before_run: row.get(12)?,
             });
         }
 
         Ok(commands)
     }
 
+    /// Lists only the ID and text of each command.
+    /// 
+    /// Lighter than `list_commands` for views that don't need the other columns.
+    /// 
+    /// # Arguments
+    /// * `limit` - The maximum number of results to return (0 for no limit)
+    /// * `ascending` - Whether to return results in ascending order
+    /// 
+    /// # Returns
+    /// * `Result<Vec<(i64, String)>>` - A list of (id, command) pairs
+    pub fn list_command_summaries(&self, limit: usize, ascending: bool) -> Result<Vec<(i64, String)>> {
+        let order = if ascending { "ASC" } else { "DESC" };
+        let mut sql = format!("SELECT id, command FROM commands ORDER BY timestamp {}", order);
+        let mut params = Vec::new();
+        if limit != 0 {
+            sql.push_str(" LIMIT ?1");
+            params.push(limit.to_string());
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let summaries = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(summaries)
+    }
+
     /// Gets a command by its ID.
     /// 
     /// # Arguments
@@ -424,7 +1370,7 @@ impl Database {
     pub fn get_command(&self, id: i64) -> Result<Option<Command>> {
         // First get the command details
         let mut stmt = self.conn.prepare(
-            "SELECT command, timestamp, directory, parameters 
+            "SELECT command, timestamp, directory, parameters, note, alias, shell, interactive, hostname, created_at, confirm_before_run 
              FROM commands 
              WHERE id = ?1"
         )?;
@@ -435,10 +1381,17 @@ impl Database {
                 row.get::<_, String>(1)?,
                 row.get::<_, String>(2)?,
                 row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<bool>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, bool>(10)?,
             ))
         });
 
-        if let Ok((command, timestamp, directory, parameters)) = command {
+        if let Ok((command, timestamp, directory, parameters, note, alias, shell, interactive, hostname, created_at, confirm_before_run)) = command {
             // Then get the tags
             let mut stmt = self.conn.prepare(
                 "SELECT t.name 
@@ -461,14 +1414,41 @@ impl Database {
                 directory,
                 tags,
                 parameters: serde_json::from_str(&parameters)?,
+                note,
+                alias,
+                shell,
+                interactive,
+                hostname,
+                created_at: parse_created_at(created_at)?,
+                confirm_before_run,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Gets a command by its alias.
+    /// 
+    /// # Arguments
+    /// * `alias` - The alias of the command to get
+    /// 
+    /// # Returns
+    /// * `Result<Option<Command>>` - The command if found, None if not found
+    pub fn get_command_by_alias(&self, alias: &str) -> Result<Option<Command>> {
+        let id: Option<i64> = self.conn
+            .query_row("SELECT id FROM commands WHERE alias = ?1", [alias], |row| row.get(0))
+            .optional()?;
+        match id {
+            Some(id) => self.get_command(id),
+            None => Ok(None),
+        }
+    }
+
     /// Updates an existing command.
     /// 
+    /// As with `add_command`, parameters are reconciled with the command text
+    /// so the two can never disagree.
+    /// 
     /// # Arguments
     /// * `command` - The updated command
     /// 
@@ -478,8 +1458,14 @@ impl Database {
         if command.id.is_none() {
             return Err(anyhow!("Cannot update command without id"));
         }
+        if let Some(alias) = &command.alias {
+            self.check_alias(alias, command.id)?;
+        }
+        let tags = normalize_tags(&command.tags, config::lowercase_tags());
 
         let tx = self.conn.transaction()?;
+        // Editing a command into a copy of another keeps both
+        let content_hash = available_hash(&tx, &command.command, &command.directory, command.id)?;
         
         // Update command
         tx.execute(
@@ -488,14 +1474,28 @@ impl Database {
                  timestamp = ?2,
                  directory = ?3,
                  tags = ?4,
-                 parameters = ?5
-             WHERE id = ?6",
+                 parameters = ?5,
+                 content_hash = ?6,
+                 note = ?7,
+                 alias = ?8,
+                 shell = ?9,
+                 interactive = ?10,
+                 hostname = ?11,
+                 confirm_before_run = ?12
+             WHERE id = ?13",
             rusqlite::params![
                 command.command,
                 command.timestamp.to_rfc3339(),
                 command.directory,
-                command.tags.join(","),
-                serde_json::to_string(&command.parameters)?,
+                tags.join(","),
+                serde_json::to_string(&reconcile_parameters(&command.command, &command.parameters))?,
+                content_hash,
+                command.note,
+                command.alias,
+                command.shell,
+                command.interactive,
+                command.hostname,
+                command.confirm_before_run,
                 command.id.unwrap()
             ],
         )?;
@@ -507,7 +1507,7 @@ impl Database {
         )?;
 
         // Add new tags using the same transaction
-        for tag in &command.tags {
+        for tag in &tags {
             // Insert or get tag
             tx.execute(
                 "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
@@ -531,6 +1531,37 @@ impl Database {
         Ok(())
     }
 
+    /// Finds commands that are stored more than once.
+    /// 
+    /// # Arguments
+    /// * `ignore_directory` - Whether the same command in different directories counts as a duplicate
+    /// 
+    /// # Returns
+    /// * `Result<Vec<Vec<Command>>>` - Groups of identical commands, newest first within each group
+    pub fn find_duplicates(&self, ignore_directory: bool) -> Result<Vec<Vec<Command>>> {
+        let same_directory = if ignore_directory { "" } else { " AND d.directory = c.directory" };
+        let sql = format!(
+            "SELECT c.id, c.command, c.timestamp, c.directory, c.tags, c.parameters, c.note, c.alias, c.shell, c.interactive, c.hostname, c.created_at, c.confirm_before_run 
+             FROM commands c
+             WHERE EXISTS (SELECT 1 FROM commands d WHERE d.command = c.command{} AND d.id != c.id)
+             ORDER BY c.command, {}c.timestamp DESC, c.id DESC",
+            same_directory,
+            if ignore_directory { "" } else { "c.directory, " },
+        );
+
+        let mut groups: Vec<Vec<Command>> = Vec::new();
+        for command in self.query_commands(&sql, Vec::new())? {
+            let same_group = groups.last().and_then(|group| group.first()).is_some_and(|first| {
+                first.command == command.command && (ignore_directory || first.directory == command.directory)
+            });
+            match groups.last_mut() {
+                Some(group) if same_group => group.push(command),
+                _ => groups.push(vec![command]),
+            }
+        }
+        Ok(groups)
+    }
+
     /// Deletes a command by its ID.
     /// 
     /// # Arguments
@@ -547,6 +1578,11 @@ impl Database {
             [command_id],
         )?;
 
+        tx.execute(
+            "DELETE FROM parameter_history WHERE command_id = ?",
+            [command_id],
+        )?;
+
         // Then delete from commands
         let rows_affected = tx.execute(
             "DELETE FROM commands WHERE id = ?",
@@ -567,3 +1603,65 @@ impl Database {
         Ok(())
     }
 }
+
+/// Parses the `created_at` column, which is only NULL for rows written by an
+/// older version after the migration ran.
+fn parse_created_at(value: Option<String>) -> Result<Option<DateTime<Utc>>> {
+    Ok(match value {
+        Some(value) => Some(DateTime::parse_from_rfc3339(&value)?.with_timezone(&Utc)),
+        None => None,
+    })
+}
+
+/// The stored name a tag argument refers to: normalized the way tags are when
+/// they're added, so `Git` finds `git`. A tag stored with exactly the given name,
+/// e.g. one added before tags were normalized, is used as-is.
+fn stored_tag_name(conn: &Connection, tag: &str) -> Result<String> {
+    let tag = tag.trim();
+    let exact: Option<String> = conn
+        .query_row("SELECT name FROM tags WHERE name = ?1", [tag], |row| row.get(0))
+        .optional()?;
+    match exact {
+        Some(name) => Ok(name),
+        None => normalize_tag(tag, config::lowercase_tags()).ok_or_else(|| anyhow!("Tag can't be empty")),
+    }
+}
+
+/// Computes the stable content hash used to de-duplicate commands.
+fn hash_content(command: &str, directory: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [command, directory] {
+        hasher.update(part.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") hash differently
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Returns the content hash for `command` in `directory`, or `None` if another
+/// command than `id` already has it, which makes this one a deliberate copy.
+fn available_hash(conn: &Connection, command: &str, directory: &str, id: Option<i64>) -> Result<Option<String>> {
+    let content_hash = hash_content(command, directory);
+    let owner: Option<i64> = conn
+        .query_row("SELECT id FROM commands WHERE content_hash = ?1", [&content_hash], |row| row.get(0))
+        .optional()?;
+    Ok(match owner {
+        Some(owner) if Some(owner) != id => None,
+        _ => Some(content_hash),
+    })
+}
+
+/// Escapes GLOB wildcards so `text` only matches itself.
+fn glob_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '*' | '?' | '[' => format!("[{}]", c),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encryption_unsupported() -> anyhow::Error {
+    anyhow!("command-vault was built without encryption support; rebuild with `--features encryption`")
+}
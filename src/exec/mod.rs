@@ -1,92 +1,172 @@
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::io;
 use std::process::Command as ProcessCommand;
 use std::env;
 use std::path::{Path, PathBuf};
 use anyhow::Result;
 use crossterm::terminal;
-use dialoguer::{theme::ColorfulTheme, Input};
+use serde::Serialize;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use crate::db::models::Command;
 use crate::shell::hooks::detect_current_shell;
+use crate::utils::params::{fill_defaults, fill_parameters, reconcile_parameters, SECRET_MASK};
+
+/// Shell used when `$SHELL` is unset, empty or doesn't exist.
+const FALLBACK_SHELL: &str = "/bin/sh";
+
+/// Share of invalid UTF-8 bytes above which output is treated as binary.
+const BINARY_INVALID_RATIO: f64 = 0.1;
 
 pub struct ExecutionContext {
     pub command: String,
     pub directory: String,
     pub test_mode: bool,
     pub debug_mode: bool,
+    /// Shell to run the command with instead of `$SHELL`
+    pub shell: Option<String>,
 }
 
 pub fn wrap_command(command: &str, test_mode: bool) -> String {
     if test_mode {
         command.to_string()
     } else {
-        // For interactive mode, handle shell initialization
-        let shell_type = detect_current_shell();
-        let clean_command = command.trim_matches('"').to_string();
-            
-        match shell_type.as_str() {
-            "zsh" => format!(
-                r#"setopt no_global_rcs; if [ -f ~/.zshrc ]; then ZDOTDIR=~ source ~/.zshrc; fi; {}"#,
-                clean_command
-            ),
-            "fish" => format!(
-                r#"if test -f ~/.config/fish/config.fish; source ~/.config/fish/config.fish 2>/dev/null; end; {}"#,
-                clean_command
-            ),
-            "bash" | _ => format!(
-                r#"if [ -f ~/.bashrc ]; then . ~/.bashrc >/dev/null 2>&1; fi; if [ -f ~/.bash_profile ]; then . ~/.bash_profile >/dev/null 2>&1; fi; {}"#,
-                clean_command
-            ),
+        wrap_for_shell(command, &detect_current_shell())
+    }
+}
+
+/// Wraps a command so the given kind of shell loads the user's rc file first.
+fn wrap_for_shell(command: &str, shell_type: &str) -> String {
+    // For interactive mode, handle shell initialization
+    let clean_command = command.trim_matches('"').to_string();
+
+    match shell_type {
+        "zsh" => format!(
+            r#"setopt no_global_rcs; if [ -f ~/.zshrc ]; then ZDOTDIR=~ source ~/.zshrc; fi; {}"#,
+            clean_command
+        ),
+        "fish" => format!(
+            r#"if test -f ~/.config/fish/config.fish; source ~/.config/fish/config.fish 2>/dev/null; end; {}"#,
+            clean_command
+        ),
+        _ => format!(
+            r#"if [ -f ~/.bashrc ]; then . ~/.bashrc >/dev/null 2>&1; fi; if [ -f ~/.bash_profile ]; then . ~/.bash_profile >/dev/null 2>&1; fi; {}"#,
+            clean_command
+        ),
+    }
+}
+
+/// Finds a shell given by path (`/bin/bash`) or by name on `$PATH` (`bash`).
+pub fn find_shell(shell: &str) -> Option<PathBuf> {
+    let path = Path::new(shell);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    env::var_os("PATH")
+        .and_then(|paths| env::split_paths(&paths).map(|dir| dir.join(shell)).find(|p| p.is_file()))
+}
+
+/// The kind of shell at `shell_path`, e.g. `zsh` for `/usr/bin/zsh`.
+fn shell_type(shell_path: &Path) -> String {
+    shell_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Expands a leading `~` and any `$VAR` or `${VAR}` in a stored directory.
+/// 
+/// Variables that aren't set are left as written, so the error for a
+/// missing directory still shows what was stored.
+pub fn resolve_directory(directory: &str) -> PathBuf {
+    let expanded = expand_env_vars(directory);
+
+    if expanded == "~" {
+        if let Some(home) = dirs::home_dir() {
+            return home;
+        }
+    } else if let Some(rest) = expanded.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
         }
     }
+    PathBuf::from(expanded)
 }
 
-fn is_path_traversal_attempt(command: &str, working_dir: &Path) -> bool {
-    // Check if the command contains path traversal attempts
-    if command.contains("..") {
-        // Get the absolute path of the working directory
-        if let Ok(working_dir) = working_dir.canonicalize() {
-            // Try to resolve any path in the command relative to working_dir
-            let potential_path = working_dir.join(command);
-            if let Ok(resolved_path) = potential_path.canonicalize() {
-                // Check if the resolved path is outside the working directory
-                return !resolved_path.starts_with(working_dir);
+fn expand_env_vars(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        result.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
             }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => result.push_str(&value),
+            _ => result.push_str(&rest[pos..pos + 1 + consumed]),
         }
-        // If we can't resolve the paths, assume it's a traversal attempt
-        return true;
+        rest = &after[consumed..];
     }
-    false
+    result.push_str(rest);
+    result
 }
 
-pub fn execute_shell_command(ctx: &ExecutionContext) -> Result<()> {
-    // Get the current shell
-    let shell = if cfg!(windows) {
-        String::from("cmd.exe")
-    } else {
-        env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"))
+/// Whether `working_dir` lies outside `root` once `..` and symlinks are resolved.
+///
+/// Only the directory is checked: `..` in the command itself (`git log a..b`,
+/// `cd ..`) is ordinary shell usage. Without a root every directory is allowed.
+pub fn is_path_traversal_attempt(working_dir: &Path, root: Option<&Path>) -> bool {
+    let Some(root) = root else {
+        return false;
     };
-
-    // Wrap the command for shell execution
-    let wrapped_command = wrap_command(&ctx.command, ctx.test_mode);
-
-    // Check for directory traversal attempts
-    if is_path_traversal_attempt(&wrapped_command, Path::new(&ctx.directory)) {
-        return Err(anyhow::anyhow!("Directory traversal attempt detected"));
+    match (working_dir.canonicalize(), root.canonicalize()) {
+        (Ok(working_dir), Ok(root)) => !working_dir.starts_with(root),
+        // If we can't resolve the paths, assume it's a traversal attempt
+        _ => true,
     }
+}
 
-    // Create command with the appropriate shell
-    let mut command = ProcessCommand::new(&shell);
-    
-    // In test mode, use simple shell execution
-    if ctx.test_mode {
-        command.args(&["-c", &wrapped_command]);
-    } else {
-        // Use -i for all shells in interactive mode to ensure proper initialization
-        command.args(&["-i", "-c", &wrapped_command]);
+/// Whether command output looks like binary data rather than text: it has
+/// NUL bytes, or more than a tenth of it isn't valid UTF-8.
+/// 
+/// A few invalid bytes (e.g. Latin-1 file names) still count as text and
+/// are printed lossily.
+pub fn looks_binary(output: &[u8]) -> bool {
+    if output.contains(&0) {
+        return true;
     }
-    
-    // Set working directory
-    command.current_dir(&ctx.directory);
+    let invalid: usize = output.utf8_chunks().map(|chunk| chunk.invalid().len()).sum();
+    invalid as f64 > output.len() as f64 * BINARY_INVALID_RATIO
+}
+
+/// What a command run by `capture_shell_command` did, as printed by `exec --capture`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutionResult {
+    pub command: String,
+    /// `None` if the command was killed by a signal
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs a command in the user's shell, printing its output.
+/// 
+/// Returns the command's stdout so callers can keep it (see `exec --save-output`).
+/// Binary stdout (see `looks_binary`) is neither printed nor returned; a
+/// warning with its size is shown instead.
+pub fn execute_shell_command(ctx: &ExecutionContext) -> Result<String> {
+    let mut command = shell_command(ctx)?;
 
     if ctx.debug_mode {
         println!("Full command: {:?}", command);
@@ -105,7 +185,7 @@ pub fn execute_shell_command(ctx: &ExecutionContext) -> Result<()> {
     }
 
     // Execute the command and capture output
-    let output = command.output()?;
+    let output = run_output(&mut command)?;
 
     // Handle command output
     if !output.status.success() {
@@ -117,9 +197,20 @@ pub fn execute_shell_command(ctx: &ExecutionContext) -> Result<()> {
         ));
     }
 
+    if looks_binary(&output.stdout) {
+        eprintln!(
+            "Warning: output appears to be binary ({} bytes), not printed or saved",
+            output.stdout.len()
+        );
+        if !output.stderr.is_empty() {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        }
+        return Ok(String::new());
+    }
+
     // Print stdout
-    if !output.stdout.is_empty() {
-        let stdout_str = String::from_utf8_lossy(&output.stdout);
+    let stdout_str = String::from_utf8_lossy(&output.stdout).to_string();
+    if !stdout_str.is_empty() {
         print!("{}", stdout_str);
     }
 
@@ -129,16 +220,173 @@ pub fn execute_shell_command(ctx: &ExecutionContext) -> Result<()> {
         eprint!("{}", stderr_str);
     }
 
-    Ok(())
+    Ok(stdout_str)
+}
+
+/// Runs a command in the user's shell without printing anything, returning
+/// its exit code and output.
+/// 
+/// A command that fails is not an error here; its exit code says so.
+pub fn capture_shell_command(ctx: &ExecutionContext) -> Result<ExecutionResult> {
+    let mut command = shell_command(ctx)?;
+    if ctx.debug_mode {
+        eprintln!("Full command: {:?}", command);
+    }
+
+    let output = run_output(&mut command)?;
+    Ok(ExecutionResult {
+        command: ctx.command.clone(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+/// Runs `command` to completion, naming the shell if it couldn't be started.
+fn run_output(command: &mut ProcessCommand) -> Result<std::process::Output> {
+    command.output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => anyhow::anyhow!(
+            "Shell not found: {} (use --shell to pick another)",
+            command.get_program().to_string_lossy()
+        ),
+        _ => e.into(),
+    })
+}
+
+/// Builds the process that runs `ctx.command` in the user's shell.
+fn shell_command(ctx: &ExecutionContext) -> Result<ProcessCommand> {
+    // Get the current shell
+    let default_shell = if cfg!(windows) {
+        String::from("cmd.exe")
+    } else {
+        env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.trim().is_empty())
+            .unwrap_or_else(|| String::from(FALLBACK_SHELL))
+    };
+
+    // A requested shell that isn't installed falls back to the default one
+    let shell = match ctx.shell.as_deref() {
+        Some(requested) => match find_shell(requested) {
+            Some(path) => Some(path),
+            None => {
+                eprintln!("Warning: shell '{}' not found, using {}", requested, default_shell);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // A `$SHELL` that no longer exists (e.g. after uninstalling zsh) falls back
+    // to sh, and the command is wrapped for sh rather than the missing shell
+    let shell = match shell {
+        None if !cfg!(windows) && find_shell(&default_shell).is_none() => {
+            eprintln!("Warning: shell '{}' from $SHELL not found, using {}", default_shell, FALLBACK_SHELL);
+            Some(PathBuf::from(FALLBACK_SHELL))
+        }
+        shell => shell,
+    };
+
+    // Wrap the command so the shell loads the user's rc file, for aliases and functions
+    let load_rc = !ctx.test_mode && crate::config::load_shell_rc();
+    let (shell, wrapped_command) = match shell {
+        Some(path) if load_rc => {
+            let wrapped = wrap_for_shell(&ctx.command, &shell_type(&path));
+            (path.to_string_lossy().to_string(), wrapped)
+        }
+        Some(path) => (path.to_string_lossy().to_string(), ctx.command.clone()),
+        None => (default_shell, wrap_command(&ctx.command, !load_rc)),
+    };
+
+    let working_dir = resolve_directory(&ctx.directory);
+
+    // Check for directory traversal attempts
+    let root = crate::config::get().allowed_root.as_deref().map(resolve_directory);
+    if is_path_traversal_attempt(&working_dir, root.as_deref()) {
+        return Err(anyhow::anyhow!(
+            "Directory traversal attempt detected: {} is outside {}",
+            working_dir.display(),
+            root.unwrap_or_default().display()
+        ));
+    }
+
+    // Create command with the appropriate shell
+    let mut command = ProcessCommand::new(&shell);
+    
+    if load_rc {
+        // Use -i for all shells in interactive mode to ensure proper initialization
+        command.args(["-i", "-c", &wrapped_command]);
+    } else {
+        command.args(["-c", &wrapped_command]);
+    }
+    
+    // Set working directory
+    command.current_dir(&working_dir);
+    Ok(command)
+}
+
+/// A ready-to-run `command-vault exec` line for a stored command, e.g. for docs or chat.
+/// 
+/// Parameters are asked for when it runs; they're listed in a trailing shell
+/// comment so readers know what to have at hand.
+pub fn exec_invocation(command: &Command) -> String {
+    let invocation = format!("command-vault exec {}", command.id.unwrap_or_default());
+    let names: Vec<String> = reconcile_parameters(&command.command, &command.parameters)
+        .into_iter()
+        .map(|param| param.name)
+        .collect();
+    if names.is_empty() {
+        invocation
+    } else {
+        format!("{}  # prompts for: {}", invocation, names.join(", "))
+    }
+}
+
+/// What to do about a command whose directory no longer exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingDirectory {
+    /// Run in the current directory instead
+    UseCurrent,
+    /// Recreate the directory
+    Create,
+    Cancel,
+}
+
+/// Asks what to do about a command directory that no longer exists,
+/// suggesting the current directory.
+pub fn prompt_missing_directory(directory: &str) -> Result<MissingDirectory> {
+    let choices = [
+        format!("Run in the current directory ({})", env::current_dir()?.display()),
+        format!("Create {}", directory),
+        "Cancel".to_string(),
+    ];
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Directory {} no longer exists", directory))
+        .items(&choices)
+        .default(0)
+        .interact()?;
+    Ok(match choice {
+        0 => MissingDirectory::UseCurrent,
+        1 => MissingDirectory::Create,
+        _ => MissingDirectory::Cancel,
+    })
+}
+
+/// Asks whether to run `command`, for commands saved with `--confirm`. Defaults to no.
+pub fn confirm_run(command: &str) -> Result<bool> {
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Run `{}`?", command))
+        .default(false)
+        .interact()?)
 }
 
 pub fn execute_command(command: &Command) -> Result<()> {
     let test_mode = std::env::var("COMMAND_VAULT_TEST").is_ok();
     let debug_mode = std::env::var("COMMAND_VAULT_DEBUG").is_ok();
-    let mut final_command = command.command.clone();
+    let mut values = HashMap::new();
 
     // If command has parameters, prompt for values first
-    if !command.parameters.is_empty() {
+    if !command.parameters.is_empty() && command.interactive != Some(false) {
         for param in &command.parameters {
             println!("Parameter: {}", param.name);
             println!();
@@ -163,23 +411,39 @@ pub fn execute_command(command: &Command) -> Result<()> {
                 }
             };
 
-            final_command = final_command.replace(&format!("@{}", param.name), &value);
+            values.entry(param.name.clone()).or_insert(value);
         }
     }
+    let final_command = if command.interactive == Some(false) {
+        fill_defaults(&command.command, &command.parameters)
+    } else {
+        fill_parameters(&command.command, &values)
+    };
+    let mut masked = values.clone();
+    for param in command.parameters.iter().filter(|p| p.secret && values.contains_key(&p.name)) {
+        masked.insert(param.name.clone(), SECRET_MASK.to_string());
+    }
+    let display = if command.interactive == Some(false) {
+        final_command.clone()
+    } else {
+        fill_parameters(&command.command, &masked)
+    };
 
     let ctx = ExecutionContext {
         command: final_command,
         directory: command.directory.clone(),
         test_mode,
         debug_mode,
+        shell: command.shell.clone(),
     };
 
     // Print command details only once
     println!("─────────────────────────────────────────────");
     println!();
-    println!("Command to execute: {}", ctx.command);
+    println!("Command to execute: {}", display);
     println!("Working directory: {}", ctx.directory);
     println!();
 
-    execute_shell_command(&ctx)
+    execute_shell_command(&ctx)?;
+    Ok(())
 }
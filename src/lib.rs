@@ -1,9 +1,11 @@
 pub mod cli;
+pub mod config;
 pub mod db;
 pub mod shell;
 pub mod ui;
 pub mod utils;
 pub mod exec;
+pub mod server;
 pub mod version;
 
 pub use db::Database;
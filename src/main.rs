@@ -1,36 +1,76 @@
 use anyhow::Result;
 use clap::Parser;
 use command_vault::{
-    cli::{args::Cli, commands::handle_command},
-    db::store::Database,
+    cli::{args::{Cli, Commands}, commands::{handle_command, read_passphrase}},
+    config::{self, config_path, write_sample, Config},
+    db::{path::default_db_path, store::{Database, KEY_ENV_VAR}},
+    shell::hooks::detect_current_shell,
 };
-use std::path::PathBuf;
-
-mod cli;
-mod db;
-mod shell;
-mod ui;
-mod utils;
-mod exec;
 
 fn main() -> Result<()> {
+    let mut args = Cli::parse();
+    config::apply_env(&mut args.command)?;
+
+    if let Some(path) = config_path() {
+        // Best effort: a read-only home directory shouldn't stop anything from working
+        let _ = write_sample(&path);
+        let config = Config::load(&path)?;
+        config.apply_to(&mut args.command);
+        config::init(config);
+    }
+
     // Enable colors globally
-    colored::control::set_override(true);
+    colored::control::set_override(config::color_enabled());
     
-    let args = Cli::parse();
+    let db_path = default_db_path();
+    if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    if args.verbose {
+        eprintln!("Using database at {}", db_path.display());
+        // Goes to stderr so `source "$(command-vault shell-init)"` keeps working
+        if let Commands::ShellInit { shell: None } = &args.command {
+            eprintln!("Detected shell: {}", detect_current_shell());
+        }
+    }
     
-    let data_dir = dirs::data_dir()
-        .ok_or_else(|| anyhow::anyhow!("Could not find data directory"))?
-        .join("command-vault");
-    std::fs::create_dir_all(&data_dir)?;
-    
-    let db_path = data_dir.join("commands.db");
-    let mut db = Database::new(db_path.to_str().unwrap())?;
+    let db_path = db_path.to_str()
+        .ok_or_else(|| anyhow::anyhow!("Database path is not valid UTF-8: {}", db_path.display()))?;
+
+    // Completion scripts run in the background, so never block them on a passphrase prompt
+    if matches!(args.command, Commands::CompleteIds)
+        && Database::is_encrypted(db_path)
+        && std::env::var(KEY_ENV_VAR).is_err()
+    {
+        return Ok(());
+    }
+    let mut db = open_database(db_path, args.encrypt)?;
     
     let result = handle_command(args.command, &mut db, args.debug);
     
     // Re-enable colors before exiting
-    colored::control::set_override(true);
+    colored::control::set_override(config::color_enabled());
     
     result
 }
+
+/// Opens the database, asking for the passphrase if it is encrypted.
+fn open_database(path: &str, encrypt: bool) -> Result<Database> {
+    if Database::is_encrypted(path) {
+        let passphrase = read_passphrase(false)?;
+        return Database::open_encrypted(path, &passphrase);
+    }
+
+    if encrypt {
+        let is_new = std::fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+        if !is_new {
+            return Err(anyhow::anyhow!(
+                "Database at {} already exists; use `command-vault lock` to encrypt it", path
+            ));
+        }
+        let passphrase = read_passphrase(true)?;
+        return Database::open_encrypted(path, &passphrase);
+    }
+
+    Database::new(path)
+}
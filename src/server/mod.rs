@@ -0,0 +1,280 @@
+//! Local HTTP server for command-vault
+//!
+//! Exposes the vault as a small JSON API so editor and IDE extensions can
+//! integrate with it. The server binds to localhost by default and only
+//! serves read endpoints unless execution is explicitly enabled.
+//!
+//! Endpoints:
+//! - `GET /commands` - List commands (optional `limit` query parameter)
+//! - `GET /commands/{id}` - Get a single command
+//! - `GET /search?q=<query>` - Search commands (optional `limit`)
+//! - `POST /commands/{id}/exec` - Execute a command (only with `allow_exec`).
+//!   Parameters take their default values, and commands saved with `--confirm`
+//!   also need `?confirm=true`
+//!
+//! Any web page can make the browser send requests to localhost, so the `Host`
+//! header must name the address the server is bound to (which stops DNS
+//! rebinding), requests from a browser must come from that same origin, and
+//! executing a command needs the token printed when the server starts, in the
+//! `X-Command-Vault-Token` header.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::db::Database;
+use crate::exec::{execute_shell_command, ExecutionContext};
+use crate::utils::params::substitute_parameters_unattended;
+
+/// Default address the server binds to.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+/// Default number of results returned by list and search endpoints.
+const DEFAULT_LIMIT: usize = 50;
+
+/// Header that carries the session token on exec requests.
+pub const TOKEN_HEADER: &str = "X-Command-Vault-Token";
+
+/// HTTP server backed by a command-vault database.
+pub struct Server {
+    http: tiny_http::Server,
+    allow_exec: bool,
+    token: String,
+}
+
+impl Server {
+    /// Binds the server to the given address.
+    ///
+    /// # Arguments
+    /// * `addr` - Address to listen on, e.g. `127.0.0.1:7878`
+    /// * `allow_exec` - Whether the execute endpoint is enabled
+    pub fn bind(addr: &str, allow_exec: bool) -> Result<Self> {
+        let http = tiny_http::Server::http(addr)
+            .map_err(|e| anyhow!("Failed to bind server to {}: {}", addr, e))?;
+        Ok(Server { http, allow_exec, token: generate_token() })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Returns the token exec requests must send in the `X-Command-Vault-Token`
+    /// header. A new one is generated each time the server starts.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Serves requests until the process is terminated.
+    pub fn run(&self, db: &Database) -> Result<()> {
+        loop {
+            self.handle_next(db)?;
+        }
+    }
+
+    /// Blocks until the next request arrives and responds to it.
+    pub fn handle_next(&self, db: &Database) -> Result<()> {
+        let request = self.http.recv()?;
+        self.respond(request, db)
+    }
+
+    fn respond(&self, request: Request, db: &Database) -> Result<()> {
+        let (status, body) = match self.check_request(&request) {
+            Ok(()) => route(db, request.method(), request.url(), self.allow_exec),
+            Err((status, message)) => (status, json!({ "error": message })),
+        };
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .map_err(|_| anyhow!("Invalid content type header"))?;
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        request.respond(response)?;
+        Ok(())
+    }
+
+    /// Rejects requests for another host or from another origin, and exec
+    /// requests without the session token.
+    fn check_request(&self, request: &Request) -> std::result::Result<(), (u16, String)> {
+        let header = |name: &'static str| {
+            request.headers().iter()
+                .find(|h| h.field.equiv(name))
+                .map(|h| h.value.as_str().to_string())
+        };
+        let addr = self.local_addr().ok_or((500, "Server is not bound to an IP address".to_string()))?;
+
+        let host = header("Host").unwrap_or_default();
+        if !is_allowed_host(&host, addr) {
+            return Err((403, format!("Host '{}' is not allowed", host)));
+        }
+        if let Some(origin) = header("Origin") {
+            let same_origin = origin.strip_prefix("http://").is_some_and(|host| is_allowed_host(host, addr));
+            if !same_origin {
+                return Err((403, format!("Cross-origin requests from '{}' are not allowed", origin)));
+            }
+        }
+
+        let path = request.url().split('?').next().unwrap_or_default();
+        let is_exec = *request.method() == Method::Post && path.trim_end_matches('/').ends_with("/exec");
+        if is_exec && self.allow_exec && header(TOKEN_HEADER).as_deref() != Some(self.token.as_str()) {
+            return Err((403, format!("Missing or wrong {} header; use the token printed at startup", TOKEN_HEADER)));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a `Host` header names `addr`. Only IP addresses (and `localhost`,
+/// for a loopback address) are accepted, since a domain name could be made to
+/// point anywhere.
+fn is_allowed_host(host: &str, addr: SocketAddr) -> bool {
+    let Some((name, port)) = host.rsplit_once(':') else {
+        return false;
+    };
+    if port.parse() != Ok(addr.port()) {
+        return false;
+    }
+    if name == "localhost" {
+        return addr.ip().is_loopback();
+    }
+    match name.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => addr.ip().is_unspecified() || ip == addr.ip(),
+        Err(_) => false,
+    }
+}
+
+/// Generates a random token. Hashers built by `RandomState` are keyed with
+/// randomness from the operating system.
+fn generate_token() -> String {
+    (0..2u64)
+        .map(|i| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Routes a request to the matching endpoint and returns the status code and JSON body.
+pub fn route(db: &Database, method: &Method, url: &str, allow_exec: bool) -> (u16, Value) {
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (url, ""),
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (method, segments.as_slice()) {
+        (Method::Get, ["commands"]) => list_commands(db, query),
+        (Method::Get, ["commands", id]) => get_command(db, id),
+        (Method::Get, ["search"]) => search_commands(db, query),
+        (Method::Post, ["commands", id, "exec"]) if allow_exec => exec_command(db, id, query),
+        (Method::Post, ["commands", _, "exec"]) => {
+            Err((403, "Command execution is disabled; start the server with --allow-exec".to_string()))
+        }
+        _ => Err((404, format!("No route for {} {}", method, path))),
+    };
+
+    match result {
+        Ok(body) => (200, body),
+        Err((status, message)) => (status, json!({ "error": message })),
+    }
+}
+
+type RouteResult = std::result::Result<Value, (u16, String)>;
+
+fn list_commands(db: &Database, query: &str) -> RouteResult {
+    let limit = parse_limit(query)?;
+    let commands = db.list_commands(limit, false).map_err(internal_error)?;
+    serde_json::to_value(commands).map_err(|e| internal_error(e.into()))
+}
+
+fn get_command(db: &Database, id: &str) -> RouteResult {
+    let id = parse_id(id)?;
+    match db.get_command(id).map_err(internal_error)? {
+        Some(command) => serde_json::to_value(command).map_err(|e| internal_error(e.into())),
+        None => Err((404, format!("Command not found with ID: {}", id))),
+    }
+}
+
+fn search_commands(db: &Database, query: &str) -> RouteResult {
+    let q = query_param(query, "q").ok_or((400, "Missing required query parameter: q".to_string()))?;
+    let limit = parse_limit(query)?;
+    let commands = db.search_commands(&q, limit).map_err(internal_error)?;
+    serde_json::to_value(commands).map_err(|e| internal_error(e.into()))
+}
+
+fn exec_command(db: &Database, id: &str, query: &str) -> RouteResult {
+    let id = parse_id(id)?;
+    let command = db.get_command(id).map_err(internal_error)?
+        .ok_or((404, format!("Command not found with ID: {}", id)))?;
+
+    // The equivalent of `exec --yes`: the caller has to ask for it explicitly
+    if command.confirm_before_run && query_param(query, "confirm").as_deref() != Some("true") {
+        return Err((403, format!("Command {} asks for confirmation before running; pass confirm=true to run it", id)));
+    }
+
+    // Parameters need interactive input, which the server cannot provide, so only defaults are used
+    let substitution = substitute_parameters_unattended(&command).map_err(|e| (400, e.to_string()))?;
+
+    // Run non-interactively: there is no terminal to hand over to the command
+    let ctx = ExecutionContext {
+        command: substitution.command,
+        directory: command.directory.clone(),
+        test_mode: true,
+        debug_mode: false,
+        shell: command.shell.clone(),
+    };
+    execute_shell_command(&ctx).map_err(internal_error)?;
+    Ok(json!({ "id": id, "status": "ok" }))
+}
+
+fn parse_id(id: &str) -> std::result::Result<i64, (u16, String)> {
+    id.parse().map_err(|_| (400, format!("Invalid command ID: {}", id)))
+}
+
+fn parse_limit(query: &str) -> std::result::Result<usize, (u16, String)> {
+    match query_param(query, "limit") {
+        Some(limit) => limit.parse().map_err(|_| (400, format!("Invalid limit: {}", limit))),
+        None => Ok(DEFAULT_LIMIT),
+    }
+}
+
+fn internal_error(e: anyhow::Error) -> (u16, String) {
+    (500, e.to_string())
+}
+
+/// Returns the percent-decoded value of a query string parameter.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -30,6 +30,20 @@ pub fn get_fish_integration_path() -> PathBuf {
     path
 }
 
+/// Get the path to the Xonsh integration script
+pub fn get_xonsh_integration_path() -> PathBuf {
+    let mut path = get_shell_integration_dir();
+    path.push("xonsh-integration.xsh");
+    path
+}
+
+/// Get the path to the Elvish integration script
+pub fn get_elvish_integration_path() -> PathBuf {
+    let mut path = get_shell_integration_dir();
+    path.push("elvish-integration.elv");
+    path
+}
+
 /// Detect the current shell from environment variables
 pub fn detect_current_shell() -> String {
     // First check for FISH_VERSION environment variable (highest priority)
@@ -47,6 +61,12 @@ pub fn detect_current_shell() -> String {
         }
         
         // Then check for other shells
+        if shell_path.contains("xonsh") {
+            return "xonsh".to_string();
+        }
+        if shell_path.contains("elvish") {
+            return "elvish".to_string();
+        }
         if shell_path.contains("zsh") {
             return "zsh".to_string();
         }
@@ -66,6 +86,8 @@ pub fn get_shell_integration_script(shell: &str) -> Result<PathBuf> {
         "zsh" => Ok(get_zsh_integration_path()),
         "bash" => Ok(get_bash_integration_path()),
         "fish" => Ok(get_fish_integration_path()),
+        "xonsh" => Ok(get_xonsh_integration_path()),
+        "elvish" => Ok(get_elvish_integration_path()),
         _ => Err(anyhow!("Unsupported shell: {}", shell)),
     }
 }
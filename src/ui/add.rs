@@ -1,20 +1,32 @@
 use std::io::Stdout;
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers, KeyEvent},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyModifiers, KeyEvent},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, Paragraph, Clear},
     Terminal,
 };
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
-/// Type alias for the command result tuple
-pub type CommandResult = Option<(String, Vec<String>, Option<i32>)>;
+use crate::db::models::Parameter;
+use crate::utils::params::parse_parameters;
+use crate::utils::prompt::strip_prompt_marker;
+use crate::utils::tags::{merge_tags, normalize_tag};
+use crate::ui::display::highlight_segments;
+use crate::ui::panic::PanicHookGuard;
+
+/// Type alias for the command result tuple: command, tags, directory
+pub type CommandResult = Option<(String, Vec<String>, String, Option<i32>)>;
+
+/// How many tag suggestions are shown while typing a tag.
+const MAX_TAG_SUGGESTIONS: usize = 5;
 
 #[derive(Default)]
 pub struct AddCommandApp {
@@ -24,16 +36,26 @@ pub struct AddCommandApp {
     pub tags: Vec<String>,
     /// Current tag being entered
     pub current_tag: String,
-    /// Current cursor position in the command
+    /// Working directory the command runs in
+    pub directory: String,
+    /// Parameters detected in the command (read-only preview)
+    pub parameters: Vec<Parameter>,
+    /// Current cursor position in the command, in characters (not bytes)
     pub command_cursor: usize,
     /// Current line in multi-line command
     pub command_line: usize,
     /// Current input mode
     pub input_mode: InputMode,
-    /// Suggested tags
+    /// Tags suggested from the command's content
     pub suggested_tags: Vec<String>,
+    /// Tags already in the vault, most used first, to complete the tag being typed
+    pub known_tags: Vec<String>,
+    /// Suggestion highlighted with Tab, as an index into `tag_suggestions()`
+    pub tag_selection: Option<usize>,
     /// Previous input mode (for returning from help)
     pub previous_mode: InputMode,
+    /// Text being searched for in the command, while Ctrl+F find is open
+    pub find_query: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -41,6 +63,7 @@ pub enum InputMode {
     #[default]
     Command,
     Tag,
+    Directory,
     Confirm,
     Help,
 }
@@ -51,6 +74,7 @@ impl AddCommandApp {
     }
 
     pub fn run(&mut self) -> Result<CommandResult> {
+        let _panic_guard = PanicHookGuard::install();
         let mut terminal = setup_terminal()?;
         let result = self.run_app(&mut terminal);
         restore_terminal(&mut terminal)?;
@@ -61,7 +85,14 @@ impl AddCommandApp {
         loop {
             terminal.draw(|f| self.ui(f))?;
 
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Paste(text) = &event {
+                self.handle_paste(text);
+            }
+            if let Event::Key(key) = event {
+                if self.handle_find_key(&key) {
+                    continue;
+                }
                 match self.input_mode {
                     InputMode::Help => match key.code {
                         KeyCode::Char('?') | KeyCode::Esc => {
@@ -81,78 +112,73 @@ impl AddCommandApp {
                                 KeyCode::Enter => {
                                     if key.modifiers.contains(KeyModifiers::SHIFT) {
                                         // Add newline to command
-                                        self.command.insert(self.command_cursor, '\n');
+                                        self.command.insert(self.byte_index(self.command_cursor), '\n');
                                         self.command_cursor += 1;
                                         self.command_line += 1;
-                                    } else {
-                                        if !self.command.is_empty() {
-                                            self.suggest_tags();
-                                            self.input_mode = InputMode::Tag;
-                                        }
+                                    } else if !self.command.is_empty() {
+                                        self.finish_command_input();
                                     }
                                 }
                                 KeyCode::Char(c) => {
-                                    self.command.insert(self.command_cursor, c);
+                                    self.command.insert(self.byte_index(self.command_cursor), c);
                                     self.command_cursor += 1;
                                 }
-                                KeyCode::Backspace => {
-                                    if self.command_cursor > 0 {
-                                        self.command.remove(self.command_cursor - 1);
-                                        self.command_cursor -= 1;
-                                        if self.command_cursor > 0 && self.command.chars().nth(self.command_cursor - 1) == Some('\n') {
-                                            self.command_line -= 1;
-                                        }
+                                KeyCode::Backspace if self.command_cursor > 0 => {
+                                    self.command.remove(self.byte_index(self.command_cursor - 1));
+                                    self.command_cursor -= 1;
+                                    if self.command_cursor > 0 && self.command.chars().nth(self.command_cursor - 1) == Some('\n') {
+                                        self.command_line -= 1;
                                     }
                                 }
-                                KeyCode::Left => {
-                                    if self.command_cursor > 0 {
-                                        self.command_cursor -= 1;
-                                        if self.command_cursor > 0 && self.command.chars().nth(self.command_cursor - 1) == Some('\n') {
-                                            self.command_line -= 1;
-                                        }
+                                KeyCode::Left if self.command_cursor > 0 => {
+                                    self.command_cursor -= 1;
+                                    if self.command_cursor > 0 && self.command.chars().nth(self.command_cursor - 1) == Some('\n') {
+                                        self.command_line -= 1;
                                     }
                                 }
-                                KeyCode::Right => {
-                                    if self.command_cursor < self.command.len() {
-                                        if self.command.chars().nth(self.command_cursor) == Some('\n') {
-                                            self.command_line += 1;
-                                        }
-                                        self.command_cursor += 1;
+                                KeyCode::Right if self.command_cursor < self.command.chars().count() => {
+                                    if self.command.chars().nth(self.command_cursor) == Some('\n') {
+                                        self.command_line += 1;
                                     }
+                                    self.command_cursor += 1;
                                 }
                                 KeyCode::Up => {
-                                    // Move cursor to previous line
-                                    let current_line_start = self.command[..self.command_cursor]
+                                    // Move cursor to previous line (byte offsets for slicing, chars for the cursor)
+                                    let cursor = self.byte_index(self.command_cursor);
+                                    let current_line_start = self.command[..cursor]
                                         .rfind('\n')
                                         .map(|pos| pos + 1)
                                         .unwrap_or(0);
                                     if let Some(prev_line_start) = self.command[..current_line_start.saturating_sub(1)]
                                         .rfind('\n')
                                         .map(|pos| pos + 1) {
-                                        let column = self.command_cursor - current_line_start;
-                                        self.command_cursor = prev_line_start + column.min(
-                                            self.command[prev_line_start..current_line_start.saturating_sub(1)]
-                                                .chars()
-                                                .count(),
-                                        );
+                                        let column = self.command[current_line_start..cursor].chars().count();
+                                        let prev_line_len = self.command[prev_line_start..current_line_start.saturating_sub(1)]
+                                            .chars()
+                                            .count();
+                                        self.command_cursor = self.command[..prev_line_start].chars().count()
+                                            + column.min(prev_line_len);
                                         self.command_line -= 1;
                                     }
                                 }
                                 KeyCode::Down => {
-                                    // Move cursor to next line
-                                    let current_line_start = self.command[..self.command_cursor]
+                                    // Move cursor to next line (byte offsets for slicing, chars for the cursor)
+                                    let cursor = self.byte_index(self.command_cursor);
+                                    let current_line_start = self.command[..cursor]
                                         .rfind('\n')
                                         .map(|pos| pos + 1)
                                         .unwrap_or(0);
-                                    if let Some(next_line_start) = self.command[self.command_cursor..]
+                                    if let Some(next_line_start) = self.command[cursor..]
                                         .find('\n')
-                                        .map(|pos| self.command_cursor + pos + 1) {
-                                        let column = self.command_cursor - current_line_start;
+                                        .map(|pos| cursor + pos + 1) {
+                                        let column = self.command[current_line_start..cursor].chars().count();
                                         let next_line_end = self.command[next_line_start..]
                                             .find('\n')
                                             .map(|pos| next_line_start + pos)
                                             .unwrap_or_else(|| self.command.len());
-                                        self.command_cursor = next_line_start + column.min(next_line_end - next_line_start);
+                                        let next_line_len = self.command[next_line_start..next_line_end].chars().count();
+                                        self.command_cursor = self.command[..next_line_start].chars().count()
+                                            + column.min(next_line_len);
                                         self.command_line += 1;
                                     }
                                 }
@@ -164,24 +190,18 @@ impl AddCommandApp {
                             InputMode::Tag => {
                                 match key.code {
                                     KeyCode::Enter => {
-                                        if !self.current_tag.is_empty() {
-                                            self.tags.push(self.current_tag.clone());
-                                            self.current_tag.clear();
-                                        } else {
-                                            self.input_mode = InputMode::Confirm;
-                                        }
+                                        self.submit_tag();
                                     }
                                     KeyCode::Char(c) => {
                                         self.current_tag.push(c);
+                                        self.tag_selection = None;
                                     }
                                     KeyCode::Backspace => {
                                         self.current_tag.pop();
+                                        self.tag_selection = None;
                                     }
                                     KeyCode::Tab => {
-                                        if !self.suggested_tags.is_empty() {
-                                            self.tags.push(self.suggested_tags[0].clone());
-                                            self.suggested_tags.remove(0);
-                                        }
+                                        self.select_next_tag_suggestion();
                                     }
                                     KeyCode::Esc => {
                                         self.input_mode = InputMode::Command;
@@ -189,12 +209,33 @@ impl AddCommandApp {
                                     _ => {}
                                 }
                             }
+                            InputMode::Directory => {
+                                match key.code {
+                                    KeyCode::Enter => {
+                                        self.input_mode = InputMode::Confirm;
+                                    }
+                                    KeyCode::Char(c) => {
+                                        self.directory.push(c);
+                                    }
+                                    KeyCode::Backspace => {
+                                        self.directory.pop();
+                                    }
+                                    KeyCode::Esc => {
+                                        self.input_mode = InputMode::Tag;
+                                    }
+                                    _ => {}
+                                }
+                            }
                             InputMode::Confirm => {
                                 match key.code {
                                     KeyCode::Char('y') => {
+                                        let command = strip_prompt_marker(&self.command)
+                                            .unwrap_or(&self.command)
+                                            .to_string();
                                         return Ok(Some((
-                                            self.command.clone(),
+                                            command,
                                             self.tags.clone(),
+                                            self.directory.clone(),
                                             None,
                                         )));
                                     }
@@ -214,13 +255,168 @@ impl AddCommandApp {
 
     pub fn set_command(&mut self, command: String) {
         self.command = command;
-        self.command_cursor = self.command.len();
+        self.command_cursor = self.command.chars().count();
     }
 
     pub fn set_tags(&mut self, tags: Vec<String>) {
         self.tags = tags;
     }
 
+    /// Sets the vault's tags, most used first, for completing tags.
+    pub fn set_known_tags(&mut self, tags: Vec<String>) {
+        self.known_tags = tags;
+    }
+
+    /// Tags to suggest for what's being typed: vault tags that fuzzy-match it,
+    /// best match first, or the suggestions based on the command when none do.
+    /// 
+    /// Tags the command already has are left out.
+    pub fn tag_suggestions(&self) -> Vec<String> {
+        let unused = |tag: &&String| !self.tags.contains(tag);
+        let query = self.current_tag.trim();
+        let mut suggestions = Vec::new();
+        if !query.is_empty() {
+            let matcher = SkimMatcherV2::default();
+            let mut scored: Vec<(i64, &String)> = self.known_tags
+                .iter()
+                .filter(unused)
+                .filter_map(|tag| matcher.fuzzy_match(tag, query).map(|score| (score, tag)))
+                .collect();
+            // Stable, so equally good matches stay in order of use
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            suggestions = scored.into_iter().map(|(_, tag)| tag.clone()).collect();
+        }
+        if suggestions.is_empty() {
+            suggestions = self.suggested_tags.iter().filter(unused).cloned().collect();
+        }
+        suggestions.truncate(MAX_TAG_SUGGESTIONS);
+        suggestions
+    }
+
+    /// Highlights the next tag suggestion, wrapping around (Tab).
+    fn select_next_tag_suggestion(&mut self) {
+        let count = self.tag_suggestions().len();
+        if count > 0 {
+            self.tag_selection = Some(self.tag_selection.map_or(0, |i| (i + 1) % count));
+        }
+    }
+
+    /// Adds the highlighted suggestion, or else the typed tag (Enter).
+    /// With nothing to add, moves on to the directory.
+    fn submit_tag(&mut self) {
+        let selected = self.tag_selection.take().and_then(|i| self.tag_suggestions().get(i).cloned());
+        let typed = normalize_tag(&self.current_tag, crate::config::lowercase_tags());
+        match selected.or(typed) {
+            Some(tag) => {
+                merge_tags(&mut self.tags, vec![tag]);
+                self.current_tag.clear();
+            }
+            None => {
+                // Whitespace alone isn't a tag
+                self.current_tag.clear();
+                self.input_mode = InputMode::Directory;
+            }
+        }
+    }
+
+    pub fn set_directory(&mut self, directory: String) {
+        self.directory = directory;
+    }
+
+    /// Converts a char index in the command into a byte offset.
+    /// 
+    /// `command_cursor` counts characters, but `String::insert`/`remove`
+    /// need byte offsets on char boundaries.
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.command
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.command.len())
+    }
+
+    /// Inserts pasted text into the field being edited.
+    /// 
+    /// Newlines are kept in the command instead of acting as Enter, so a
+    /// multi-line paste never moves on to the next field. Tags and the
+    /// directory are single-line, so newlines are dropped there.
+    pub fn handle_paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        match self.input_mode {
+            InputMode::Command => {
+                self.command.insert_str(self.byte_index(self.command_cursor), &text);
+                self.command_cursor += text.chars().count();
+                self.command_line += text.matches('\n').count();
+            }
+            InputMode::Tag => self.current_tag.extend(text.chars().filter(|&c| c != '\n')),
+            InputMode::Directory => self.directory.extend(text.chars().filter(|&c| c != '\n')),
+            _ => {}
+        }
+    }
+
+    /// Handles Ctrl+F and the keys typed while finding in the command.
+    /// 
+    /// Returns whether the key was used; the arrow keys still move the cursor
+    /// while find is open.
+    fn handle_find_key(&mut self, key: &KeyEvent) -> bool {
+        if self.input_mode != InputMode::Command {
+            return false;
+        }
+        if key.code == KeyCode::Char('f') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            if self.find_query.is_some() {
+                self.find_next();
+            } else {
+                self.find_query = Some(String::new());
+            }
+            return true;
+        }
+        let Some(query) = self.find_query.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Char(c) => query.push(c),
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Enter => {
+                self.find_next();
+            }
+            KeyCode::Esc => self.find_query = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Moves the cursor to the next occurrence of the find query after it,
+    /// wrapping around to the start of the command.
+    /// 
+    /// Returns whether there was a match.
+    pub fn find_next(&mut self) -> bool {
+        let Some(query) = self.find_query.as_deref().filter(|query| !query.is_empty()) else {
+            return false;
+        };
+        let start = self.byte_index(self.command_cursor + 1);
+        let found = self.command[start..]
+            .find(query)
+            .map(|pos| start + pos)
+            .or_else(|| self.command.find(query));
+        match found {
+            Some(pos) => {
+                self.command_cursor = self.command[..pos].chars().count();
+                self.command_line = self.command[..pos].matches('\n').count();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Leaves command input: detects parameters and suggests tags.
+    fn finish_command_input(&mut self) {
+        self.parameters = parse_parameters(&self.command);
+        self.suggest_tags();
+        self.input_mode = InputMode::Tag;
+    }
+
     fn ui(&self, f: &mut ratatui::Frame) {
         match self.input_mode {
             InputMode::Help => {
@@ -236,10 +432,15 @@ impl AddCommandApp {
                     "  Shift+Enter  - Add new line",
                     "  ←/→         - Move cursor",
                     "  ↑/↓         - Navigate between lines",
+                    "  Ctrl+F       - Find in the command (Enter for next match)",
                     "",
                     "Tag Input Mode:",
                     "  Enter  - Add tag",
-                    "  Tab    - Show tag suggestions",
+                    "  Tab    - Pick a suggested tag (Enter adds it)",
+                    "  Enter  - Continue to directory (when tag is empty)",
+                    "",
+                    "Directory Input Mode:",
+                    "  Enter  - Continue to confirmation",
                     "",
                     "Confirmation Mode:",
                     "  y/Y    - Save command",
@@ -263,6 +464,8 @@ impl AddCommandApp {
                         Constraint::Length(3),  // Title
                         Constraint::Min(5),     // Command input
                         Constraint::Length(3),  // Tags input
+                        Constraint::Length(3),  // Directory input
+                        Constraint::Length(3),  // Parameters preview
                         Constraint::Min(0),     // Message/Help
                     ])
                     .split(f.size());
@@ -276,8 +479,27 @@ impl AddCommandApp {
                 // Command input
                 let mut command_text = self.command.clone();
                 if self.input_mode == InputMode::Command {
-                    command_text.insert(self.command_cursor, '│'); // Add cursor
+                    command_text.insert(self.byte_index(self.command_cursor), '│'); // Add cursor
                 }
+                let query = self.find_query.as_deref().unwrap_or("");
+                let command_text: Text = command_text
+                    .split('\n')
+                    .map(|line| {
+                        Line::from(
+                            highlight_segments(line, query, true)
+                                .into_iter()
+                                .map(|(segment, matched)| {
+                                    if matched {
+                                        Span::styled(segment, Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD))
+                                    } else {
+                                        Span::raw(segment)
+                                    }
+                                })
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .into();
                 let command_input = Paragraph::new(command_text)
                     .style(Style::default().fg(if self.input_mode == InputMode::Command {
                         Color::Yellow
@@ -306,17 +528,70 @@ impl AddCommandApp {
                     .block(Block::default().borders(Borders::ALL).title("Tags"));
                 f.render_widget(tags_input, chunks[2]);
 
+                // Directory input
+                let mut directory_text = self.directory.clone();
+                if self.input_mode == InputMode::Directory {
+                    directory_text.push('│');
+                }
+                let directory_input = Paragraph::new(directory_text)
+                    .style(Style::default().fg(if self.input_mode == InputMode::Directory {
+                        Color::Yellow
+                    } else {
+                        Color::Gray
+                    }))
+                    .block(Block::default().borders(Borders::ALL).title("Directory"));
+                f.render_widget(directory_input, chunks[3]);
+
+                // Parameters preview (read-only)
+                let parameters_text = if self.parameters.is_empty() {
+                    "None".to_string()
+                } else {
+                    self.parameters.iter()
+                        .map(|p| match &p.description {
+                            Some(desc) => format!("{} ({})", p.name, desc),
+                            None => p.name.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let parameters_preview = Paragraph::new(parameters_text)
+                    .style(Style::default().fg(Color::Gray))
+                    .block(Block::default().borders(Borders::ALL).title("Parameters"));
+                f.render_widget(parameters_preview, chunks[4]);
+
                 // Help text or confirmation prompt
+                let find_text = self.find_query.as_ref().map(|query| {
+                    let status = if !query.is_empty() && !self.command.contains(query.as_str()) { " (no match)" } else { "" };
+                    format!("Find: {}{}  (Enter for next match, Esc to close)", query, status)
+                });
                 let help_text = match self.input_mode {
+                    InputMode::Command if find_text.is_some() => find_text.as_deref().unwrap_or_default(),
                     InputMode::Command => "Press ? for help",
                     InputMode::Tag => "Press ? for help",
+                    InputMode::Directory => "Press ? for help",
                     InputMode::Confirm => "Save command? (y/n)",
                     InputMode::Help => unreachable!(),
                 };
-                let help = Paragraph::new(help_text)
+                // While typing a tag, the help line lists the suggestions instead
+                let suggestions = self.tag_suggestions();
+                let help_line = if self.input_mode == InputMode::Tag && !suggestions.is_empty() {
+                    let mut spans = vec![Span::raw("Suggestions (Tab to pick, Enter to add):")];
+                    for (i, tag) in suggestions.into_iter().enumerate() {
+                        spans.push(Span::raw(" "));
+                        if self.tag_selection == Some(i) {
+                            spans.push(Span::styled(tag, Style::default().fg(Color::Black).bg(Color::Yellow)));
+                        } else {
+                            spans.push(Span::styled(tag, Style::default().fg(Color::Cyan)));
+                        }
+                    }
+                    Line::from(spans)
+                } else {
+                    Line::from(help_text)
+                };
+                let help = Paragraph::new(help_line)
                     .style(Style::default().fg(Color::White))
                     .block(Block::default().borders(Borders::ALL));
-                f.render_widget(help, chunks[3]);
+                f.render_widget(help, chunks[5]);
             }
         }
     }
@@ -353,6 +628,9 @@ impl AddCommandApp {
     }
 
     pub fn handle_key_event(&mut self, key: KeyEvent) {
+        if self.handle_find_key(&key) {
+            return;
+        }
         match self.input_mode {
             InputMode::Help => match key.code {
                 KeyCode::Char('?') | KeyCode::Esc => {
@@ -368,32 +646,26 @@ impl AddCommandApp {
                 _ => match self.input_mode {
                     InputMode::Command => match key.code {
                         KeyCode::Char(c) => {
-                            self.command.insert(self.command_cursor, c);
+                            self.command.insert(self.byte_index(self.command_cursor), c);
                             self.command_cursor += 1;
                         }
-                        KeyCode::Backspace => {
-                            if self.command_cursor > 0 {
-                                self.command.remove(self.command_cursor - 1);
-                                self.command_cursor -= 1;
-                            }
+                        KeyCode::Backspace if self.command_cursor > 0 => {
+                            self.command.remove(self.byte_index(self.command_cursor - 1));
+                            self.command_cursor -= 1;
                         }
-                        KeyCode::Left => {
-                            if self.command_cursor > 0 {
-                                self.command_cursor -= 1;
-                            }
+                        KeyCode::Left if self.command_cursor > 0 => {
+                            self.command_cursor -= 1;
                         }
-                        KeyCode::Right => {
-                            if self.command_cursor < self.command.len() {
-                                self.command_cursor += 1;
-                            }
+                        KeyCode::Right if self.command_cursor < self.command.chars().count() => {
+                            self.command_cursor += 1;
                         }
                         KeyCode::Enter => {
                             if key.modifiers.contains(KeyModifiers::SHIFT) {
-                                self.command.insert(self.command_cursor, '\n');
+                                self.command.insert(self.byte_index(self.command_cursor), '\n');
                                 self.command_cursor += 1;
                                 self.command_line += 1;
                             } else if !self.command.is_empty() {
-                                self.input_mode = InputMode::Tag;
+                                self.finish_command_input();
                             }
                         }
                         _ => {}
@@ -401,12 +673,29 @@ impl AddCommandApp {
                     InputMode::Tag => match key.code {
                         KeyCode::Char(c) => {
                             self.current_tag.push(c);
+                            self.tag_selection = None;
+                        }
+                        KeyCode::Backspace => {
+                            self.current_tag.pop();
+                            self.tag_selection = None;
+                        }
+                        KeyCode::Tab => {
+                            self.select_next_tag_suggestion();
                         }
                         KeyCode::Enter => {
-                            if !self.current_tag.is_empty() {
-                                self.tags.push(self.current_tag.clone());
-                                self.current_tag.clear();
-                            }
+                            self.submit_tag();
+                        }
+                        _ => {}
+                    },
+                    InputMode::Directory => match key.code {
+                        KeyCode::Char(c) => {
+                            self.directory.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            self.directory.pop();
+                        }
+                        KeyCode::Enter => {
+                            self.input_mode = InputMode::Confirm;
                         }
                         _ => {}
                     },
@@ -420,7 +709,7 @@ impl AddCommandApp {
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.hide_cursor()?;
@@ -429,7 +718,7 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
 
 fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
     terminal.show_cursor()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
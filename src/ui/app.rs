@@ -1,5 +1,9 @@
+use std::collections::HashMap;
 use std::io::{self, Stdout};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use anyhow::Result;
+use chrono::Utc;
 use crossterm::{
     event::{self, Event, KeyCode, KeyModifiers},
     execute,
@@ -14,49 +18,152 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
 };
 use crate::db::{Command, Database};
-use crate::utils::params::{substitute_parameters, parse_parameters};
-use crate::exec::{ExecutionContext, execute_shell_command};
+use crate::utils::params::{parse_parameters, reconcile_parameters, substitute_parameters_with_history};
+use crate::utils::limits::check_command_length;
+use crate::utils::tags::normalize_tag;
+use crate::utils::time::humanize_ago;
+use crate::exec::{confirm_run, exec_invocation, execute_shell_command, prompt_missing_directory, resolve_directory, ExecutionContext, MissingDirectory};
 use crate::ui::AddCommandApp;
+use crate::ui::editor::edit_in_editor;
+use crate::ui::keys::{Action, KeyBindings};
+use crate::ui::panic::PanicHookGuard;
+use crate::ui::display::{collapse_lines, count_summary, highlight_segments, history_summary, parse_color, shorten_home, truncate_to_width, wrap_to_width};
+use crate::ui::state::{state_path, ColumnSet, UiState};
+
+/// Commands are never cut shorter than this, even in a very narrow terminal.
+const MIN_COMMAND_WIDTH: usize = 10;
+
+/// How long a status message stays up.
+pub const MESSAGE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often the list is redrawn while waiting for a key, so expired messages disappear.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the filter text is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Command text, tags, directory and note
+    Text,
+    /// Tags only
+    Tag,
+}
+
+/// The selected command's tags being edited in place, opened with `T`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagEdit {
+    /// Tag being typed, added on Enter
+    pub input: String,
+    /// Position of the existing tag picked with ←/→, removed on Delete
+    pub selected: Option<usize>,
+}
 
 pub struct App<'a> {
     pub commands: Vec<Command>,
     pub selected: Option<usize>,
     pub show_help: bool,
     pub message: Option<(String, Color)>,
+    /// When `message` was set
+    pub message_time: Option<Instant>,
     pub filter_text: String,
+    pub filter_mode: FilterMode,
     pub filtered_commands: Vec<usize>,
     pub db: &'a mut Database,
     pub confirm_delete: Option<usize>, // Index of command pending deletion
     pub debug_mode: bool,
+    /// Return to the list after executing a command instead of exiting
+    pub stay_after_exec: bool,
+    /// Where the filter is remembered between sessions, if enabled
+    pub state_path: Option<PathBuf>,
+    /// Match the filter's case exactly instead of ignoring it
+    pub case_sensitive: bool,
+    /// Colors set with `tag color`; other tags are shown in green
+    pub tag_colors: HashMap<String, Color>,
+    /// Show absolute timestamps instead of "3m ago"
+    pub absolute_time: bool,
+    /// How many commands the vault holds, not just the ones loaded
+    pub total_commands: i64,
+    /// Moving past the last command goes back to the first, and the other way around
+    pub wrap_navigation: bool,
+    /// Row number typed after `:`, while jumping to a row
+    pub goto_input: Option<String>,
+    /// Tags of the selected command being edited in place
+    pub tag_edit: Option<TagEdit>,
+    /// Keys for navigation and the command actions
+    pub keys: KeyBindings,
+    /// Which columns each row shows
+    pub columns: ColumnSet,
 }
 
 impl<'a> App<'a> {
     pub fn new(commands: Vec<Command>, db: &'a mut Database, debug_mode: bool) -> App<'a> {
         let filtered_commands: Vec<usize> = (0..commands.len()).collect();
-        App {
+        // Colors that no longer parse fall back to the default
+        let tag_colors = db.tag_colors()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(tag, color)| Some((tag, parse_color(&color)?)))
+            .collect();
+        let total_commands = db.count_commands().unwrap_or(commands.len() as i64);
+        let mut app = App {
             commands,
             selected: None,
             show_help: false,
             message: None,
+            message_time: None,
             filter_text: String::new(),
+            filter_mode: FilterMode::Text,
             filtered_commands,
             db,
             confirm_delete: None,
             debug_mode,
+            stay_after_exec: false,
+            state_path: state_path(),
+            case_sensitive: false,
+            tag_colors,
+            absolute_time: false,
+            total_commands,
+            wrap_navigation: crate::config::get().wrap_navigation,
+            goto_input: None,
+            tag_edit: None,
+            keys: crate::config::get().keys.clone(),
+            columns: ColumnSet::default(),
+        };
+
+        // Pick up where the last session's filter and columns left off
+        if let Some(path) = &app.state_path {
+            let state = UiState::load(path);
+            app.columns = state.columns;
+            if !state.filter_text.is_empty() {
+                app.set_filter(state.filter_text);
+            }
         }
+        app
     }
 
     pub fn run(&mut self) -> Result<()> {
+        let _panic_guard = PanicHookGuard::install();
         let mut terminal = setup_terminal()?;
         let res = self.run_app(&mut terminal);
         restore_terminal(&mut terminal)?;
+
+        if let Some(path) = &self.state_path {
+            let state = UiState { filter_text: self.filter_text.clone(), columns: self.columns };
+            if let Err(e) = state.save(path) {
+                eprintln!("Warning: could not save filter to {}: {}", path.display(), e);
+            }
+        }
         res
     }
 
     fn run_app(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
         loop {
+            self.clear_expired_message();
             terminal.draw(|f| self.ui(f))?;
 
+            // Wake up now and then even without input, to clear old messages
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
+            }
             if let Event::Key(key) = event::read()? {
                 if let Some(()) = self.handle_key_event(terminal, key)? {
                     return Ok(());
@@ -66,29 +173,51 @@ impl<'a> App<'a> {
     }
 
     fn handle_key_event(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>, key: event::KeyEvent) -> Result<Option<()>> {
+        let action = self.keys.action(&key);
         match key.code {
+            _ if self.tag_edit.is_some() && !key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_tag_edit_key(key.code),
             KeyCode::Char('q') => self.handle_quit(),
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Ok(Some(())),
-            KeyCode::Char('?') => self.handle_help_toggle(),
+            _ if self.goto_input.is_some() => self.handle_goto_key(key.code),
+            _ if action == Some(Action::Help) => self.handle_help_toggle(),
+            KeyCode::Esc => self.handle_escape(),
             _ if self.show_help => Ok(None),
-            KeyCode::Char('c') | KeyCode::Char('y') => self.handle_copy(),
-            KeyCode::Enter => self.handle_enter(terminal),
-            KeyCode::Char('e') => self.handle_edit(terminal),
-            KeyCode::Down | KeyCode::Char('j') => self.handle_down(),
-            KeyCode::Up | KeyCode::Char('k') => self.handle_up(),
-            KeyCode::Char('/') => self.handle_filter_start(),
-            KeyCode::Char('d') => self.handle_delete(),
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_case_toggle(),
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_time_toggle(),
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_editor_edit(terminal),
+            KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_copy_exec(),
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) => self.handle_column_toggle(c),
+            _ => match action {
+                Some(Action::Copy) => self.handle_copy(),
+                Some(Action::Execute) => self.handle_enter(terminal),
+                Some(Action::Edit) => self.handle_edit(terminal),
+                Some(Action::Next) => self.handle_down(),
+                Some(Action::Prev) => self.handle_up(),
+                Some(Action::Filter) => self.handle_filter_start(),
+                Some(Action::Delete) => self.handle_delete(),
+                Some(Action::Help) | None => self.handle_unbound_key(key.code),
+            },
+        }
+    }
+
+    /// Keys that can't be rebound, and typing into the filter.
+    pub fn handle_unbound_key(&mut self, code: KeyCode) -> Result<Option<()>> {
+        match code {
+            KeyCode::Char('i') if !self.is_filtering() => self.handle_copy_id(),
+            // Once a filter is being typed, `#` is part of it (shell comments, `#!`)
+            KeyCode::Char('#') if !self.is_filtering() => self.handle_tag_filter_start(),
+            KeyCode::Char(':') if !self.is_filtering() => self.handle_goto_start(),
+            KeyCode::Char('T') if !self.is_filtering() => self.handle_tag_edit_start(),
             KeyCode::Char(c) => self.handle_char_input(c),
             KeyCode::Backspace => self.handle_backspace(),
-            KeyCode::Esc => self.handle_escape(),
             _ => Ok(None)
         }
     }
 
     pub fn handle_quit(&mut self) -> Result<Option<()>> {
-        if !self.filter_text.is_empty() {
-            self.filter_text.clear();
-            self.update_filtered_commands();
+        if !self.filter_text.is_empty() || self.filter_mode == FilterMode::Tag {
+            self.filter_mode = FilterMode::Text;
+            self.clear_filter();
             Ok(None)
         } else if self.confirm_delete.is_some() {
             self.confirm_delete = None;
@@ -114,11 +243,32 @@ impl<'a> App<'a> {
         Ok(None)
     }
 
+    /// Copies the selected command's id, e.g. for `cv exec <id>` in a script.
+    pub fn handle_copy_id(&mut self) -> Result<Option<()>> {
+        if let Some(id) = self.get_selected_command().and_then(|cmd| cmd.id) {
+            copy_to_clipboard(&id.to_string())?;
+            self.set_success_message(format!("Copied ID {}", id));
+        }
+        Ok(None)
+    }
+
+    /// Copies a `command-vault exec` line that re-runs the selected command.
+    pub fn handle_copy_exec(&mut self) -> Result<Option<()>> {
+        if let Some(cmd) = self.get_selected_command() {
+            copy_to_clipboard(&exec_invocation(cmd))?;
+            self.set_success_message("Exec command copied to clipboard!".to_string());
+        }
+        Ok(None)
+    }
+
     fn handle_enter(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<Option<()>> {
-        if let Some(selected) = self.get_selection() {
+        if self.get_selection().is_some() {
             if self.confirm_delete.is_some() {
                 self.delete_selected_command()?;
                 Ok(None)
+            } else if self.stay_after_exec {
+                self.execute_and_return(terminal)?;
+                Ok(None)
             } else {
                 self.execute_selected_command(terminal).map(Some)
             }
@@ -132,6 +282,11 @@ impl<'a> App<'a> {
         Ok(None)
     }
 
+    fn handle_editor_edit(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<Option<()>> {
+        self.edit_selected_in_editor(terminal)?;
+        Ok(None)
+    }
+
     fn handle_down(&mut self) -> Result<Option<()>> {
         self.select_next();
         Ok(None)
@@ -142,16 +297,186 @@ impl<'a> App<'a> {
         Ok(None)
     }
 
+    pub fn handle_case_toggle(&mut self) -> Result<Option<()>> {
+        self.set_case_sensitive(!self.case_sensitive);
+        let state = if self.case_sensitive { "on" } else { "off" };
+        self.set_message(format!("Case-sensitive filter {}", state), Color::Blue);
+        Ok(None)
+    }
+
+    pub fn handle_time_toggle(&mut self) -> Result<Option<()>> {
+        self.absolute_time = !self.absolute_time;
+        let state = if self.absolute_time { "absolute" } else { "relative" };
+        self.set_message(format!("Showing {} times", state), Color::Blue);
+        Ok(None)
+    }
+
+    /// Shows or hides a column: Alt+1 the id, Alt+2 the time, Alt+3 the
+    /// directory and Alt+4 the tags.
+    pub fn handle_column_toggle(&mut self, c: char) -> Result<Option<()>> {
+        let (column, name) = match c {
+            '1' => (&mut self.columns.id, "IDs"),
+            '2' => (&mut self.columns.time, "times"),
+            '3' => (&mut self.columns.directory, "directories"),
+            '4' => (&mut self.columns.tags, "tags"),
+            _ => return Ok(None),
+        };
+        *column = !*column;
+        let state = if *column { "Showing" } else { "Hiding" };
+        self.set_message(format!("{} {}", state, name), Color::Blue);
+        Ok(None)
+    }
+
     fn handle_filter_start(&mut self) -> Result<Option<()>> {
+        self.filter_mode = FilterMode::Text;
         self.clear_filter();
         self.set_message("Type to filter commands...".to_string(), Color::Blue);
         Ok(None)
     }
 
+    pub fn handle_tag_filter_start(&mut self) -> Result<Option<()>> {
+        self.filter_mode = FilterMode::Tag;
+        self.clear_filter();
+        self.set_message("Type to filter by tag...".to_string(), Color::Blue);
+        Ok(None)
+    }
+
+    pub fn handle_goto_start(&mut self) -> Result<Option<()>> {
+        self.goto_input = Some(String::new());
+        Ok(None)
+    }
+
+    /// Handles a key while a row number is being typed: digits build it up,
+    /// Enter jumps to that row (counting from 1) and Esc cancels.
+    pub fn handle_goto_key(&mut self, code: KeyCode) -> Result<Option<()>> {
+        let Some(input) = self.goto_input.as_mut() else {
+            return Ok(None);
+        };
+        match code {
+            KeyCode::Char(c) if c.is_ascii_digit() => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.goto_input = None,
+            KeyCode::Enter => {
+                let input = self.goto_input.take().unwrap_or_default();
+                let rows = self.filtered_commands.len();
+                match input.parse::<usize>() {
+                    Ok(row) if (1..=rows).contains(&row) => {
+                        self.selected = Some(row - 1);
+                        self.clear_message();
+                    }
+                    Ok(row) => self.set_error_message(format!("No row {} ({} shown)", row, rows)),
+                    Err(_) => {}
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Starts editing the selected command's tags in place.
+    pub fn handle_tag_edit_start(&mut self) -> Result<Option<()>> {
+        if self.get_selected_command().is_some_and(|cmd| cmd.id.is_some()) {
+            self.tag_edit = Some(TagEdit::default());
+        }
+        Ok(None)
+    }
+
+    /// Handles a key while tags are being edited: typing and Enter add a tag,
+    /// ←/→ pick an existing tag and Delete removes it. Esc stops editing.
+    pub fn handle_tag_edit_key(&mut self, code: KeyCode) -> Result<Option<()>> {
+        let tag_count = self.get_selected_command().map_or(0, |cmd| cmd.tags.len());
+        let Some(edit) = self.tag_edit.as_mut() else {
+            return Ok(None);
+        };
+        match code {
+            KeyCode::Char(c) => {
+                edit.input.push(c);
+                edit.selected = None;
+            }
+            KeyCode::Backspace => {
+                edit.input.pop();
+            }
+            KeyCode::Left if tag_count > 0 => {
+                edit.selected = Some(edit.selected.map_or(tag_count - 1, |i| i.saturating_sub(1)));
+            }
+            KeyCode::Right if tag_count > 0 => {
+                edit.selected = Some(edit.selected.map_or(0, |i| (i + 1).min(tag_count - 1)));
+            }
+            KeyCode::Esc => {
+                self.tag_edit = None;
+                // The command may no longer match a tag filter
+                self.update_filtered_commands();
+            }
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut edit.input);
+                if !input.trim().is_empty() {
+                    self.add_tag_to_selected(&input);
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(pos) = edit.selected {
+                    self.remove_tag_from_selected(pos);
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Adds `tag` to the selected command, in the database and the list.
+    fn add_tag_to_selected(&mut self, tag: &str) {
+        let Some(idx) = self.get_selected_index() else {
+            return;
+        };
+        let Some(id) = self.commands[idx].id else {
+            return;
+        };
+        let Some(tag) = normalize_tag(tag, crate::config::lowercase_tags()) else {
+            self.set_error_message("Tag can't be empty".to_string());
+            return;
+        };
+        match self.db.add_tags_to_command(id, std::slice::from_ref(&tag)) {
+            Ok(()) => {
+                if !self.commands[idx].tags.contains(&tag) {
+                    self.commands[idx].tags.push(tag.clone());
+                }
+                self.set_success_message(format!("Added tag '{}'", tag));
+            }
+            Err(e) => self.set_error_message(format!("Failed to add tag: {}", e)),
+        }
+    }
+
+    /// Removes the tag at `pos` from the selected command, in the database and the list.
+    fn remove_tag_from_selected(&mut self, pos: usize) {
+        let Some(idx) = self.get_selected_index() else {
+            return;
+        };
+        let Some(id) = self.commands[idx].id else {
+            return;
+        };
+        let Some(tag) = self.commands[idx].tags.get(pos).cloned() else {
+            return;
+        };
+        match self.db.remove_tag_from_command(id, &tag) {
+            Ok(()) => {
+                self.commands[idx].tags.remove(pos);
+                let remaining = self.commands[idx].tags.len();
+                if let Some(edit) = self.tag_edit.as_mut() {
+                    // Stay on the tag that took its place, or the new last one
+                    edit.selected = (remaining > 0).then(|| pos.min(remaining - 1));
+                }
+                self.set_success_message(format!("Removed tag '{}'", tag));
+            }
+            Err(e) => self.set_error_message(format!("Failed to remove tag: {}", e)),
+        }
+    }
+
     fn handle_delete(&mut self) -> Result<Option<()>> {
         if let Some(selected) = self.get_selection() {
             if let Some(&filtered_idx) = self.filtered_commands.get(selected) {
-                if let Some(_) = self.commands[filtered_idx].id {
+                if self.commands[filtered_idx].id.is_some() {
                     self.confirm_delete = Some(selected);
                 }
             }
@@ -175,7 +500,10 @@ impl<'a> App<'a> {
     }
 
     pub fn handle_escape(&mut self) -> Result<Option<()>> {
-        if !self.filter_text.is_empty() {
+        if self.show_help {
+            self.show_help = false;
+        } else if !self.filter_text.is_empty() || self.filter_mode == FilterMode::Tag {
+            self.filter_mode = FilterMode::Text;
             self.clear_filter();
         } else if self.confirm_delete.is_some() {
             self.confirm_delete = None;
@@ -206,11 +534,35 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Switches case-sensitive filtering on or off and re-applies the filter.
+    pub fn set_case_sensitive(&mut self, case_sensitive: bool) {
+        self.case_sensitive = case_sensitive;
+        self.update_filtered_commands();
+    }
+
     fn matches_filter(&self, command: &Command, search_term: &str) -> bool {
+        if self.filter_mode == FilterMode::Tag {
+            return command.tags.iter().any(|tag| {
+                if self.case_sensitive {
+                    tag.contains(search_term)
+                } else {
+                    tag.to_lowercase().contains(&search_term.to_lowercase())
+                }
+            });
+        }
+
+        if self.case_sensitive {
+            return command.command.contains(search_term) ||
+                command.tags.iter().any(|tag| tag.contains(search_term)) ||
+                command.directory.contains(search_term) ||
+                command.note.as_ref().is_some_and(|note| note.contains(search_term));
+        }
+
         let search_term = search_term.to_lowercase();
         command.command.to_lowercase().contains(&search_term) ||
         command.tags.iter().any(|tag| tag.to_lowercase().contains(&search_term)) ||
-        command.directory.to_lowercase().contains(&search_term)
+        command.directory.to_lowercase().contains(&search_term) ||
+        command.note.as_ref().is_some_and(|note| note.to_lowercase().contains(&search_term))
     }
 
     pub fn update_filtered_commands(&mut self) {
@@ -239,33 +591,53 @@ impl<'a> App<'a> {
     }
 
     fn render_help_screen(&self, f: &mut ratatui::Frame) {
+        // Rebindable keys show what's configured
+        let key = |action: Action, description: &str| format!("  {:<8} - {}", self.keys.label(action), description);
+        let prev = key(Action::Prev, "Move cursor up");
+        let next = key(Action::Next, "Move cursor down");
+        let execute = key(Action::Execute, "Execute selected command");
+        let copy = key(Action::Copy, "Copy command to clipboard");
+        let edit = key(Action::Edit, "Edit selected command (text, tags, directory)");
+        let delete = key(Action::Delete, "Delete selected command (requires confirmation)");
+        let filter = key(Action::Filter, "Start filtering commands");
+        let help = key(Action::Help, "Toggle this help screen");
         let help_text = vec![
             "Command Vault Help",
             "",
             "Navigation:",
-            "  ↑/k      - Move cursor up",
-            "  ↓/j      - Move cursor down",
+            &prev,
+            &next,
+            "  :N Enter - Jump to row N of the list",
             "  q        - Quit (or clear filter/cancel delete/close help)",
             "  Ctrl+c   - Force quit",
             "",
             "Command Actions:",
-            "  Enter    - Execute selected command",
-            "  c/y      - Copy command to clipboard",
-            "  e        - Edit selected command (text, tags, directory)",
-            "  d        - Delete selected command (requires confirmation)",
+            &execute,
+            &copy,
+            "  i        - Copy command ID to clipboard",
+            "  Ctrl+y   - Copy a `command-vault exec` line for the command",
+            &edit,
+            "  Ctrl+e   - Edit selected command's text in $EDITOR",
+            "  T        - Add/remove tags of the selected command in place",
+            &delete,
             "",
             "Search and Filter:",
-            "  /        - Start filtering commands",
+            &filter,
             "  [type]   - Filter by command text, tags, or directory",
+            "             (i, :, # and T are typed into a filter once it's started)",
+            "  #        - Start filtering by tag only",
             "  Esc      - Clear filter or cancel current operation",
             "  Backspace- Remove last character from filter",
+            "  Ctrl+t   - Toggle case-sensitive filtering",
             "",
             "Display:",
-            "  ?        - Toggle this help screen",
+            &help,
+            "  Ctrl+a   - Toggle relative/absolute times",
+            "  Alt+1..4 - Show/hide the ID, time, directory and tags columns",
             "",
             "Command Format:",
             "  - (@param) Parameters are shown with @ prefix",
-            "  - (#tag)  Tags are shown in green with # prefix",
+            "  - (#tag)  Tags are shown with # prefix (see `tag color`)",
             "  - (dir)   Working directory is shown if set",
             "  - (id)    Command IDs are shown in parentheses",
             "",
@@ -305,42 +677,104 @@ impl<'a> App<'a> {
     }
 
     fn render_title(&self, f: &mut ratatui::Frame, area: Rect) {
-        let title = Paragraph::new("Command Vault")
-            .style(Style::default().fg(Color::Cyan))
+        let title = Paragraph::new(Line::from(vec![
+            Span::styled("Command Vault", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!(" │ Commands ({})", count_summary(&self.commands, self.total_commands)),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]))
             .block(Block::default().borders(Borders::ALL));
         f.render_widget(title, area);
     }
 
     fn render_commands_list(&mut self, f: &mut ratatui::Frame, area: Rect) {
+        // Room inside the borders; long commands are cut to fit rather than overflowing
+        let inner_width = area.width.saturating_sub(2) as usize;
+
         let commands: Vec<ListItem> = self.filtered_commands.iter()
-            .map(|&i| {
+            .enumerate()
+            .map(|(row, &i)| {
                 let cmd = &self.commands[i];
-                let local_time = cmd.timestamp.with_timezone(&chrono::Local);
-                let time_str = local_time.format("%Y-%m-%d %H:%M:%S").to_string();
-                
-                let mut spans = vec![
-                    Span::styled(
-                        format!("({}) ", cmd.id.unwrap_or(0)),
-                        Style::default().fg(Color::DarkGray)
-                    ),
-                    Span::styled(
-                        format!("[{}] ", time_str),
-                        Style::default().fg(Color::Yellow)
-                    ),
-                    Span::raw(&cmd.command),
-                ];
-
-                if !cmd.tags.is_empty() {
+                // The selected row always shows the exact time
+                let time_str = if self.absolute_time || self.selected == Some(row) {
+                    let local_time = cmd.timestamp.with_timezone(&chrono::Local);
+                    local_time.format("%Y-%m-%d %H:%M:%S").to_string()
+                } else {
+                    humanize_ago(cmd.timestamp)
+                };
+
+                let mut prefix = Vec::new();
+                if self.columns.id {
+                    prefix.push(Span::styled(format!("({}) ", cmd.id.unwrap_or(0)), Style::default().fg(Color::DarkGray)));
+                }
+                if self.columns.time {
+                    prefix.push(Span::styled(format!("[{}] ", time_str), Style::default().fg(Color::Yellow)));
+                }
+                if self.columns.directory {
+                    prefix.push(Span::styled(format!("{} ", shorten_home(&cmd.directory)), Style::default().fg(Color::Blue)));
+                }
+                let show_tags = self.columns.tags && !cmd.tags.is_empty();
+                // Tags are rendered as " #tag1 #tag2 "
+                let tags_width = if show_tags {
+                    1 + cmd.tags.iter().map(|tag| tag.chars().count() + 2).sum::<usize>()
+                } else {
+                    0
+                };
+
+                let prefix_width = prefix.iter().map(|span| span.content.chars().count()).sum::<usize>();
+                let available = inner_width
+                    .saturating_sub(prefix_width + tags_width)
+                    .max(MIN_COMMAND_WIDTH);
+
+                let collapsed = collapse_lines(&cmd.command);
+                let fits = !cmd.command.contains('\n') && collapsed.chars().count() <= available;
+
+                // The selected row is expanded so the full command can be read
+                let wrapped = if self.selected == Some(row) && !fits {
+                    wrap_to_width(&cmd.command, available)
+                } else {
+                    vec![truncate_to_width(&collapsed, available)]
+                };
+
+                let mut spans = prefix;
+                spans.extend(self.highlighted(&wrapped[0]));
+
+                if show_tags {
                     spans.push(Span::raw(" "));
                     for tag in &cmd.tags {
                         spans.push(Span::styled(
                             format!("#{} ", tag),
-                            Style::default().fg(Color::Green)
+                            Style::default().fg(self.tag_color(tag))
                         ));
                     }
                 }
 
-                ListItem::new(Line::from(spans))
+                let mut lines = vec![Line::from(spans)];
+                let indent = " ".repeat(prefix_width);
+                for line in &wrapped[1..] {
+                    let mut spans = vec![Span::raw(indent.clone())];
+                    spans.extend(self.highlighted(line));
+                    lines.push(Line::from(spans));
+                }
+
+                // The selected row also shows its note, if it has one
+                if let Some(note) = cmd.note.as_ref().filter(|_| self.selected == Some(row)) {
+                    lines.push(Line::from(Span::styled(
+                        format!("{}{}", indent, truncate_to_width(&collapse_lines(note), available)),
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+                    )));
+                }
+
+                // ...and where and when it was added, if it has been edited since
+                if let Some(summary) = history_summary(cmd, Utc::now()).filter(|_| self.selected == Some(row)) {
+                    lines.push(Line::from(Span::styled(
+                        format!("{}{}", indent, summary),
+                        Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+                    )));
+                }
+
+                ListItem::new(lines)
             })
             .collect();
 
@@ -361,17 +795,72 @@ impl<'a> App<'a> {
         }
     }
 
+    /// The color `tag` is shown in.
+    pub fn tag_color(&self, tag: &str) -> Color {
+        self.tag_colors.get(tag).copied().unwrap_or(Color::Green)
+    }
+
+    /// Renders text with the parts matching the filter highlighted.
+    fn highlighted(&self, text: &str) -> Vec<Span<'static>> {
+        // A tag filter doesn't match the command text
+        let filter = match self.filter_mode {
+            FilterMode::Text => self.filter_text.as_str(),
+            FilterMode::Tag => "",
+        };
+        highlight_segments(text, filter, self.case_sensitive)
+            .into_iter()
+            .map(|(segment, matched)| {
+                if matched {
+                    Span::styled(segment, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                } else {
+                    Span::raw(segment)
+                }
+            })
+            .collect()
+    }
+
     fn render_filter(&self, f: &mut ratatui::Frame, area: Rect) {
-        if !self.filter_text.is_empty() {
-            let filter = Paragraph::new(format!("Filter: {}", self.filter_text))
-                .style(Style::default().fg(Color::Yellow));
+        // The tag filter's prompt shows as soon as it starts, so the mode is visible
+        if !self.filter_text.is_empty() || self.filter_mode == FilterMode::Tag {
+            let (label, color) = match self.filter_mode {
+                FilterMode::Text => ("Filter", Color::Yellow),
+                FilterMode::Tag => ("Tag filter", Color::Green),
+            };
+            let case = if self.case_sensitive { " (case-sensitive)" } else { "" };
+            let matches = self.filtered_commands.len();
+            let color = if matches == 0 { Color::Red } else { color };
+            let text = format!(
+                "{}{}: {}  ({} match{})",
+                label, case, self.filter_text, matches, if matches == 1 { "" } else { "es" }
+            );
+            let filter = Paragraph::new(text).style(Style::default().fg(color));
             f.render_widget(filter, area);
         }
     }
 
     fn render_status_bar(&self, f: &mut ratatui::Frame, area: Rect) {
-        let status = if let Some((msg, color)) = &self.message {
+        let status = if let Some(edit) = &self.tag_edit {
+            // Existing tags first, with the one picked for removal highlighted
+            let mut spans = vec![Span::styled("Tags: ", Style::default().fg(Color::Yellow))];
+            let tags = self.get_selected_command().map(|cmd| cmd.tags.clone()).unwrap_or_default();
+            for (i, tag) in tags.iter().enumerate() {
+                let style = Style::default().fg(self.tag_color(tag));
+                let style = if edit.selected == Some(i) { style.add_modifier(Modifier::REVERSED) } else { style };
+                spans.push(Span::styled(format!("#{}", tag), style));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(format!("+ {}", edit.input), Style::default().fg(Color::Yellow)));
+            spans.push(Span::raw(" | Enter to add, ←/→ and Delete to remove, Esc when done"));
+            spans
+        } else if let Some(input) = &self.goto_input {
+            vec![
+                Span::styled(format!("Go to row: {}", input), Style::default().fg(Color::Yellow)),
+                Span::raw(" | Enter to jump, Esc to cancel"),
+            ]
+        } else if let Some((msg, color)) = &self.message {
             vec![Span::styled(msg, Style::default().fg(*color))]
+        } else if let Some(status) = self.no_matches_status() {
+            vec![Span::styled(status, Style::default().fg(Color::Red))]
         } else if self.show_help {
             vec![
                 Span::raw("Press "),
@@ -386,6 +875,14 @@ impl<'a> App<'a> {
                 Span::styled("?", Style::default().fg(Color::Yellow)),
                 Span::raw(" for help"),
             ]
+        } else if let Some(summary) = self.parameter_summary() {
+            // Show what execution will prompt for before Enter is pressed
+            vec![
+                Span::styled(summary, Style::default().fg(Color::Cyan)),
+                Span::raw(" | Press "),
+                Span::styled("?", Style::default().fg(Color::Yellow)),
+                Span::raw(" for help"),
+            ]
         } else {
             vec![
                 Span::raw("Press "),
@@ -399,6 +896,16 @@ impl<'a> App<'a> {
         f.render_widget(status, area);
     }
 
+    /// Whether a filter is being typed, by text or by tag.
+    pub fn is_filtering(&self) -> bool {
+        !self.filter_text.is_empty() || self.filter_mode == FilterMode::Tag
+    }
+
+    /// Status bar text when the filter matches nothing, so an empty list isn't mistaken for an empty vault.
+    pub fn no_matches_status(&self) -> Option<&'static str> {
+        (self.is_filtering() && self.filtered_commands.is_empty()).then_some("No matches — press Esc to clear")
+    }
+
     fn render_delete_confirmation(&self, f: &mut ratatui::Frame) {
         if let Some(idx) = self.confirm_delete {
             if let Some(&cmd_idx) = self.filtered_commands.get(idx) {
@@ -406,7 +913,7 @@ impl<'a> App<'a> {
                     let command_str = format!("Command: {}", cmd.command);
                     let id_str = format!("ID: {}", cmd.id.unwrap_or(0));
                     
-                    let dialog_text = vec![
+                    let dialog_text = [
                         "Are you sure you want to delete this command?",
                         "",
                         &command_str,
@@ -432,10 +939,19 @@ impl<'a> App<'a> {
 
     pub fn set_message(&mut self, text: String, color: Color) {
         self.message = Some((text, color));
+        self.message_time = Some(Instant::now());
     }
 
     pub fn clear_message(&mut self) {
         self.message = None;
+        self.message_time = None;
+    }
+
+    /// Clears the status message once it has been up for `MESSAGE_TIMEOUT`.
+    pub fn clear_expired_message(&mut self) {
+        if self.message_time.is_some_and(|time| time.elapsed() >= MESSAGE_TIMEOUT) {
+            self.clear_message();
+        }
     }
 
     pub fn set_success_message(&mut self, text: String) {
@@ -470,10 +986,16 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Keeps the highlight on the same command after the row at `deleted_index`
+    /// (a position in `filtered_commands`, which no longer includes it) is removed.
+    /// 
+    /// If the selected row itself was deleted, the selection stays at the same
+    /// position, which is now the command that followed it.
     pub fn update_selection_after_delete(&mut self, deleted_index: usize) {
         if self.filtered_commands.is_empty() {
             self.selected = None;
         } else if let Some(selected) = self.selected {
+            let selected = if deleted_index < selected { selected - 1 } else { selected };
             self.selected = Some(selected.min(self.filtered_commands.len() - 1));
         }
     }
@@ -482,6 +1004,8 @@ impl<'a> App<'a> {
         if let Some(selected) = self.selected {
             if selected < self.filtered_commands.len() - 1 {
                 self.selected = Some(selected + 1);
+            } else if self.wrap_navigation {
+                self.selected = Some(0);
             }
         } else if !self.filtered_commands.is_empty() {
             self.selected = Some(0);
@@ -492,6 +1016,8 @@ impl<'a> App<'a> {
         if let Some(selected) = self.selected {
             if selected > 0 {
                 self.selected = Some(selected - 1);
+            } else if self.wrap_navigation && !self.filtered_commands.is_empty() {
+                self.selected = Some(self.filtered_commands.len() - 1);
             }
         } else if !self.filtered_commands.is_empty() {
             self.selected = Some(self.filtered_commands.len() - 1);
@@ -504,6 +1030,26 @@ impl<'a> App<'a> {
             .and_then(|&idx| self.commands.get(idx))
     }
 
+    /// Summarizes the parameters the selected command will prompt for,
+    /// e.g. `Parameters: message (Commit message), branch`.
+    /// 
+    /// Returns `None` if nothing is selected or the command has no parameters.
+    pub fn parameter_summary(&self) -> Option<String> {
+        let command = self.get_selected_command()?;
+        let parameters = parse_parameters(&command.command);
+        if parameters.is_empty() {
+            return None;
+        }
+
+        let names: Vec<String> = parameters.iter()
+            .map(|p| match &p.description {
+                Some(desc) => format!("{} ({})", p.name, desc),
+                None => p.name.clone(),
+            })
+            .collect();
+        Some(format!("Parameters: {}", names.join(", ")))
+    }
+
     pub fn get_selected_index(&self) -> Option<usize> {
         self.selected
             .and_then(|selected| self.filtered_commands.get(selected))
@@ -511,25 +1057,34 @@ impl<'a> App<'a> {
     }
 
     fn execute_selected_command(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
-        if let Some(cmd) = self.get_selected_command() {
+        if let Some(cmd) = self.get_selected_command().cloned() {
             // Exit TUI temporarily
             restore_terminal(terminal)?;
             
             // Re-enable colors after restoring terminal
-            colored::control::set_override(true);
-
-            // If command has parameters, substitute them with user input
-            let current_params = parse_parameters(&cmd.command);
-            let final_command = substitute_parameters(&cmd.command, &current_params, None)?;
-            let ctx = ExecutionContext {
-                command: final_command,
-                directory: cmd.directory.clone(),
-                test_mode: false,
-                debug_mode: self.debug_mode,
-            };
-            execute_shell_command(&ctx)?;
-            
-            return Ok(());
+            colored::control::set_override(crate::config::color_enabled());
+
+            return execute_command(self.db, &cmd, self.debug_mode);
+        }
+        Ok(())
+    }
+
+    /// Executes the selected command, then waits for Enter and re-enters the
+    /// TUI with the same selection.
+    fn execute_and_return(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+        let result = self.execute_selected_command(terminal);
+
+        println!("\nPress Enter to return to the list...");
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+
+        let mut new_terminal = setup_terminal()?;
+        new_terminal.clear()?;
+        *terminal = new_terminal;
+
+        match result {
+            Ok(()) => self.set_success_message("Command executed".to_string()),
+            Err(e) => self.set_error_message(format!("Failed to execute command: {}", e)),
         }
         Ok(())
     }
@@ -543,9 +1098,12 @@ impl<'a> App<'a> {
                             match self.db.delete_command(command_id) {
                                 Ok(_) => {
                                     self.commands.remove(idx);
+                                    self.total_commands -= 1;
                                     self.set_success_message("Command deleted successfully".to_string());
                                     self.update_filtered_commands();
-                                    self.update_selection_after_delete(idx);
+                                    // `update_filtered_commands` may have clamped the selection
+                                    self.selected = Some(selected);
+                                    self.update_selection_after_delete(selected);
                                 }
                                 Err(e) => {
                                     self.set_error_message(format!("Failed to delete command: {}", e));
@@ -560,6 +1118,53 @@ impl<'a> App<'a> {
         Ok(())
     }
 
+    /// Saves an edit of the command at `idx` in `commands`.
+    /// 
+    /// The stored command is only replaced once the database update has
+    /// succeeded, so a failed or rejected edit leaves the list as it was.
+    pub fn apply_edit(&mut self, idx: usize, new_command: String, new_tags: Vec<String>, new_directory: String) {
+        let Some(cmd) = self.commands.get(idx) else {
+            return;
+        };
+        if new_command.trim().is_empty() {
+            self.set_error_message("Cannot save an empty command".to_string());
+            return;
+        }
+        if let Err(e) = check_command_length(&new_command) {
+            self.set_error_message(e.to_string());
+            return;
+        }
+        // Keep the old directory if the field was cleared
+        let directory = if new_directory.trim().is_empty() {
+            cmd.directory.clone()
+        } else {
+            new_directory
+        };
+        let updated_cmd = Command {
+            id: cmd.id,
+            command: new_command.clone(),
+            timestamp: Utc::now(),
+            directory,
+            tags: new_tags,
+            parameters: reconcile_parameters(&new_command, &cmd.parameters),
+            note: cmd.note.clone(),
+            alias: cmd.alias.clone(),
+            shell: cmd.shell.clone(),
+            interactive: cmd.interactive,
+            hostname: cmd.hostname.clone(),
+            created_at: cmd.created_at,
+            confirm_before_run: cmd.confirm_before_run,
+        };
+
+        match self.db.update_command(&updated_cmd) {
+            Ok(()) => {
+                self.commands[idx] = updated_cmd;
+                self.set_success_message("Command updated successfully!".to_string());
+            }
+            Err(e) => self.set_error_message(format!("Failed to update command: {}", e)),
+        }
+    }
+
     fn edit_selected_command(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
         if let Some(selected) = self.get_selection() {
             if let Some(&idx) = self.filtered_commands.get(selected) {
@@ -571,6 +1176,8 @@ impl<'a> App<'a> {
                     let mut add_app = AddCommandApp::new();
                     add_app.set_command(cmd.command.clone());
                     add_app.set_tags(cmd.tags.clone());
+                    add_app.set_directory(cmd.directory.clone());
+                    add_app.set_known_tags(self.db.list_tags()?.into_iter().map(|(tag, _)| tag).collect());
                     
                     let result = add_app.run();
                     
@@ -581,26 +1188,8 @@ impl<'a> App<'a> {
                     terminal.draw(|f| self.ui(f))?;
                     
                     match result {
-                        Ok(Some((new_command, new_tags, _))) => {
-                            // Update command
-                            let updated_cmd = Command {
-                                id: cmd.id,
-                                command: new_command.clone(),
-                                timestamp: cmd.timestamp,
-                                directory: cmd.directory.clone(),
-                                tags: new_tags,
-                                parameters: crate::utils::params::parse_parameters(&new_command),
-                            };
-                            
-                            if let Err(e) = self.db.update_command(&updated_cmd) {
-                                self.set_error_message(format!("Failed to update command: {}", e));
-                            } else {
-                                // Update local command list
-                                if let Some(cmd) = self.commands.get_mut(idx) {
-                                    *cmd = updated_cmd;
-                                }
-                                self.set_success_message("Command updated successfully!".to_string());
-                            }
+                        Ok(Some((new_command, new_tags, new_directory, _))) => {
+                            self.apply_edit(idx, new_command, new_tags, new_directory);
                         }
                         Ok(None) => {
                             self.set_message("Edit cancelled".to_string(), Color::Yellow);
@@ -614,6 +1203,29 @@ impl<'a> App<'a> {
         }
         Ok(())
     }
+
+    fn edit_selected_in_editor(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+        let Some(&idx) = self.get_selection().and_then(|selected| self.filtered_commands.get(selected)) else {
+            return Ok(());
+        };
+        let Some(cmd) = self.commands.get(idx).cloned() else {
+            return Ok(());
+        };
+
+        // Hand the terminal to the editor
+        restore_terminal(terminal)?;
+        let result = edit_in_editor(&cmd.command);
+        let mut new_terminal = setup_terminal()?;
+        new_terminal.clear()?;
+        *terminal = new_terminal;
+
+        match result {
+            Ok(Some(new_command)) => self.apply_edit(idx, new_command, cmd.tags, cmd.directory),
+            Ok(None) => self.set_message("Edit cancelled".to_string(), Color::Yellow),
+            Err(e) => self.set_error_message(format!("Error during edit: {}", e)),
+        }
+        Ok(())
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -642,11 +1254,72 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result
     terminal.show_cursor()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     disable_raw_mode()?;
-    colored::control::set_override(true);
+    colored::control::set_override(crate::config::color_enabled());
+    Ok(())
+}
+
+/// Runs a command picked from the list: prompts for its parameters (and what
+/// to do if its directory is gone), records the run and executes it.
+pub fn execute_command(db: &mut Database, cmd: &Command, debug_mode: bool) -> Result<()> {
+    let mut directory = cmd.directory.clone();
+    if !resolve_directory(&directory).exists() {
+        match prompt_missing_directory(&directory)? {
+            MissingDirectory::UseCurrent => directory = std::env::current_dir()?.to_string_lossy().to_string(),
+            MissingDirectory::Create => std::fs::create_dir_all(resolve_directory(&directory))?,
+            MissingDirectory::Cancel => return Err(anyhow::anyhow!("Directory does not exist: {}", directory)),
+        }
+    }
+
+    // If command has parameters, substitute them with user input
+    let final_command = substitute_parameters_with_history(db, cmd.id, &cmd.command, None)?;
+    if cmd.confirm_before_run && !confirm_run(&final_command.display)? {
+        return Err(anyhow::anyhow!("Operation cancelled by user"));
+    }
+    let ctx = ExecutionContext {
+        command: final_command.command,
+        directory,
+        test_mode: false,
+        debug_mode,
+        shell: cmd.shell.clone(),
+    };
+    if let Some(id) = cmd.id {
+        db.record_run(id)?;
+    }
+    execute_shell_command(&ctx)?;
     Ok(())
 }
 
+/// `clipboard` setting that copies through the terminal instead of a command.
+pub const OSC52_CLIPBOARD: &str = "osc52";
+
 pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let clipboard = crate::config::get().clipboard.as_deref();
+    // Over SSH the local tools would copy on the remote machine, if they work at all
+    if clipboard == Some(OSC52_CLIPBOARD) || (clipboard.is_none() && is_ssh_session()) {
+        use std::io::Write;
+        let mut stdout = io::stdout();
+        stdout.write_all(osc52_sequence(text).as_bytes())?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    // A configured clipboard command replaces the platform default
+    if let Some(clipboard) = clipboard {
+        use std::io::Write;
+        let parts = shlex::split(clipboard)
+            .filter(|parts| !parts.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Invalid clipboard command: {}", clipboard))?;
+        let mut child = std::process::Command::new(&parts[0])
+            .args(&parts[1..])
+            .stdin(std::process::Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        child.wait()?;
+        return Ok(());
+    }
+
     #[cfg(target_os = "macos")]
     {
         use std::process::Command;
@@ -681,3 +1354,33 @@ pub fn copy_to_clipboard(text: &str) -> Result<()> {
     
     Ok(())
 }
+
+/// Whether we're running in an SSH session.
+pub fn is_ssh_session() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|value| !value.is_empty()))
+}
+
+/// The OSC 52 escape sequence that asks the terminal to put `text` on the clipboard.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,149 @@
+//! Helpers for fitting commands into the width of the TUI list.
+
+use chrono::{DateTime, Utc};
+use ratatui::style::Color;
+
+use crate::db::Command;
+use crate::utils::time::humanize_ago_from;
+
+/// Shown in place of line breaks when a command is collapsed onto one line.
+pub const NEWLINE_MARKER: &str = " ⏎ ";
+
+/// Collapses a multi-line command onto a single line, marking each line break.
+pub fn collapse_lines(command: &str) -> String {
+    command
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(NEWLINE_MARKER)
+}
+
+/// Truncates `text` to at most `max_width` characters, ending with `…` if anything was cut.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.chars().count() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut truncated: String = text.chars().take(max_width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Splits a command into lines of at most `width` characters, keeping its own line breaks.
+pub fn wrap_to_width(command: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for line in command.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(width) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+/// Splits a command into its lines, indenting every line after the first by `indent`
+/// characters so they line up under a prefix such as the timestamp.
+pub fn indent_continuation(command: &str, indent: usize) -> Vec<String> {
+    let padding = " ".repeat(indent);
+    let mut lines: Vec<String> = command
+        .lines()
+        .enumerate()
+        .map(|(i, line)| if i == 0 { line.to_string() } else { format!("{}{}", padding, line) })
+        .collect();
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Writes a directory under the home directory as `~/...`, to keep it short in the list.
+pub fn shorten_home(directory: &str) -> String {
+    let Some(home) = dirs::home_dir() else {
+        return directory.to_string();
+    };
+    match std::path::Path::new(directory).strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.display()),
+        Err(_) => directory.to_string(),
+    }
+}
+
+/// Splits `text` into segments, flagging those that match `filter`.
+/// 
+/// Every occurrence is flagged, ignoring case unless `case_sensitive` is set.
+/// An empty filter gives back the whole text unflagged.
+pub fn highlight_segments(text: &str, filter: &str, case_sensitive: bool) -> Vec<(String, bool)> {
+    let chars: Vec<char> = text.chars().collect();
+    let filter: Vec<char> = filter.chars().collect();
+    if filter.is_empty() || filter.len() > chars.len() {
+        return vec![(text.to_string(), false)];
+    }
+
+    let matches_at = |start: usize| {
+        chars[start..start + filter.len()]
+            .iter()
+            .zip(&filter)
+            .all(|(a, b)| if case_sensitive { a == b } else { a.to_lowercase().eq(b.to_lowercase()) })
+    };
+
+    let mut segments: Vec<(String, bool)> = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + filter.len() <= chars.len() && matches_at(i) {
+            if !plain.is_empty() {
+                segments.push((std::mem::take(&mut plain), false));
+            }
+            segments.push((chars[i..i + filter.len()].iter().collect(), true));
+            i += filter.len();
+        } else {
+            plain.push(chars[i]);
+            i += 1;
+        }
+    }
+    if !plain.is_empty() {
+        segments.push((plain, false));
+    }
+    segments
+}
+
+/// Parses a color name such as `red`, `light-blue`, `208` or `#ff8800`.
+pub fn parse_color(name: &str) -> Option<Color> {
+    name.trim().parse().ok()
+}
+
+/// Summarizes which part of the vault is shown, e.g. "showing 50 of 1200, IDs 3-97".
+pub fn count_summary(commands: &[Command], total: i64) -> String {
+    let ids = commands.iter().filter_map(|cmd| cmd.id);
+    match (ids.clone().min(), ids.max()) {
+        (Some(first), Some(last)) if first != last => {
+            format!("showing {} of {}, IDs {}-{}", commands.len(), total, first, last)
+        }
+        (Some(id), Some(_)) => format!("showing {} of {}, ID {}", commands.len(), total, id),
+        _ => format!("showing {} of {}", commands.len(), total),
+    }
+}
+
+/// Describes where and when the selected command was added, and when it was
+/// last edited, e.g. "added 3d ago on build-box, updated 2h ago". `None` if
+/// there's nothing to say beyond the time already in its row.
+pub fn history_summary(cmd: &Command, now: DateTime<Utc>) -> Option<String> {
+    let host = cmd.hostname.as_ref().map(|host| format!(" on {}", host)).unwrap_or_default();
+    match cmd.created_at.filter(|created| *created != cmd.timestamp) {
+        Some(created) => Some(format!(
+            "added {}{}, updated {}",
+            humanize_ago_from(created, now),
+            host,
+            humanize_ago_from(cmd.timestamp, now)
+        )),
+        None if cmd.hostname.is_some() => Some(format!("added{}", host)),
+        None => None,
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use std::process::Command as ProcessCommand;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{anyhow, Result};
+
+/// Returns the editor to use: `$VISUAL`, then `$EDITOR`, then `vi`.
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Opens `text` in the user's editor and returns what was saved.
+///
+/// Returns `None` if the edit was abandoned: the file was emptied, left
+/// unchanged, or the editor exited with an error (e.g. `:cq` in vim).
+pub fn edit_in_editor(text: &str) -> Result<Option<String>> {
+    edit_with(&editor_command(), text)
+}
+
+/// Like `edit_in_editor`, using `editor` (which may include arguments, e.g. `code --wait`).
+pub fn edit_with(editor: &str, text: &str) -> Result<Option<String>> {
+    let mut args = shlex::split(editor)
+        .filter(|args| !args.is_empty())
+        .ok_or_else(|| anyhow!("Invalid editor command: {}", editor))?;
+    let program = args.remove(0);
+
+    // `.sh` so editors pick shell syntax highlighting
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let path = std::env::temp_dir().join(format!("command-vault-{}-{}.sh", std::process::id(), nanos));
+    fs::write(&path, format!("{}\n", text))?;
+
+    let status = ProcessCommand::new(&program).args(&args).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status.map_err(|e| anyhow!("Failed to start editor '{}': {}", program, e))?;
+    if !status.success() {
+        return Ok(None);
+    }
+    // Editors add a final newline; newlines inside the command are kept
+    let edited = edited?.trim_end_matches(['\n', '\r']).to_string();
+    if edited.trim().is_empty() || edited == text {
+        return Ok(None);
+    }
+    Ok(Some(edited))
+}
@@ -0,0 +1,130 @@
+//! Configurable key bindings for the command list.
+//!
+//! Keys are written as a single character (`"j"`, `"/"`) or the name of a
+//! special key (`"enter"`, `"down"`, `"pagedown"`, ...), in the `[keys]`
+//! table of the config file.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Next,
+    Prev,
+    Delete,
+    Edit,
+    Copy,
+    Execute,
+    Filter,
+    Help,
+}
+
+/// Keys for each action. An action can have several keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyBindings {
+    pub next: Vec<String>,
+    pub prev: Vec<String>,
+    pub delete: Vec<String>,
+    pub edit: Vec<String>,
+    pub copy: Vec<String>,
+    pub execute: Vec<String>,
+    pub filter: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect();
+        KeyBindings {
+            next: keys(&["down", "j"]),
+            prev: keys(&["up", "k"]),
+            delete: keys(&["d"]),
+            edit: keys(&["e"]),
+            copy: keys(&["c", "y"]),
+            execute: keys(&["enter"]),
+            filter: keys(&["/"]),
+            help: keys(&["?"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    fn bindings(&self) -> [(Action, &'static str, &[String]); 8] {
+        [
+            (Action::Next, "next", &self.next),
+            (Action::Prev, "prev", &self.prev),
+            (Action::Delete, "delete", &self.delete),
+            (Action::Edit, "edit", &self.edit),
+            (Action::Copy, "copy", &self.copy),
+            (Action::Execute, "execute", &self.execute),
+            (Action::Filter, "filter", &self.filter),
+            (Action::Help, "help", &self.help),
+        ]
+    }
+
+    /// Checks that every key can be parsed, so typos show up when the config is loaded.
+    pub fn validate(&self) -> Result<()> {
+        for (_, name, keys) in self.bindings() {
+            if let Some(key) = keys.iter().find(|key| parse_key(key).is_none()) {
+                return Err(anyhow!("Unknown key '{}' in keys.{}", key, name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The action bound to `key`, if any. Keys pressed with Ctrl or Alt are never bound.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return None;
+        }
+        self.bindings()
+            .into_iter()
+            .find(|(_, _, keys)| keys.iter().any(|spec| parse_key(spec) == Some(key.code)))
+            .map(|(action, _, _)| action)
+    }
+
+    /// The keys bound to `action` for the help screen, e.g. `↓/j`.
+    pub fn label(&self, action: Action) -> String {
+        let (_, _, keys) = self
+            .bindings()
+            .into_iter()
+            .find(|(bound, _, _)| *bound == action)
+            .expect("every action has bindings");
+        keys.iter()
+            .map(|key| match key.to_lowercase().as_str() {
+                "up" => "↑".to_string(),
+                "down" => "↓".to_string(),
+                "enter" => "Enter".to_string(),
+                _ => key.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Parses a key as written in the config: a single character or a key name.
+pub fn parse_key(spec: &str) -> Option<KeyCode> {
+    let mut chars = spec.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match spec.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Char(' '),
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" => KeyCode::Delete,
+        _ => return None,
+    };
+    Some(code)
+}
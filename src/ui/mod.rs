@@ -1,5 +1,11 @@
 pub mod app;
 pub mod add;
+pub mod display;
+pub mod editor;
+pub mod keys;
+pub mod panic;
+pub mod picker;
+pub mod state;
 
 pub use app::App;
 pub use add::AddCommandApp;
@@ -0,0 +1,68 @@
+//! Terminal restoration when the TUI panics
+//!
+//! A panic while the TUI is running would otherwise leave the terminal in raw
+//! mode on the alternate screen, which makes the user's shell unusable.
+
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Arc;
+use crossterm::{
+    cursor::Show,
+    event::DisableBracketedPaste,
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Runs a cleanup step before the panic hook that was installed when it was
+/// created, and puts that hook back when dropped.
+pub struct PanicHookGuard {
+    previous: Option<Arc<PanicHook>>,
+}
+
+impl PanicHookGuard {
+    /// Installs a hook that restores the terminal before the previous hook
+    /// reports the panic.
+    pub fn install() -> PanicHookGuard {
+        PanicHookGuard::with_cleanup(reset_terminal)
+    }
+
+    /// Installs a hook that calls `cleanup` before the previous hook.
+    pub fn with_cleanup<F>(cleanup: F) -> PanicHookGuard
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let previous = Arc::new(panic::take_hook());
+        let chained = Arc::clone(&previous);
+        panic::set_hook(Box::new(move |info| {
+            cleanup();
+            chained(info);
+        }));
+        PanicHookGuard { previous: Some(previous) }
+    }
+}
+
+impl Drop for PanicHookGuard {
+    fn drop(&mut self) {
+        // The hook can't be changed while unwinding; ours stays in place, which is harmless
+        if std::thread::panicking() {
+            return;
+        }
+
+        // Dropping our hook releases its handle on the previous one
+        drop(panic::take_hook());
+        if let Some(previous) = self.previous.take() {
+            if let Ok(previous) = Arc::try_unwrap(previous) {
+                panic::set_hook(previous);
+            }
+        }
+    }
+}
+
+/// Leaves raw mode, bracketed paste and the alternate screen, ignoring errors: the terminal may
+/// already be restored.
+fn reset_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), DisableBracketedPaste, LeaveAlternateScreen, Show);
+}
@@ -0,0 +1,56 @@
+//! Line-based command picker, for terminals the TUI can't run in.
+
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Select};
+
+use crate::db::{Command, Database};
+use crate::ui::app::execute_command;
+use crate::ui::display::{collapse_lines, truncate_to_width};
+
+/// Environment variable that makes `ls` and `search` use the picker instead of the TUI.
+pub const SIMPLE_UI_ENV_VAR: &str = "COMMAND_VAULT_SIMPLE_UI";
+
+/// Longest command preview shown in the picker.
+const MAX_LABEL_WIDTH: usize = 100;
+
+/// Whether the picker was asked for instead of the TUI.
+pub fn simple_ui_requested() -> bool {
+    std::env::var(SIMPLE_UI_ENV_VAR).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// The line the picker shows for `cmd`, e.g. `12: git push  [git]`.
+pub fn picker_label(cmd: &Command) -> String {
+    let mut label = format!(
+        "{}: {}",
+        cmd.id.unwrap_or(0),
+        truncate_to_width(&collapse_lines(&cmd.command), MAX_LABEL_WIDTH)
+    );
+    if !cmd.tags.is_empty() {
+        label.push_str(&format!("  [{}]", cmd.tags.join(", ")));
+    }
+    label
+}
+
+/// Asks which of `commands` to run. Returns `None` if the user backs out with Esc or q.
+pub fn pick_command(commands: &[Command]) -> Result<Option<usize>> {
+    let labels: Vec<String> = commands.iter().map(picker_label).collect();
+    Ok(Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Command to execute (Esc to cancel)")
+        .items(&labels)
+        .default(0)
+        .max_length(20)
+        .interact_opt()?)
+}
+
+/// Lets the user pick one of `commands` and executes it the way the TUI does.
+/// With `stay`, asks again after each command until the user backs out.
+pub fn run_picker(commands: &[Command], db: &mut Database, debug_mode: bool, stay: bool) -> Result<()> {
+    while let Some(index) = pick_command(commands)? {
+        execute_command(db, &commands[index], debug_mode)?;
+        if !stay {
+            break;
+        }
+        println!();
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! TUI state that is remembered between sessions: the filter and the list's columns
+//!
+//! Only enabled when `$COMMAND_VAULT_REMEMBER_FILTER` or the `remember_filter`
+//! setting is set, so the TUI behaves the same as always (and tests never touch
+//! the state file) by default.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::db::path::resolve_data_dir;
+
+/// Environment variable that turns on remembering the last filter.
+pub const REMEMBER_FILTER_ENV_VAR: &str = "COMMAND_VAULT_REMEMBER_FILTER";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiState {
+    /// Filter text the list was left with
+    #[serde(default)]
+    pub filter_text: String,
+    /// Columns the list was showing
+    #[serde(default)]
+    pub columns: ColumnSet,
+}
+
+/// Which parts of each row the command list shows, besides the command itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnSet {
+    pub id: bool,
+    pub time: bool,
+    pub directory: bool,
+    pub tags: bool,
+}
+
+impl Default for ColumnSet {
+    fn default() -> Self {
+        ColumnSet { id: true, time: true, directory: false, tags: true }
+    }
+}
+
+impl UiState {
+    /// Loads the state from `path`, falling back to the default if it is missing or unreadable.
+    pub fn load(path: &Path) -> UiState {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Saves the state to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Returns where the TUI state is kept, or `None` if remembering it is turned off.
+pub fn state_path() -> Option<PathBuf> {
+    let enabled = std::env::var(REMEMBER_FILTER_ENV_VAR)
+        .map(|v| !v.is_empty() && v != "0")
+        .unwrap_or(false)
+        || crate::config::get().remember_filter;
+    if !enabled {
+        return None;
+    }
+
+    Some(resolve_data_dir().ok()?.join("state.json"))
+}
@@ -0,0 +1,87 @@
+//! Parsing shell history files for `import-history`.
+
+use std::collections::HashSet;
+
+/// Commands that aren't worth keeping unless `--all` is given.
+const TRIVIAL_COMMANDS: &[&str] = &[
+    "ls", "ll", "la", "l", "cd", "pwd", "clear", "exit", "history", "true", "false",
+];
+
+/// Parses the contents of a bash or zsh history file into commands, oldest first.
+///
+/// Handles plain bash history, bash's `#<timestamp>` lines (written when
+/// `HISTTIMEFORMAT` is set), and zsh's extended `: <start>:<elapsed>;<command>`
+/// format. Lines zsh continued with a trailing `\` are joined into one
+/// multi-line command.
+pub fn parse_history(contents: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut pending: Option<String> = None;
+
+    for line in contents.lines() {
+        let line = match pending.take() {
+            Some(mut command) => {
+                command.push('\n');
+                command.push_str(line);
+                command
+            }
+            None => {
+                if is_bash_timestamp(line) {
+                    continue;
+                }
+                strip_zsh_prefix(line).to_string()
+            }
+        };
+
+        match line.strip_suffix('\\') {
+            Some(continued) => pending = Some(continued.to_string()),
+            None => commands.push(line),
+        }
+    }
+    commands.extend(pending);
+
+    commands
+        .into_iter()
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Picks the commands to import: the most recent occurrence of each, without trivial ones
+/// unless `all` is set, capped at `limit`. Oldest first, like the history itself.
+pub fn select_commands(commands: Vec<String>, limit: Option<usize>, all: bool) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut selected: Vec<String> = commands
+        .into_iter()
+        .rev()
+        .filter(|command| all || !is_trivial(command))
+        .filter(|command| seen.insert(command.clone()))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    selected.reverse();
+    selected
+}
+
+/// Whether `command` is something like `ls` or `cd ..` that isn't worth storing.
+pub fn is_trivial(command: &str) -> bool {
+    command
+        .split_whitespace()
+        .next()
+        .is_none_or(|program| TRIVIAL_COMMANDS.contains(&program))
+}
+
+/// `#1700000000` lines that bash writes before each command when `HISTTIMEFORMAT` is set.
+fn is_bash_timestamp(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Strips zsh's extended history prefix, `: <start>:<elapsed>;`.
+fn strip_zsh_prefix(line: &str) -> &str {
+    let Some(rest) = line.strip_prefix(": ") else {
+        return line;
+    };
+    match rest.split_once(';') {
+        Some((times, command)) if times.chars().all(|c| c.is_ascii_digit() || c == ':') => command,
+        _ => line,
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{anyhow, Result};
+
+/// Default maximum command length, in bytes.
+pub const DEFAULT_MAX_COMMAND_LENGTH: usize = 16 * 1024;
+
+/// Environment variable overriding the maximum command length (`0` disables the limit).
+pub const MAX_COMMAND_LENGTH_ENV_VAR: &str = "COMMAND_VAULT_MAX_COMMAND_LENGTH";
+
+/// Returns the configured maximum command length, or `None` if the limit is disabled.
+pub fn max_command_length() -> Option<usize> {
+    let limit = std::env::var(MAX_COMMAND_LENGTH_ENV_VAR)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_COMMAND_LENGTH);
+    (limit > 0).then_some(limit)
+}
+
+/// Checks a command against the configured maximum length.
+pub fn check_command_length(command: &str) -> Result<()> {
+    check_command_length_with(command, max_command_length())
+}
+
+/// Checks a command against an explicit maximum length (`None` for no limit).
+pub fn check_command_length_with(command: &str, limit: Option<usize>) -> Result<()> {
+    match limit {
+        Some(limit) if command.len() > limit => Err(anyhow!(
+            "Command is too long ({} bytes, maximum is {} bytes). Set {} to change the limit, or 0 to disable it",
+            command.len(),
+            limit,
+            MAX_COMMAND_LENGTH_ENV_VAR
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Maximum size of a command's saved output, in bytes.
+pub const MAX_OUTPUT_LENGTH: usize = 64 * 1024;
+
+/// Cuts `output` down to `MAX_OUTPUT_LENGTH` bytes, on a character boundary.
+pub fn truncate_output(output: &str) -> &str {
+    if output.len() <= MAX_OUTPUT_LENGTH {
+        return output;
+    }
+    let mut end = MAX_OUTPUT_LENGTH;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    &output[..end]
+}
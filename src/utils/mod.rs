@@ -1,2 +1,8 @@
 pub mod time;
 pub mod params;
+pub mod prompt;
+pub mod limits;
+pub mod quote;
+pub mod tags;
+pub mod history;
+pub mod host;
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::*;
 use crossterm::{
     cursor::MoveTo,
@@ -7,112 +7,337 @@ use crossterm::{
     style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
 };
-use regex::Regex;
+use regex::{Captures, Regex};
 use std::{
     collections::HashMap,
     io::{stdout, Stdout, Write},
 };
 
-use crate::db::models::Parameter;
+use crate::db::{models::Parameter, Command, Database};
 
+/// Ends the description of a secret parameter, as in `@token:API token!secret`.
+const SECRET_SUFFIX: &str = "!secret";
+
+/// Finds the `@name` parameters in `command`. `@@` is a literal `@`, so `@@name` isn't one.
+/// 
+/// `@name!` and `@name:description!secret` mark the parameter as secret.
 pub fn parse_parameters(command: &str) -> Vec<Parameter> {
-    let re = Regex::new(r"@([a-zA-Z_][a-zA-Z0-9_]*)(?::([^@\s][^@]*))?").unwrap();
+    let re = Regex::new(r"@@|@([a-zA-Z_][a-zA-Z0-9_]*)(?::([^@\s][^@]*)|(!))?").unwrap();
     let mut parameters = Vec::new();
     
     for cap in re.captures_iter(command) {
-        let name = cap[1].to_string();
-        let description = cap.get(2).map(|m| {
+        let Some(name) = cap.get(1) else { continue };
+        let name = name.as_str().to_string();
+        let mut secret = cap.get(3).is_some();
+        let description = cap.get(2).and_then(|m| {
             let desc = m.as_str().trim_end();
-            if let Some(space_pos) = desc.find(char::is_whitespace) {
+            let desc = if let Some(space_pos) = desc.find(char::is_whitespace) {
                 &desc[..space_pos]
             } else {
                 desc
-            }.to_string()
+            };
+            let desc = match desc.strip_suffix(SECRET_SUFFIX) {
+                Some(desc) => {
+                    secret = true;
+                    desc
+                }
+                None => desc,
+            };
+            (!desc.is_empty()).then(|| desc.to_string())
         });
-        parameters.push(Parameter::with_description(name, description));
+        // `@name:/regex/` gives a pattern instead of a description
+        let pattern = description.as_deref()
+            .and_then(|desc| desc.strip_prefix('/')?.strip_suffix('/'))
+            .filter(|pattern| !pattern.is_empty())
+            .map(str::to_string);
+        let mut param = match pattern {
+            Some(pattern) => Parameter { name, description: None, pattern: Some(pattern), default_value: None, secret: false },
+            None => Parameter::with_description(name, description),
+        };
+        param.secret = secret;
+        parameters.push(param);
     }
     
     parameters
 }
 
-pub fn substitute_parameters(command: &str, parameters: &[Parameter], test_input: Option<&str>) -> Result<String> {
-    let is_test = test_input.is_some() || std::env::var("COMMAND_VAULT_TEST").is_ok();
-    if is_test {
-        let mut final_command = command.to_string();
-        let test_values: Vec<&str> = if let Some(input) = test_input {
-            if input.is_empty() {
-                parameters.iter()
-                    .map(|p| p.description.as_deref().unwrap_or(""))
-                    .collect()
-            } else {
-                input.split('\n').collect()
-            }
-        } else {
-            // When no test input is provided, use descriptions
-            parameters.iter()
-                .map(|p| p.description.as_deref().unwrap_or(""))
-                .collect()
-        };
+/// Replaces each `@name` in `command` with its value from `values`, and each `@@` with `@`.
+/// 
+/// Parameters without a value are left as they are.
+pub fn fill_parameters(command: &str, values: &HashMap<String, String>) -> String {
+    let re = Regex::new(r"@@|@([a-zA-Z_][a-zA-Z0-9_]*)").unwrap();
+    re.replace_all(command, |cap: &Captures| match cap.get(1) {
+        Some(name) => values.get(name.as_str()).cloned().unwrap_or_else(|| cap[0].to_string()),
+        None => "@".to_string(),
+    })
+    .into_owned()
+}
+
+/// Substitutes the parameters that have a default value, leaving the others as written.
+pub fn fill_defaults(command: &str, parameters: &[Parameter]) -> String {
+    let mut values = HashMap::new();
+    for param in parameters {
+        if let Some(default) = &param.default_value {
+            values.entry(param.name.clone()).or_insert_with(|| quote_value(default, command));
+        }
+    }
+    fill_parameters(&strip_parameter_specs(command, parameters), &values)
+}
+
+/// Quotes a parameter value for substitution into `command` when the shell would otherwise split or
+/// interpret it.
+fn quote_value(value: &str, command: &str) -> String {
+    let needs_quotes = value.is_empty() || 
+                     value.contains(' ') || 
+                     value.contains('*') || 
+                     value.contains(';') ||
+                     value.contains('|') ||
+                     value.contains('>') ||
+                     value.contains('<') ||
+                     command.contains('>') ||
+                     command.contains('<') ||
+                     command.contains('|') ||
+                     command.starts_with("grep");
 
-        // First, remove all parameter descriptions from the command
-        for param in parameters {
-            if let Some(desc) = &param.description {
-                // Match the exact pattern including the @ symbol
-                let pattern = format!("@{}:{}", param.name, desc);
-                final_command = final_command.replace(&pattern, &format!("@{}", param.name));
+    if needs_quotes && !value.starts_with('\'') && !value.starts_with('"') {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Removes the `:description`, `:/pattern/` or secret marker after each parameter name in `command`.
+fn strip_parameter_specs(command: &str, parameters: &[Parameter]) -> String {
+    let mut result = command.to_string();
+    for param in parameters {
+        let pattern = param.pattern.as_ref().map(|pattern| format!("/{}/", pattern));
+        for spec in param.description.iter().chain(pattern.iter()) {
+            // Match the exact pattern including the @ symbol
+            if param.secret {
+                result = result.replace(&format!("@{}:{}{}", param.name, spec, SECRET_SUFFIX), &format!("@{}", param.name));
             }
+            result = result.replace(&format!("@{}:{}", param.name, spec), &format!("@{}", param.name));
         }
+        if param.secret {
+            result = result.replace(&format!("@{}:{}", param.name, SECRET_SUFFIX), &format!("@{}", param.name));
+            result = result.replace(&format!("@{}!", param.name), &format!("@{}", param.name));
+        }
+    }
+    result
+}
 
-        // Then replace parameters with values
-        for (i, param) in parameters.iter().enumerate() {
-            let value = if i < test_values.len() {
-                test_values[i]
-            } else {
-                param.description.as_deref().unwrap_or("")
-            };
+/// Shown in place of a secret parameter's value.
+pub const SECRET_MASK: &str = "****";
 
-            let needs_quotes = value.is_empty() || 
-                             value.contains(' ') || 
-                             value.contains('*') || 
-                             value.contains(';') ||
-                             value.contains('|') ||
-                             value.contains('>') ||
-                             value.contains('<') ||
-                             command.contains('>') ||
-                             command.contains('<') ||
-                             command.contains('|') ||
-                             final_command.starts_with("grep");
-
-            let quoted_value = if needs_quotes && !value.starts_with('\'') && !value.starts_with('"') {
-                format!("'{}'", value.replace('\'', "'\\''"))
-            } else {
-                value.to_string()
-            };
+/// Compiles the pattern of each parameter that has one, keyed by parameter name.
+fn compile_patterns(parameters: &[Parameter]) -> Result<HashMap<String, Regex>> {
+    let mut patterns = HashMap::new();
+    for param in parameters {
+        if let Some(pattern) = &param.pattern {
+            let regex = Regex::new(pattern)
+                .map_err(|e| anyhow!("Invalid pattern for parameter '{}': {}", param.name, e))?;
+            patterns.insert(param.name.clone(), regex);
+        }
+    }
+    Ok(patterns)
+}
 
-            final_command = final_command.replace(&format!("@{}", param.name), &quoted_value);
+/// Checks a value against the pattern of the parameter called `name`, if it has one.
+fn check_value(patterns: &HashMap<String, Regex>, name: &str, value: &str) -> Result<()> {
+    match patterns.get(name) {
+        Some(regex) if !regex.is_match(value) => Err(anyhow!(
+            "Value '{}' for parameter '{}' doesn't match /{}/",
+            value, name, regex.as_str()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reconciles a supplied parameter list with the parameters in the command text.
+/// 
+/// The text decides which parameters exist: supplied parameters it doesn't
+/// reference are dropped, and ones it references but the list lacks are
+/// added. Supplied parameters keep their order, their default value and,
+/// where the text gives none, their description.
+pub fn reconcile_parameters(command: &str, supplied: &[Parameter]) -> Vec<Parameter> {
+    let mut parsed = parse_parameters(command);
+    let mut parameters = Vec::new();
+
+    for param in supplied {
+        if let Some(pos) = parsed.iter().position(|p| p.name == param.name) {
+            let mut from_text = parsed.remove(pos);
+            if from_text.description.is_none() {
+                from_text.description = param.description.clone();
+            }
+            from_text.default_value = param.default_value.clone();
+            parameters.push(from_text);
         }
-        
-        if std::env::var("COMMAND_VAULT_DEBUG").is_ok() {
-            eprintln!("[DEBUG] Final result: {}", final_command);
+    }
+    parameters.extend(parsed);
+    parameters
+}
+
+pub fn substitute_parameters(command: &str, parameters: &[Parameter], test_input: Option<&str>) -> Result<String> {
+    let values = parameter_values(command, parameters, test_input, &HashMap::new())?;
+    Ok(fill_values(command, parameters, &values).command)
+}
+
+/// Gets a value for each parameter not in `preset`: from `test_input` in test
+/// mode, or by prompting. Returns them together with the `preset` ones.
+fn parameter_values(
+    command: &str,
+    parameters: &[Parameter],
+    test_input: Option<&str>,
+    preset: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let is_test = test_input.is_some() || std::env::var("COMMAND_VAULT_TEST").is_ok();
+    if !is_test {
+        return prompt_values(command, parameters, test_input, &HashMap::new(), preset);
+    }
+
+    let prompted: Vec<&Parameter> = parameters.iter().filter(|p| !preset.contains_key(&p.name)).collect();
+    let fallback = |p: &Parameter| p.default_value.clone().or(p.description.clone()).unwrap_or_default();
+    let test_values: Vec<String> = match test_input {
+        Some(input) if !input.is_empty() => input.split('\n').map(str::to_string).collect(),
+        // When no test input is provided, use defaults or descriptions
+        _ => prompted.iter().map(|p| fallback(p)).collect(),
+    };
+    let patterns = compile_patterns(parameters)?;
+
+    let mut values = preset.clone();
+    for (i, param) in prompted.iter().enumerate() {
+        let value = test_values.get(i).cloned().unwrap_or_else(|| fallback(param));
+        check_value(&patterns, &param.name, &value)?;
+
+        // A repeated parameter takes its first value everywhere
+        values.entry(param.name.clone()).or_insert(value);
+    }
+    Ok(values)
+}
+
+/// A command with its parameters filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// The command to run
+    pub command: String,
+    /// The command to show, with secret values masked
+    pub display: String,
+}
+
+/// A value being typed at a parameter prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueInput {
+    /// The text typed so far
+    pub value: String,
+    /// Cursor position, counted in characters rather than bytes
+    pub cursor: usize,
+}
+
+impl ValueInput {
+    /// Starts editing `value` with the cursor at its end.
+    pub fn new(value: String) -> Self {
+        let cursor = value.chars().count();
+        Self { value, cursor }
+    }
+
+    /// Applies a typed character, Backspace, Left or Right; other keys are ignored.
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => {
+                self.value.insert(self.byte_index(self.cursor), c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.value.remove(self.byte_index(self.cursor - 1));
+                self.cursor -= 1;
+            }
+            KeyCode::Left if self.cursor > 0 => {
+                self.cursor -= 1;
+            }
+            KeyCode::Right if self.cursor < self.value.chars().count() => {
+                self.cursor += 1;
+            }
+            _ => {}
         }
-        Ok(final_command)
-    } else {
-        prompt_parameters(command, parameters, test_input)
     }
+
+    /// Converts a char index in the value into a byte offset, as
+    /// `String::insert`/`remove` need.
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.value.len())
+    }
+}
+
+/// Substitutes `values` into `command` in one pass, removing the parameters'
+/// descriptions and turning `@@` into `@`. Values are quoted where needed.
+fn fill_values(command: &str, parameters: &[Parameter], values: &HashMap<String, String>) -> Substitution {
+    let stripped = strip_parameter_specs(command, parameters);
+    let quoted: HashMap<String, String> = values.iter()
+        .map(|(name, value)| (name.clone(), quote_value(value, command)))
+        .collect();
+    let mut masked = quoted.clone();
+    for param in parameters.iter().filter(|p| p.secret && values.contains_key(&p.name)) {
+        masked.insert(param.name.clone(), SECRET_MASK.to_string());
+    }
+    let substitution = Substitution {
+        command: fill_parameters(&stripped, &quoted),
+        display: fill_parameters(&stripped, &masked),
+    };
+    if std::env::var("COMMAND_VAULT_DEBUG").is_ok() {
+        eprintln!("[DEBUG] Final result: {}", substitution.display);
+    }
+    substitution
 }
 
 pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Option<&str>) -> Result<String> {
+    prompt_parameters_with_defaults(command, parameters, test_input, &HashMap::new())
+        .map(|(final_command, _)| final_command)
+}
+
+/// Prompts for parameter values, pre-filling each prompt with its value from `defaults`.
+/// 
+/// Returns the final command along with the value entered for each parameter.
+pub fn prompt_parameters_with_defaults(
+    command: &str,
+    parameters: &[Parameter],
+    test_input: Option<&str>,
+    defaults: &HashMap<String, String>,
+) -> Result<(String, HashMap<String, String>)> {
+    let values = prompt_values(command, parameters, test_input, defaults, &HashMap::new())?;
+    Ok((fill_values(command, parameters, &values).command, values))
+}
+
+/// Prompts for the parameters not in `preset`, like `prompt_parameters_with_defaults`.
+/// Returns the entered values together with the `preset` ones.
+fn prompt_values(
+    command: &str,
+    parameters: &[Parameter],
+    test_input: Option<&str>,
+    defaults: &HashMap<String, String>,
+    preset: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
     let is_test = test_input.is_some() || std::env::var("COMMAND_VAULT_TEST").is_ok();
-    let result = (|| -> Result<String> {
-        let mut param_values: HashMap<String, String> = HashMap::new();
+    let result = (|| -> Result<HashMap<String, String>> {
+        let mut param_values = preset.clone();
+        let patterns = compile_patterns(parameters)?;
+        let secrets: Vec<&String> = parameters.iter().filter(|p| p.secret).map(|p| &p.name).collect();
         
-        for param in parameters {
+        for param in parameters.iter().filter(|p| !preset.contains_key(&p.name)) {
             let value = if is_test {
-                if let Some(input) = test_input {
+                let value = if let Some(input) = test_input {
                     input.to_string()
+                } else if let Some(default) = defaults.get(&param.name) {
+                    default.clone()
                 } else {
-                    param.description.clone().unwrap_or_default()
-                }
+                    param.default_value.clone().or(param.description.clone()).unwrap_or_default()
+                };
+                // There's no one to ask again, so a bad value is an error
+                check_value(&patterns, &param.name, &value)?;
+                value
             } else {
                 enable_raw_mode()?;
                 let mut stdout = stdout();
@@ -120,10 +345,14 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                 
                 // Function to update the preview
                 let update_preview = |stdout: &mut Stdout, current_value: &str| -> Result<()> {
-                    let mut preview_command = command.to_string();
+                    let mut values = HashMap::new();
                     
                     // Add all previous parameter values
                     for (name, value) in &param_values {
+                        if secrets.contains(&name) {
+                            values.insert(name.clone(), SECRET_MASK.to_string());
+                            continue;
+                        }
                         let needs_quotes = value.is_empty() || 
                             value.contains(' ') || 
                             value.contains('*') || 
@@ -131,7 +360,7 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                             value.contains('|') ||
                             value.contains('>') ||
                             value.contains('<') ||
-                            preview_command.starts_with("grep");
+                            command.starts_with("grep");
 
                         let quoted_value = if needs_quotes && !value.starts_with('\'') && !value.starts_with('"') {
                             format!("'{}'", value.replace('\'', "'\\''"))
@@ -139,7 +368,7 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                             value.clone()
                         };
 
-                        preview_command = preview_command.replace(&format!("@{}", name), &quoted_value);
+                        values.insert(name.clone(), quoted_value);
                     }
 
                     // Add current parameter value
@@ -150,7 +379,7 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                         current_value.contains('|') ||
                         current_value.contains('>') ||
                         current_value.contains('<') ||
-                        preview_command.starts_with("grep");
+                        command.starts_with("grep");
 
                     let quoted_value = if needs_quotes && !current_value.starts_with('\'') && !current_value.starts_with('"') {
                         format!("'{}'", current_value.replace('\'', "'\\''"))
@@ -158,7 +387,12 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                         current_value.to_string()
                     };
 
-                    preview_command = preview_command.replace(&format!("@{}", param.name), &quoted_value);
+                    if param.secret {
+                        values.insert(param.name.clone(), SECRET_MASK.to_string());
+                    } else {
+                        values.insert(param.name.clone(), quoted_value);
+                    }
+                    let preview_command = fill_parameters(&strip_parameter_specs(command, parameters), &values);
 
                     stdout.queue(MoveTo(0, 0))?
                           .queue(Print("─".repeat(45).dimmed()))?;
@@ -176,8 +410,14 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                     Ok(())
                 };
 
+                // Start from the last value used, if any, or the parameter's default
+                let mut input = ValueInput::new(defaults.get(&param.name)
+                    .or(param.default_value.as_ref())
+                    .cloned()
+                    .unwrap_or_default());
+
                 // Initial display
-                update_preview(&mut stdout, "")?;
+                update_preview(&mut stdout, &input.value)?;
 
                 stdout.queue(MoveTo(0, 4))?
                       .queue(Print("─".repeat(45).dimmed()))?;
@@ -192,18 +432,32 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                               "Description".cyan().bold(), 
                               desc.white()
                           )))?;
+                } else if let Some(pattern) = &param.pattern {
+                    stdout.queue(MoveTo(0, 6))?
+                          .queue(Print(format!("{}: /{}/", 
+                              "Must match".cyan().bold(), 
+                              pattern.white()
+                          )))?;
                 }
+                // Secret values are shown as one `*` per character
+                let shown = |value: &str| if param.secret {
+                    "*".repeat(value.chars().count())
+                } else {
+                    value.to_string()
+                };
                 stdout.queue(MoveTo(0, 7))?
-                      .queue(Print(format!("{}: ", "Enter value".yellow().bold())))?;
+                      .queue(Print(format!("{}: {}", "Enter value".yellow().bold(), shown(&input.value))))?;
                 stdout.flush()?;
 
-                let mut value = String::new();
-                let mut cursor_pos = 0;
-
                 loop {
                     if let Event::Key(key) = event::read()? {
+                        let mut invalid = None;
                         match key.code {
-                            KeyCode::Enter => break,
+                            // Ask again until the value matches the parameter's pattern
+                            KeyCode::Enter => match check_value(&patterns, &param.name, &input.value) {
+                                Ok(()) => break,
+                                Err(e) => invalid = Some(e.to_string()),
+                            },
                             KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                                 // Handle Ctrl+C
                                 disable_raw_mode()?;
@@ -212,78 +466,36 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
                                 stdout.flush()?;
                                 return Err(anyhow::anyhow!("Operation cancelled by user"));
                             }
-                            KeyCode::Char(c) => {
-                                value.insert(cursor_pos, c);
-                                cursor_pos += 1;
-                            }
-                            KeyCode::Backspace if cursor_pos > 0 => {
-                                value.remove(cursor_pos - 1);
-                                cursor_pos -= 1;
-                            }
-                            KeyCode::Left if cursor_pos > 0 => {
-                                cursor_pos -= 1;
-                            }
-                            KeyCode::Right if cursor_pos < value.len() => {
-                                cursor_pos += 1;
-                            }
-                            _ => {}
+                            code => input.handle_key(code),
                         }
 
                         // Update command preview
-                        update_preview(&mut stdout, &value)?;
+                        update_preview(&mut stdout, &input.value)?;
 
                         // Redraw the value line
                         stdout.queue(MoveTo(0, 7))?
                               .queue(Clear(ClearType::CurrentLine))?
                               .queue(Print(format!("{}: {}", 
                                   "Enter value".yellow().bold(), 
-                                  value
+                                  shown(&input.value)
                               )))?;
-                        stdout.queue(MoveTo((cursor_pos + 13) as u16, 7))?;
+                        stdout.queue(MoveTo(0, 8))?
+                              .queue(Clear(ClearType::CurrentLine))?;
+                        if let Some(message) = &invalid {
+                            stdout.queue(Print(message.red()))?;
+                        }
+                        stdout.queue(MoveTo((input.cursor + 13) as u16, 7))?;
                         stdout.flush()?;
                     }
                 }
 
                 disable_raw_mode()?;
-                value
+                input.value
             };
 
             param_values.insert(param.name.clone(), value);
         }
 
-        // First, remove all parameter descriptions from the command
-        let mut final_command = command.to_string();
-        for param in parameters {
-            if let Some(desc) = &param.description {
-                // Match the exact pattern including the @ symbol
-                let pattern = format!("@{}:{}", param.name, desc);
-                final_command = final_command.replace(&pattern, &format!("@{}", param.name));
-            }
-        }
-
-        // Build final command with parameter values
-        for (name, value) in &param_values {
-            let needs_quotes = value.is_empty() || 
-                             value.contains(' ') || 
-                             value.contains('*') || 
-                             value.contains(';') ||
-                             value.contains('|') ||
-                             value.contains('>') ||
-                             value.contains('<') ||
-                             command.contains('>') ||
-                             command.contains('<') ||
-                             command.contains('|') ||
-                             final_command.starts_with("grep");
-
-            let quoted_value = if needs_quotes && !value.starts_with('\'') && !value.starts_with('"') {
-                format!("'{}'", value.replace('\'', "'\\''"))
-            } else {
-                value.clone()
-            };
-
-            final_command = final_command.replace(&format!("@{}", name), &quoted_value);
-        }
-
         if !is_test {
             let mut stdout = stdout();
             stdout.queue(Clear(ClearType::All))?;
@@ -291,7 +503,7 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
             stdout.flush()?;
         }
 
-        Ok(final_command)
+        Ok(param_values)
     })();
 
     if !is_test {
@@ -300,3 +512,104 @@ pub fn prompt_parameters(command: &str, parameters: &[Parameter], test_input: Op
 
     result
 }
+
+/// Substitutes a stored command's parameters, remembering the values used.
+/// 
+/// Interactive prompts are pre-filled with the value last used for each
+/// parameter of this command, and the entered values are saved for next time.
+/// Descriptions and defaults stored with the command are used too.
+/// In test mode this behaves exactly like `substitute_parameters`.
+pub fn substitute_parameters_with_history(
+    db: &mut Database,
+    command_id: Option<i64>,
+    command: &str,
+    test_input: Option<&str>,
+) -> Result<Substitution> {
+    substitute_stored_parameters(db, command_id, command, test_input, false)
+}
+
+/// Like `substitute_parameters_with_history`, but parameters with a default value
+/// take it without asking; only the ones without a default are prompted for.
+pub fn substitute_parameters_using_defaults(
+    db: &mut Database,
+    command_id: Option<i64>,
+    command: &str,
+    test_input: Option<&str>,
+) -> Result<Substitution> {
+    substitute_stored_parameters(db, command_id, command, test_input, true)
+}
+
+/// Substitutes a stored command's parameters without asking for anything, for
+/// callers with no terminal: every parameter takes its default value.
+/// 
+/// Fails if a parameter has no default, or if the command always prompts
+/// (`--interactive true`). Commands that never prompt (`--interactive false`)
+/// leave parameters without a default as written, as they do from `exec`.
+pub fn substitute_parameters_unattended(command: &Command) -> Result<Substitution> {
+    let parameters = reconcile_parameters(&command.command, &command.parameters);
+    let values = default_values(&parameters);
+    if command.interactive != Some(false) {
+        let always_prompts = command.interactive == Some(true) && !parameters.is_empty();
+        if let Some(param) = parameters.iter().find(|p| always_prompts || !values.contains_key(&p.name)) {
+            return Err(anyhow!("Parameter '{}' needs a value, which can't be asked for here", param.name));
+        }
+    }
+    Ok(fill_values(&command.command, &parameters, &values))
+}
+
+/// The default value of each parameter that has one, keyed by parameter name.
+fn default_values(parameters: &[Parameter]) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for param in parameters {
+        if let Some(default) = &param.default_value {
+            values.entry(param.name.clone()).or_insert_with(|| default.clone());
+        }
+    }
+    values
+}
+
+fn substitute_stored_parameters(
+    db: &mut Database,
+    command_id: Option<i64>,
+    command: &str,
+    test_input: Option<&str>,
+    use_defaults: bool,
+) -> Result<Substitution> {
+    let stored = match command_id {
+        Some(id) => db.get_command(id)?,
+        None => None,
+    };
+    let parameters = reconcile_parameters(command, stored.as_ref().map_or(&[], |cmd| &cmd.parameters));
+
+    // Never prompt: fill in the defaults and leave everything else as written
+    let never_prompt = stored.as_ref().and_then(|cmd| cmd.interactive) == Some(false);
+    let preset = if use_defaults || never_prompt { default_values(&parameters) } else { HashMap::new() };
+    if never_prompt {
+        return Ok(fill_values(command, &parameters, &preset));
+    }
+
+    let is_test = test_input.is_some() || std::env::var("COMMAND_VAULT_TEST").is_ok();
+    let command_id = match command_id {
+        Some(id) if !parameters.is_empty() && !is_test => id,
+        _ => {
+            let values = parameter_values(command, &parameters, test_input, &preset)?;
+            return Ok(fill_values(command, &parameters, &values));
+        }
+    };
+
+    // Secret values are never saved, so they're never pre-filled either
+    let mut defaults = HashMap::new();
+    for param in parameters.iter().filter(|param| !param.secret) {
+        if let Some(value) = db.last_param_value(command_id, &param.name)? {
+            defaults.insert(param.name.clone(), value);
+        }
+    }
+
+    let values = prompt_values(command, &parameters, None, &defaults, &preset)?;
+    for param in parameters.iter().filter(|param| !param.secret && !preset.contains_key(&param.name)) {
+        if let Some(value) = values.get(&param.name) {
+            db.record_param_value(command_id, &param.name, value)?;
+        }
+    }
+    Ok(fill_values(command, &parameters, &values))
+}
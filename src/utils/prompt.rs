@@ -0,0 +1,24 @@
+/// Shell prompt markers that are often pasted along with commands from docs.
+const PROMPT_MARKERS: [&str; 2] = ["$ ", "# "];
+
+/// Strips a single leading `$ ` or `# ` prompt marker from a command.
+///
+/// Returns the command without the marker, or `None` if it didn't start with one.
+pub fn strip_prompt_marker(command: &str) -> Option<&str> {
+    PROMPT_MARKERS
+        .iter()
+        .find_map(|marker| command.strip_prefix(marker))
+        .map(|rest| rest.trim_start())
+}
+
+/// Splits a prompt marker passed as its own argument (`add -- $ ls -la`) off the arguments.
+///
+/// Returns the marker, if there was one, and the remaining arguments.
+pub fn split_prompt_marker(args: &[String]) -> (Option<&str>, &[String]) {
+    match args.split_first() {
+        Some((first, rest)) if !rest.is_empty() && PROMPT_MARKERS.iter().any(|m| m.trim_end() == first) => {
+            (Some(first.as_str()), rest)
+        }
+        _ => (None, args),
+    }
+}
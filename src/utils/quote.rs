@@ -0,0 +1,15 @@
+use anyhow::{anyhow, Result};
+
+/// Joins command-line arguments back into a single shell command.
+///
+/// A single argument is taken to be a complete command the user already
+/// quoted (e.g. `add "git commit -m @message"`) and is kept as is. Multiple
+/// arguments are each quoted where needed, so the result runs exactly the
+/// arguments that were given.
+pub fn join_args(args: &[String]) -> Result<String> {
+    match args {
+        [single] => Ok(single.clone()),
+        _ => shlex::try_join(args.iter().map(String::as_str))
+            .map_err(|e| anyhow!("Cannot quote command: {}", e)),
+    }
+}
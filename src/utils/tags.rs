@@ -0,0 +1,82 @@
+/// Returns the tag name if `token` is a standalone `#tag` (e.g. `#deploy`).
+///
+/// Tags must contain a letter, so things like `#1` are left alone.
+fn inline_tag(token: &str) -> Option<&str> {
+    let name = token.strip_prefix('#')?;
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && name.chars().any(|c| c.is_ascii_alphabetic())
+        && !name.starts_with('-');
+    valid.then_some(name)
+}
+
+/// Strips trailing `#tag` tokens from a command, returning the command and the tags.
+///
+/// Only a run of whole `#word` tokens at the end counts as tags: a `#` inside
+/// a word (`http://host/page#section`), inside quotes, or followed by more
+/// command text is left untouched.
+pub fn extract_inline_tags(command: &str) -> (String, Vec<String>) {
+    let mut rest = command.trim_end();
+    let mut tags = Vec::new();
+
+    while let Some(pos) = rest.rfind(char::is_whitespace) {
+        match inline_tag(&rest[pos + 1..]) {
+            Some(tag) => {
+                tags.push(tag.to_string());
+                rest = rest[..pos].trim_end();
+            }
+            None => break,
+        }
+    }
+
+    tags.reverse();
+    if tags.is_empty() {
+        (command.to_string(), tags)
+    } else {
+        (rest.to_string(), tags)
+    }
+}
+
+/// Like `extract_inline_tags`, for a command given as separate arguments.
+pub fn split_inline_tag_args(args: &[String]) -> (&[String], Vec<String>) {
+    let mut end = args.len();
+    // Keep at least one argument so the command itself is never consumed
+    while end > 1 && inline_tag(&args[end - 1]).is_some() {
+        end -= 1;
+    }
+    let tags = args[end..]
+        .iter()
+        .filter_map(|arg| inline_tag(arg).map(str::to_string))
+        .collect();
+    (&args[..end], tags)
+}
+
+/// Trims a tag and lowercases it if `lowercase` is set, so `" Git "` and `git`
+/// end up the same tag. Returns `None` for a tag that is empty once trimmed.
+pub fn normalize_tag(tag: &str, lowercase: bool) -> Option<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        None
+    } else if lowercase {
+        Some(tag.to_lowercase())
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+/// Normalizes each tag with `normalize_tag`, dropping empty ones and the
+/// duplicates normalizing creates.
+pub fn normalize_tags(tags: &[String], lowercase: bool) -> Vec<String> {
+    let mut normalized = Vec::with_capacity(tags.len());
+    merge_tags(&mut normalized, tags.iter().filter_map(|tag| normalize_tag(tag, lowercase)).collect());
+    normalized
+}
+
+/// Adds `extra` tags to `tags`, skipping ones that are already there.
+pub fn merge_tags(tags: &mut Vec<String>, extra: Vec<String>) {
+    for tag in extra {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+}
@@ -39,3 +39,31 @@ pub fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
 
     None
 }
+
+/// Describes how long ago `dt` was, e.g. "3m ago", "2d ago" or "1y ago".
+pub fn humanize_ago(dt: DateTime<Utc>) -> String {
+    humanize_ago_from(dt, Utc::now())
+}
+
+/// Like `humanize_ago`, measured from `now` instead of the current time.
+pub fn humanize_ago_from(dt: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    // Timestamps in the future (clock skew) count as now
+    let seconds = (now - dt).num_seconds().max(0);
+    let minutes = seconds / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+
+    if minutes == 0 {
+        "just now".to_string()
+    } else if hours == 0 {
+        format!("{}m ago", minutes)
+    } else if days == 0 {
+        format!("{}h ago", hours)
+    } else if days < 30 {
+        format!("{}d ago", days)
+    } else if days < 365 {
+        format!("{}mo ago", days / 30)
+    } else {
+        format!("{}y ago", days / 365)
+    }
+}
@@ -1,4 +1,4 @@
-use command_vault::cli::args::{Cli, Commands, TagCommands};
+use command_vault::cli::args::{Cli, CommandRef, Commands, TagCommands};
 use clap::Parser;
 
 #[test]
@@ -6,7 +6,7 @@ fn test_parse_args_add() {
     let args = vec!["cv", "add", "--", "ls", "-l"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command, vec!["ls", "-l"]);
             assert!(tags.is_empty());
         }
@@ -19,7 +19,7 @@ fn test_parse_args_add_with_tags() {
     let args = vec!["cv", "add", "-t", "file", "-t", "list", "--", "ls", "-l"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command, vec!["ls", "-l"]);
             assert_eq!(tags, vec!["file", "list"]);
         }
@@ -32,8 +32,8 @@ fn test_parse_args_ls() {
     let args = vec!["cv", "ls"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Ls { limit, asc } => {
-            assert_eq!(limit, 50);
+        Commands::Ls { limit, asc, .. } => {
+            assert_eq!(limit, None);
             assert!(!asc);
         }
         _ => panic!("Expected Ls command"),
@@ -45,8 +45,8 @@ fn test_parse_args_ls_with_limit() {
     let args = vec!["cv", "ls", "--limit", "5"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Ls { limit, asc } => {
-            assert_eq!(limit, 5);
+        Commands::Ls { limit, asc, .. } => {
+            assert_eq!(limit, Some(5));
             assert!(!asc);
         }
         _ => panic!("Expected Ls command"),
@@ -58,9 +58,9 @@ fn test_parse_args_exec() {
     let args = vec!["cv", "exec", "1"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Exec { command_id, debug } => {
-            assert_eq!(command_id, 1);
-            assert_eq!(debug, false); // Default value should be false
+        Commands::Exec { command_id, debug, .. } => {
+            assert_eq!(command_id, CommandRef::Id(1));
+            assert!(!debug); // Default value should be false
         }
         _ => panic!("Expected Exec command"),
     }
@@ -69,9 +69,9 @@ fn test_parse_args_exec() {
     let args = vec!["cv", "exec", "1", "--debug"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Exec { command_id, debug } => {
-            assert_eq!(command_id, 1);
-            assert_eq!(debug, true);
+        Commands::Exec { command_id, debug, .. } => {
+            assert_eq!(command_id, CommandRef::Id(1));
+            assert!(debug);
         }
         _ => panic!("Expected Exec command"),
     }
@@ -82,7 +82,7 @@ fn test_parse_args_search() {
     let args = vec!["cv", "search", "git"];
     let cli = Cli::parse_from(args);
     match cli.command {
-        Commands::Search { query, limit } => {
+        Commands::Search { query, limit, .. } => {
             assert_eq!(query, "git");
             assert_eq!(limit, 10);
         }
@@ -133,7 +133,7 @@ fn test_parse_args_tag_list() {
     match cli.command {
         Commands::Tag { action } => {
             match action {
-                TagCommands::List => (),
+                TagCommands::List { .. } => (),
                 _ => panic!("Expected Tag List command"),
             }
         }
@@ -148,8 +148,9 @@ fn test_parse_args_tag_search() {
     match cli.command {
         Commands::Tag { action } => {
             match action {
-                TagCommands::Search { tag, limit } => {
-                    assert_eq!(tag, "git");
+                TagCommands::Search { tags, all, limit, .. } => {
+                    assert_eq!(tags, vec!["git"]);
+                    assert!(!all);
                     assert_eq!(limit, 10);
                 }
                 _ => panic!("Expected Tag Search command"),
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use command_vault::cli::args::{Cli, Commands, TagCommands};
-use clap::Parser;
+use command_vault::cli::args::{Cli, CommandRef, Commands, Field, TagCommands};
+use clap::{CommandFactory, Parser};
+use command_vault::db::models::SortOrder;
 
 #[test]
 fn test_add_command_parsing() -> Result<()> {
@@ -16,7 +17,7 @@ fn test_add_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "git commit -m test");
             assert_eq!(tags, Vec::<String>::new());
         }
@@ -39,7 +40,7 @@ fn test_add_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "git commit -m test");
             assert_eq!(tags, vec!["git", "vcs"]);
         }
@@ -56,7 +57,7 @@ fn test_add_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "echo hello world");
             assert_eq!(tags, Vec::<String>::new());
         }
@@ -85,7 +86,7 @@ fn test_search_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Search { query, limit } => {
+        Commands::Search { query, limit, .. } => {
             assert_eq!(query, "git commit");
             assert_eq!(limit, 5);
         }
@@ -105,8 +106,8 @@ fn test_ls_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Ls { limit, asc } => {
-            assert_eq!(limit, 20);
+        Commands::Ls { limit, asc, .. } => {
+            assert_eq!(limit, Some(20));
             assert!(asc);
         }
         _ => panic!("Expected Ls command"),
@@ -114,6 +115,30 @@ fn test_ls_command_parsing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_ls_paging_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "ls", "--after-id", "100", "--limit", "50"])?;
+    match args.command {
+        Commands::Ls { after_id, before_id, limit, .. } => {
+            assert_eq!(after_id, Some(100));
+            assert_eq!(before_id, None);
+            assert_eq!(limit, Some(50));
+        }
+        _ => panic!("Expected Ls command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "ls", "--host", "laptop"])?;
+    match args.command {
+        Commands::Ls { host, .. } => assert_eq!(host.as_deref(), Some("laptop")),
+        _ => panic!("Expected Ls command"),
+    }
+
+    // Paging is always by ID
+    assert!(Cli::try_parse_from(["command-vault", "ls", "--before-id", "5", "--recent"]).is_err());
+    assert!(Cli::try_parse_from(["command-vault", "ls", "--after-id", "5", "--asc"]).is_err());
+    Ok(())
+}
+
 #[test]
 fn test_ls_command_default_behavior() -> Result<()> {
     // Test ls with default values
@@ -123,8 +148,8 @@ fn test_ls_command_default_behavior() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Ls { limit, asc } => {
-            assert_eq!(limit, 50); // Default limit is 50
+        Commands::Ls { limit, asc, .. } => {
+            assert_eq!(limit, None); // Falls back to the config, then 50
             assert!(!asc); // Default is descending order
         }
         _ => panic!("Expected Ls command"),
@@ -177,10 +202,52 @@ fn test_tag_commands_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Tag { action: TagCommands::List } => (),
+        Commands::Tag { action: TagCommands::List { prefix, porcelain } } => {
+            assert_eq!(prefix, None);
+            assert!(!porcelain);
+        }
+        _ => panic!("Expected Tag List command"),
+    }
+
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "tag",
+        "list",
+        "--prefix",
+        "gi",
+    ])?;
+
+    match args.command {
+        Commands::Tag { action: TagCommands::List { prefix, .. } } => assert_eq!(prefix.as_deref(), Some("gi")),
         _ => panic!("Expected Tag List command"),
     }
 
+    let args = Cli::try_parse_from(["command-vault", "tag", "list", "--porcelain"])?;
+    match args.command {
+        Commands::Tag { action: TagCommands::List { porcelain, .. } } => assert!(porcelain),
+        _ => panic!("Expected Tag List command"),
+    }
+
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "tag",
+        "add-matching",
+        "docker",
+        "--query",
+        "docker ",
+    ])?;
+
+    match args.command {
+        Commands::Tag { action: TagCommands::AddMatching { tag, query } } => {
+            assert_eq!(tag, "docker");
+            assert_eq!(query, "docker ");
+        }
+        _ => panic!("Expected Tag AddMatching command"),
+    }
+
+    // The query is required
+    assert!(Cli::try_parse_from(["command-vault", "tag", "remove-matching", "docker"]).is_err());
+
     // Test tag search
     let args = Cli::try_parse_from([
         "command-vault",
@@ -192,8 +259,9 @@ fn test_tag_commands_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Tag { action: TagCommands::Search { tag, limit } } => {
-            assert_eq!(tag, "git");
+        Commands::Tag { action: TagCommands::Search { tags, all, limit, .. } } => {
+            assert_eq!(tags, vec!["git"]);
+            assert!(!all);
             assert_eq!(limit, 5);
         }
         _ => panic!("Expected Tag Search command"),
@@ -210,23 +278,76 @@ fn test_exec_command_parsing() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Exec { command_id, debug } => {
-            assert_eq!(command_id, 42);
-            assert_eq!(debug, false);
+        Commands::Exec { command_id, debug, .. } => {
+            assert_eq!(command_id, CommandRef::Id(42));
+            assert!(!debug);
         }
         _ => panic!("Expected Exec command"),
     }
     Ok(())
 }
 
+#[test]
+fn test_rehome_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "rehome", "--from", "/old", "--to", "/new"])?;
+    match args.command {
+        Commands::Rehome { from, to } => {
+            assert_eq!(from, "/old");
+            assert_eq!(to, "/new");
+        }
+        _ => panic!("Expected Rehome command"),
+    }
+    assert!(Cli::try_parse_from(["command-vault", "rehome", "--from", "/old"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_confirm_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "add", "--confirm", "rm", "-rf", "build"])?;
+    assert!(matches!(args.command, Commands::Add { confirm: true, .. }));
+
+    let args = Cli::try_parse_from(["command-vault", "edit", "3", "--confirm"])?;
+    assert!(matches!(args.command, Commands::Edit { confirm: Some(true), .. }));
+    let args = Cli::try_parse_from(["command-vault", "edit", "3", "--confirm", "false"])?;
+    assert!(matches!(args.command, Commands::Edit { confirm: Some(false), .. }));
+    assert!(Cli::try_parse_from(["command-vault", "edit", "3", "--confirm", "--editor"]).is_err());
+
+    let args = Cli::try_parse_from(["command-vault", "exec", "3", "-y"])?;
+    assert!(matches!(args.command, Commands::Exec { yes: true, .. }));
+    Ok(())
+}
+
+#[test]
+fn test_last_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "last"])?;
+    assert!(matches!(args.command, Commands::Last { debug: false, use_defaults: false, yes: false }));
+    let args = Cli::try_parse_from(["command-vault", "last", "--use-defaults", "-y"])?;
+    assert!(matches!(args.command, Commands::Last { use_defaults: true, yes: true, .. }));
+    assert!(Cli::try_parse_from(["command-vault", "last", "3"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_edit_command_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "edit", "deploy", "--editor"])?;
+    match args.command {
+        Commands::Edit { command_id, editor, .. } => {
+            assert_eq!(command_id, CommandRef::Alias("deploy".to_string()));
+            assert!(editor);
+        }
+        _ => panic!("Expected Edit command"),
+    }
+    Ok(())
+}
+
 #[test]
 fn test_parse_exec_command() {
     let args = vec!["command-vault", "exec", "123"];
     let cli = Cli::try_parse_from(args).unwrap();
     match cli.command {
-        Commands::Exec { command_id, debug } => {
-            assert_eq!(command_id, 123);
-            assert_eq!(debug, false);
+        Commands::Exec { command_id, debug, .. } => {
+            assert_eq!(command_id, CommandRef::Id(123));
+            assert!(!debug);
         }
         _ => panic!("Expected Exec command"),
     }
@@ -235,24 +356,26 @@ fn test_parse_exec_command() {
     let args = vec!["command-vault", "exec", "123", "--debug"];
     let cli = Cli::try_parse_from(args).unwrap();
     match cli.command {
-        Commands::Exec { command_id, debug } => {
-            assert_eq!(command_id, 123);
-            assert_eq!(debug, true);
+        Commands::Exec { command_id, debug, .. } => {
+            assert_eq!(command_id, CommandRef::Id(123));
+            assert!(debug);
         }
         _ => panic!("Expected Exec command"),
     }
 }
 
 #[test]
-fn test_invalid_command_id() {
+fn test_non_numeric_command_id() {
+    // Anything that isn't a number is taken as an alias
     let result = Cli::try_parse_from([
         "command-vault",
         "exec",
         "not_a_number",
-    ]);
-    assert!(result.is_err());
-    let err = result.unwrap_err().to_string();
-    assert!(err.contains("invalid value 'not_a_number'"));
+    ]).unwrap();
+    match result.command {
+        Commands::Exec { command_id, .. } => assert_eq!(command_id, CommandRef::Alias("not_a_number".to_string())),
+        _ => panic!("Expected Exec command"),
+    }
 }
 
 #[test]
@@ -276,7 +399,7 @@ fn test_search_command_default_limit() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Search { query, limit } => {
+        Commands::Search { query, limit, .. } => {
             assert_eq!(query, "git commit");
             assert_eq!(limit, 10); // Default limit is 10
         }
@@ -312,6 +435,31 @@ fn test_delete_command_parsing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_clone_command_parsing() -> Result<()> {
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "clone",
+        "42",
+    ])?;
+
+    match args.command {
+        Commands::Clone { command_id } => {
+            assert_eq!(command_id, 42);
+        }
+        _ => panic!("Expected Clone command"),
+    }
+
+    // Test missing command ID
+    let result = Cli::try_parse_from([
+        "command-vault",
+        "clone",
+    ]);
+    assert!(result.is_err());
+
+    Ok(())
+}
+
 #[test]
 fn test_shell_init_command_parsing() -> Result<()> {
     // Test default shell initialization
@@ -372,7 +520,7 @@ fn test_tag_commands_all() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Tag { action: TagCommands::List } => (),
+        Commands::Tag { action: TagCommands::List { .. } } => (),
         _ => panic!("Expected Tag List command"),
     }
 
@@ -387,8 +535,9 @@ fn test_tag_commands_all() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Tag { action: TagCommands::Search { tag, limit } } => {
-            assert_eq!(tag, "git");
+        Commands::Tag { action: TagCommands::Search { tags, all, limit, .. } } => {
+            assert_eq!(tags, vec!["git"]);
+            assert!(!all);
             assert_eq!(limit, 5);
         }
         _ => panic!("Expected Tag Search command"),
@@ -403,8 +552,9 @@ fn test_tag_commands_all() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Tag { action: TagCommands::Search { tag, limit } } => {
-            assert_eq!(tag, "git");
+        Commands::Tag { action: TagCommands::Search { tags, all, limit, .. } } => {
+            assert_eq!(tags, vec!["git"]);
+            assert!(!all);
             assert_eq!(limit, 10); // Default limit is 10
         }
         _ => panic!("Expected Tag Search command"),
@@ -425,7 +575,7 @@ fn test_add_command_with_parameters() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "touch @filename");
             assert_eq!(tags, Vec::<String>::new());
         }
@@ -442,7 +592,7 @@ fn test_add_command_with_parameters() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "touch @filename:Name of file to create");
             assert_eq!(tags, Vec::<String>::new());
         }
@@ -459,7 +609,7 @@ fn test_add_command_with_parameters() -> Result<()> {
     ])?;
 
     match args.command {
-        Commands::Add { command, tags } => {
+        Commands::Add { command, tags, .. } => {
             assert_eq!(command.join(" "), "touch @filename:Name of file to create=test.txt");
             assert_eq!(tags, Vec::<String>::new());
         }
@@ -468,3 +618,500 @@ fn test_add_command_with_parameters() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_add_allow_duplicate_parsing() -> Result<()> {
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "add",
+        "--allow-duplicate",
+        "ls",
+    ])?;
+
+    match args.command {
+        Commands::Add { command, allow_duplicate, .. } => {
+            assert_eq!(command, vec!["ls"]);
+            assert!(allow_duplicate);
+        }
+        _ => panic!("Expected Add command"),
+    }
+
+    // Defaults to refusing duplicates
+    let args = Cli::try_parse_from(["command-vault", "add", "ls"])?;
+    match args.command {
+        Commands::Add { allow_duplicate, .. } => assert!(!allow_duplicate),
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_tag_search_multiple_tags_parsing() -> Result<()> {
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "tag",
+        "search",
+        "--all",
+        "git",
+        "deploy",
+    ])?;
+
+    match args.command {
+        Commands::Tag { action: TagCommands::Search { tags, all, limit, .. } } => {
+            assert_eq!(tags, vec!["git", "deploy"]);
+            assert!(all);
+            assert_eq!(limit, 10);
+        }
+        _ => panic!("Expected Tag Search command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_explain_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "search", "git", "--explain"])?;
+    match args.command {
+        Commands::Search { explain, .. } => assert!(explain),
+        _ => panic!("Expected Search command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "ls", "--explain"])?;
+    match args.command {
+        Commands::Ls { explain, .. } => assert!(explain),
+        _ => panic!("Expected Ls command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_ls_oneline_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "ls", "--oneline", "--limit", "0"])?;
+    match args.command {
+        Commands::Ls { limit, oneline, .. } => {
+            assert_eq!(limit, Some(0));
+            assert!(oneline);
+        }
+        _ => panic!("Expected Ls command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_count_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "ls", "--count"])?;
+    assert!(matches!(args.command, Commands::Ls { count: true, .. }));
+
+    let args = Cli::try_parse_from(["command-vault", "search", "git", "--count"])?;
+    assert!(matches!(args.command, Commands::Search { count: true, .. }));
+
+    let args = Cli::try_parse_from(["command-vault", "tag", "search", "git", "--count"])?;
+    assert!(matches!(args.command, Commands::Tag { action: TagCommands::Search { count: true, .. } }));
+
+    // Nothing is listed, so the listing flags don't apply
+    assert!(Cli::try_parse_from(["command-vault", "ls", "--count", "--oneline"]).is_err());
+    assert!(Cli::try_parse_from(["command-vault", "search", "git", "--count", "--explain"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_encryption_commands_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "--encrypt", "ls"])?;
+    assert!(args.encrypt);
+
+    let args = Cli::try_parse_from(["command-vault", "lock"])?;
+    assert!(matches!(args.command, Commands::Lock));
+    assert!(!args.encrypt);
+
+    let args = Cli::try_parse_from(["command-vault", "unlock"])?;
+    assert!(matches!(args.command, Commands::Unlock));
+    Ok(())
+}
+
+#[test]
+fn test_add_directory_parsing() -> Result<()> {
+    let args = Cli::try_parse_from([
+        "command-vault",
+        "add",
+        "--directory",
+        "/srv/app",
+        "--no-validate-dir",
+        "make",
+    ])?;
+
+    match args.command {
+        Commands::Add { directory, no_validate_dir, command, .. } => {
+            assert_eq!(directory, Some("/srv/app".to_string()));
+            assert!(no_validate_dir);
+            assert_eq!(command, vec!["make"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_exec_create_dir_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "7", "--create-dir"])?;
+    match args.command {
+        Commands::Exec { command_id, create_dir, .. } => {
+            assert_eq!(command_id, CommandRef::Id(7));
+            assert!(create_dir);
+        }
+        _ => panic!("Expected Exec command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "exec", "7", "--fallback-cwd"])?;
+    match args.command {
+        Commands::Exec { create_dir, fallback_cwd, .. } => {
+            assert!(!create_dir);
+            assert!(fallback_cwd);
+        }
+        _ => panic!("Expected Exec command"),
+    }
+    assert!(Cli::try_parse_from(["command-vault", "exec", "7", "--create-dir", "--fallback-cwd"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_stay_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "ls", "--stay"])?;
+    match args.command {
+        Commands::Ls { stay, .. } => assert!(stay),
+        _ => panic!("Expected Ls command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "search", "git"])?;
+    match args.command {
+        Commands::Search { stay, .. } => assert!(!stay),
+        _ => panic!("Expected Search command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_complete_ids_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "--complete-ids"])?;
+    assert!(matches!(args.command, Commands::CompleteIds));
+
+    // The helper is for completion scripts and stays out of the help output
+    let help = Cli::command().render_help().to_string();
+    assert!(!help.contains("complete-ids"));
+    Ok(())
+}
+
+#[test]
+fn test_verbose_after_subcommand() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "shell-init", "--verbose"])?;
+    assert!(args.verbose);
+    assert!(matches!(args.command, Commands::ShellInit { shell: None }));
+
+    let args = Cli::try_parse_from(["command-vault", "-v", "ls"])?;
+    assert!(args.verbose);
+    Ok(())
+}
+
+#[test]
+fn test_add_note_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "add", "--note", "Restarts the API", "kubectl", "rollout"])?;
+    match args.command {
+        Commands::Add { note, command, .. } => {
+            assert_eq!(note, Some("Restarts the API".to_string()));
+            assert_eq!(command, vec!["kubectl", "rollout"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_vacuum_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "vacuum"])?;
+    assert!(matches!(args.command, Commands::Vacuum));
+    Ok(())
+}
+
+#[test]
+fn test_recent_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "ls", "--recent"])?;
+    match args.command {
+        Commands::Ls { recent, asc, .. } => {
+            assert!(recent);
+            assert!(!asc);
+        }
+        _ => panic!("Expected Ls command"),
+    }
+
+    // Last-run order has no ascending variant
+    assert!(Cli::try_parse_from(["command-vault", "ls", "--recent", "--asc"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_case_sensitive_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "search", "HOME", "--case-sensitive"])?;
+    match args.command {
+        Commands::Search { case_sensitive, .. } => assert!(case_sensitive),
+        _ => panic!("Expected Search command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "search", "HOME"])?;
+    match args.command {
+        Commands::Search { case_sensitive, .. } => assert!(!case_sensitive),
+        _ => panic!("Expected Search command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_alias_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "deploy"])?;
+    match args.command {
+        Commands::Exec { command_id, .. } => assert_eq!(command_id, CommandRef::Alias("deploy".to_string())),
+        _ => panic!("Expected Exec command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "add", "--alias", "deploy", "--", "make", "deploy"])?;
+    match args.command {
+        Commands::Add { alias, command, .. } => {
+            assert_eq!(alias, Some("deploy".to_string()));
+            assert_eq!(command, vec!["make", "deploy"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "edit", "3", "--alias", "ship"])?;
+    assert!(matches!(args.command, Commands::Edit { alias: Some(ref alias), .. } if alias == "ship"));
+    let args = Cli::try_parse_from(["command-vault", "edit", "3", "--alias", ""])?;
+    assert!(matches!(args.command, Commands::Edit { alias: Some(ref alias), .. } if alias.is_empty()));
+    assert!(Cli::try_parse_from(["command-vault", "edit", "3", "--alias", "ship", "--editor"]).is_err());
+
+    let args = Cli::try_parse_from(["command-vault", "edit", "3", "--note", "Deploys to staging"])?;
+    assert!(matches!(args.command, Commands::Edit { note: Some(ref note), .. } if note == "Deploys to staging"));
+    assert!(Cli::try_parse_from(["command-vault", "edit", "3", "--note", "x", "--editor"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_stdin_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "add", "--stdin", "--tags", "gen"])?;
+    match args.command {
+        Commands::Add { stdin, command, tags, .. } => {
+            assert!(stdin);
+            assert!(command.is_empty());
+            assert_eq!(tags, vec!["gen"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+
+    // The command comes from one place or the other
+    assert!(Cli::try_parse_from(["command-vault", "add", "--stdin", "--", "ls"]).is_err());
+    assert!(Cli::try_parse_from(["command-vault", "add"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_save_output_and_get_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "3", "--save-output"])?;
+    match args.command {
+        Commands::Exec { save_output, .. } => assert!(save_output),
+        _ => panic!("Expected Exec command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "get", "3", "--field", "output"])?;
+    match args.command {
+        Commands::Get { command_id, field, .. } => {
+            assert_eq!(command_id, CommandRef::Id(3));
+            assert_eq!(field, Field::Output);
+        }
+        _ => panic!("Expected Get command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "get", "deploy"])?;
+    match args.command {
+        Commands::Get { field, as_exec, .. } => {
+            assert_eq!(field, Field::Command);
+            assert!(!as_exec);
+        }
+        _ => panic!("Expected Get command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "get", "deploy", "--as-exec"])?;
+    match args.command {
+        Commands::Get { as_exec, .. } => assert!(as_exec),
+        _ => panic!("Expected Get command"),
+    }
+    assert!(Cli::try_parse_from(["command-vault", "get", "3", "--as-exec", "--field", "note"]).is_err());
+    assert!(Cli::try_parse_from(["command-vault", "get", "3", "--field", "nope"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_params_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "params", "deploy"])?;
+    match args.command {
+        Commands::Params { command_id } => assert_eq!(command_id, CommandRef::Alias("deploy".to_string())),
+        _ => panic!("Expected Params command"),
+    }
+    assert!(Cli::try_parse_from(["command-vault", "params"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_capture_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "3", "--capture"])?;
+    match args.command {
+        Commands::Exec { capture, .. } => assert!(capture),
+        _ => panic!("Expected Exec command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_use_defaults_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "3", "--use-defaults"])?;
+    match args.command {
+        Commands::Exec { use_defaults, .. } => assert!(use_defaults),
+        _ => panic!("Expected Exec command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "exec", "3"])?;
+    match args.command {
+        Commands::Exec { use_defaults, .. } => assert!(!use_defaults),
+        _ => panic!("Expected Exec command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_shell_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "exec", "3", "--shell", "bash"])?;
+    match args.command {
+        Commands::Exec { shell, .. } => assert_eq!(shell, Some("bash".to_string())),
+        _ => panic!("Expected Exec command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "add", "--shell", "bash", "--", "echo", "hi"])?;
+    match args.command {
+        Commands::Add { shell, command, .. } => {
+            assert_eq!(shell, Some("bash".to_string()));
+            assert_eq!(command, vec!["echo", "hi"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_no_param_prompt_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "add", "--no-param-prompt", "--", "echo", "@msg"])?;
+    match args.command {
+        Commands::Add { no_param_prompt, command, .. } => {
+            assert!(no_param_prompt);
+            assert_eq!(command, vec!["echo", "@msg"]);
+        }
+        _ => panic!("Expected Add command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "add", "echo", "@msg"])?;
+    match args.command {
+        Commands::Add { no_param_prompt, .. } => assert!(!no_param_prompt),
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_interactive_flag_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "add", "--interactive", "false", "--", "notify", "@channel"])?;
+    match args.command {
+        Commands::Add { interactive, .. } => assert_eq!(interactive, Some(false)),
+        _ => panic!("Expected Add command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "add", "echo", "@msg"])?;
+    match args.command {
+        Commands::Add { interactive, .. } => assert_eq!(interactive, None),
+        _ => panic!("Expected Add command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_recent_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "recent"])?;
+    match args.command {
+        Commands::Recent { limit } => assert_eq!(limit, 10),
+        _ => panic!("Expected Recent command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "recent", "--limit", "3"])?;
+    match args.command {
+        Commands::Recent { limit } => assert_eq!(limit, 3),
+        _ => panic!("Expected Recent command"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_promote_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "promote", "-t", "k8s", "--", "kubectl", "get", "pods", "-A"])?;
+    match args.command {
+        Commands::Promote { tags, command } => {
+            assert_eq!(tags, vec!["k8s"]);
+            assert_eq!(command, vec!["kubectl", "get", "pods", "-A"]);
+        }
+        _ => panic!("Expected Promote command"),
+    }
+
+    assert!(Cli::try_parse_from(["command-vault", "promote"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_search_sort_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "search", "git", "--sort", "oldest"])?;
+    match args.command {
+        Commands::Search { sort, .. } => assert_eq!(sort, SortOrder::Oldest),
+        _ => panic!("Expected Search command"),
+    }
+
+    let args = Cli::try_parse_from(["command-vault", "search", "git"])?;
+    match args.command {
+        Commands::Search { sort, .. } => assert_eq!(sort, SortOrder::Newest),
+        _ => panic!("Expected Search command"),
+    }
+
+    assert!(Cli::try_parse_from(["command-vault", "search", "git", "--sort", "sideways"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_tag_merge_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "tag", "merge", "k8s", "kubernetes"])?;
+    match args.command {
+        Commands::Tag { action: TagCommands::Merge { from, into } } => {
+            assert_eq!(from, "k8s");
+            assert_eq!(into, "kubernetes");
+        }
+        _ => panic!("Expected Tag Merge command"),
+    }
+
+    assert!(Cli::try_parse_from(["command-vault", "tag", "merge", "k8s"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_dedupe_parsing() -> Result<()> {
+    let args = Cli::try_parse_from(["command-vault", "dedupe", "--apply", "--ignore-directory"])?;
+    match args.command {
+        Commands::Dedupe { dry_run, apply, ignore_directory } => {
+            assert!(!dry_run);
+            assert!(apply);
+            assert!(ignore_directory);
+        }
+        _ => panic!("Expected Dedupe command"),
+    }
+
+    assert!(Cli::try_parse_from(["command-vault", "dedupe", "--dry-run", "--apply"]).is_err());
+    Ok(())
+}
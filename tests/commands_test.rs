@@ -1,9 +1,10 @@
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use command_vault::{
-    cli::{args::Commands, commands::handle_command},
-    db::{Command, models::Parameter},
+    cli::{args::{Commands, Field, TagCommands}, commands::{duplicate_to_keep, format_completion_ids, format_explain, format_parameters, format_tags_porcelain, handle_command, read_command, PROMOTED_TAG}},
+    db::{Command, Database, models::{Parameter, SortOrder}},
 };
+use serial_test::serial;
 use tempfile::tempdir;
 use std::env;
 
@@ -34,6 +35,13 @@ fn test_handle_command_list() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     db.add_command(&command)?;
     let commands = db.list_commands(10, false)?;
@@ -53,6 +61,13 @@ fn test_ls_with_limit() -> Result<()> {
             directory: "/test".to_string(),
             tags: vec![],
             parameters: Vec::new(),
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         };
         db.add_command(&command)?;
     }
@@ -64,7 +79,7 @@ fn test_ls_with_limit() -> Result<()> {
 #[test]
 fn test_ls_ordering() -> Result<()> {
     let (mut db, _db_dir) = create_test_db()?;
-    let timestamps = vec![
+    let timestamps = [
         Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
         Utc.with_ymd_and_hms(2022, 1, 2, 0, 0, 0).unwrap(),
         Utc.with_ymd_and_hms(2022, 1, 3, 0, 0, 0).unwrap(),
@@ -78,6 +93,13 @@ fn test_ls_ordering() -> Result<()> {
             directory: "/test".to_string(),
             tags: vec![],
             parameters: Vec::new(),
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         };
         db.add_command(&command)?;
     }
@@ -100,6 +122,13 @@ fn test_delete_command() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     db.delete_command(id)?;
@@ -118,6 +147,13 @@ fn test_search_commands() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     db.add_command(&command)?;
     let commands = db.search_commands("test", 10)?;
@@ -127,6 +163,7 @@ fn test_search_commands() -> Result<()> {
 }
 
 #[test]
+#[serial]
 fn test_add_command_with_tags() -> Result<()> {
     let (mut db, _db_dir) = create_test_db()?;
     let temp_dir = tempdir()?;
@@ -140,7 +177,18 @@ fn test_add_command_with_tags() -> Result<()> {
     let command = vec!["test".to_string(), "command".to_string()];
     let add_command = Commands::Add { 
         command: command.clone(), 
-        tags: vec!["tag1".to_string(), "tag2".to_string()] 
+        tags: vec!["tag1".to_string(), "tag2".to_string()],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
     };
     
     handle_command(add_command, &mut db, false)?;
@@ -164,14 +212,26 @@ fn test_command_with_output() -> Result<()> {
     let command = vec!["echo".to_string(), "\"Hello, World!\"".to_string()];
     let add_command = Commands::Add { 
         command: command.clone(), 
-        tags: vec![] 
+        tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
     };
     
     handle_command(add_command, &mut db, false)?;
     
     let commands = db.list_commands(1, false)?;
     assert_eq!(commands.len(), 1);
-    assert_eq!(commands[0].command, "echo \"Hello, World!\"");
+    // The argument itself contains the quotes, so they are kept
+    assert_eq!(commands[0].command, "echo '\"Hello, World!\"'");
     
     Ok(())
 }
@@ -184,7 +244,18 @@ fn test_command_with_stderr() -> Result<()> {
     let command = vec!["ls".to_string(), "nonexistent_directory".to_string()];
     let add_command = Commands::Add { 
         command: command.clone(), 
-        tags: vec![] 
+        tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
     };
     
     handle_command(add_command, &mut db, false)?;
@@ -197,6 +268,7 @@ fn test_command_with_stderr() -> Result<()> {
 }
 
 #[test]
+#[serial]
 fn test_git_log_format_command() -> Result<()> {
     let (mut db, _db_dir) = create_test_db()?;
     let temp_dir = tempdir()?;
@@ -219,7 +291,18 @@ fn test_git_log_format_command() -> Result<()> {
     
     let add_command = Commands::Add { 
         command: command.clone(), 
-        tags: vec![] 
+        tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
     };
     
     handle_command(add_command, &mut db, false)?;
@@ -228,7 +311,7 @@ fn test_git_log_format_command() -> Result<()> {
     assert_eq!(commands.len(), 1);
     assert_eq!(
         commands[0].command, 
-        format!("git log --graph \"--pretty=format:{}\" --abbrev-commit", format_str)
+        format!("git log --graph '--pretty=format:{}' --abbrev-commit", format_str)
     );
     
     // Restore the original directory
@@ -252,6 +335,13 @@ fn test_parameter_parsing() -> Result<()> {
             "message".to_string(),
             Some("User_name".to_string())
         )],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     let saved = db.get_command(id)?.unwrap();
@@ -270,6 +360,13 @@ fn test_parameter_parsing() -> Result<()> {
             "message".to_string(),
             Some("User_name".to_string())
         )],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     let saved = db.get_command(id)?.unwrap();
@@ -300,11 +397,18 @@ fn test_exec_command_with_parameters() -> Result<()> {
             "message".to_string(),
             Some("test message".to_string())
         )],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     
     // Execute command with default parameter
-    let exec_command = Commands::Exec { command_id: id, debug: false };
+    let exec_command = Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false };
     handle_command(exec_command, &mut db, false)?;
     
     // Verify command was saved correctly
@@ -312,16 +416,168 @@ fn test_exec_command_with_parameters() -> Result<()> {
     assert_eq!(saved.parameters.len(), 1);
     assert_eq!(saved.parameters[0].name, "message");
     assert_eq!(saved.parameters[0].description, Some("test message".to_string()));
+
+    // The run is recorded, so it sorts ahead of a newer command that never ran
+    db.add_command(&Command { command: "echo newer".to_string(), ..command })?;
+    assert_eq!(db.list_by_last_run(0)?[0].id, Some(id));
     
     Ok(())
 }
 
+#[test]
+fn test_exec_command_by_alias() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+    std::env::set_var("COMMAND_VAULT_TEST", "1");
+
+    let id = db.add_command(&Command {
+        id: None,
+        command: "echo deploying".to_string(),
+        timestamp: Utc::now(),
+        directory: test_dir.path().to_string_lossy().to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: Some("deploy".to_string()),
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    handle_command(Commands::Exec { command_id: "deploy".parse()?, debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    assert_eq!(db.list_by_last_run(0)?[0].id, Some(id));
+
+    let result = handle_command(Commands::Exec { command_id: "missing".parse()?, debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("Command not found with alias: missing"));
+
+    // `edit --alias` renames it, and `--alias ""` removes it
+    let edit = |command_id: &str, alias: &str| Commands::Edit { command_id: command_id.parse().unwrap(), editor: false, confirm: None, alias: Some(alias.to_string()), note: None };
+    handle_command(edit("deploy", "ship"), &mut db, false)?;
+    assert_eq!(db.get_command(id)?.unwrap().alias, Some("ship".to_string()));
+    assert!(db.get_command_by_alias("deploy")?.is_none());
+    assert!(handle_command(edit("ship", "-5"), &mut db, false).unwrap_err().to_string().contains("Invalid alias"));
+    handle_command(edit("ship", ""), &mut db, false)?;
+    assert_eq!(db.get_command(id)?.unwrap().alias, None);
+    Ok(())
+}
+
+#[test]
+fn test_edit_note() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+
+    let id = db.add_command(&Command {
+        id: None,
+        command: "make deploy".to_string(),
+        timestamp: Utc::now(),
+        directory: test_dir.path().to_string_lossy().to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // `edit --note` sets the note, and `--note ""` clears it
+    let edit = |note: &str| Commands::Edit { command_id: id.into(), editor: false, confirm: None, alias: None, note: Some(note.to_string()) };
+    handle_command(edit("Deploys to staging"), &mut db, false)?;
+    assert_eq!(db.get_command(id)?.unwrap().note, Some("Deploys to staging".to_string()));
+    handle_command(edit(""), &mut db, false)?;
+    assert_eq!(db.get_command(id)?.unwrap().note, None);
+    assert_eq!(db.get_command(id)?.unwrap().command, "make deploy");
+    Ok(())
+}
+
+#[test]
+fn test_last() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+    std::env::set_var("COMMAND_VAULT_TEST", "1");
+    let last = || Commands::Last { debug: false, use_defaults: false, yes: false };
+
+    let result = handle_command(last(), &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("No commands in the vault"));
+
+    let log = test_dir.path().join("log.txt");
+    let mut ids = Vec::new();
+    for word in ["one", "two"] {
+        ids.push(db.add_command(&Command {
+            id: None,
+            command: format!("echo {} >> log.txt", word),
+            timestamp: Utc::now(),
+            directory: test_dir.path().to_string_lossy().to_string(),
+            tags: vec![],
+            parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
+        })?);
+    }
+
+    // Nothing has run yet, so the newest command runs
+    handle_command(last(), &mut db, false)?;
+    assert_eq!(std::fs::read_to_string(&log)?, "two\n");
+
+    // Then whichever ran most recently
+    handle_command(Commands::Exec { command_id: ids[0].into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    handle_command(last(), &mut db, false)?;
+    assert_eq!(std::fs::read_to_string(&log)?, "two\none\none\n");
+    Ok(())
+}
+
+#[test]
+fn test_confirm_before_run() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+    std::env::set_var("COMMAND_VAULT_TEST", "1");
+
+    handle_command(Commands::Add {
+        command: vec!["echo".to_string(), "dangerous".to_string()],
+        tags: vec![],
+        allow_duplicate: false,
+        directory: Some(test_dir.path().to_string_lossy().to_string()),
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: true,
+        stdin: false,
+        confirm: true,
+    }, &mut db, false)?;
+    let id = db.list_commands(1, false)?[0].id.unwrap();
+    assert!(db.get_command(id)?.unwrap().confirm_before_run);
+
+    // There's no one to ask, so it only runs with --yes
+    let exec = |yes| Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes };
+    let result = handle_command(exec(false), &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("--yes"));
+    handle_command(exec(true), &mut db, false)?;
+
+    // `edit --confirm false` turns it off again
+    handle_command(Commands::Edit { command_id: id.into(), editor: false, confirm: Some(false), alias: None, note: None }, &mut db, false)?;
+    assert!(!db.get_command(id)?.unwrap().confirm_before_run);
+    handle_command(exec(false), &mut db, false)?;
+    Ok(())
+}
+
 #[test]
 fn test_exec_command_not_found() -> Result<()> {
     let (mut db, _db_dir) = create_test_db()?;
     
     // Try to execute a non-existent command
-    let exec_command = Commands::Exec { command_id: 999, debug: false };
+    let exec_command = Commands::Exec { command_id: 999.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false };
     let result = handle_command(exec_command, &mut db, false);
     
     // Verify that we get an error
@@ -343,6 +599,13 @@ fn test_parameter_validation() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     let saved = db.get_command(id)?.unwrap();
@@ -356,10 +619,19 @@ fn test_parameter_validation() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     let saved = db.get_command(id)?.unwrap();
-    assert_eq!(saved.parameters.len(), 0); // Invalid parameter should be ignored
+    // The trailing character isn't part of the name, so this is the parameter `name`
+    assert_eq!(saved.parameters.len(), 1);
+    assert_eq!(saved.parameters[0].name, "name");
     
     Ok(())
 }
@@ -377,6 +649,13 @@ fn test_command_with_spaces_in_parameters() -> Result<()> {
             "message".to_string(),
             Some("A test message".to_string())
         )],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     
     db.add_command(&command)?;
@@ -398,6 +677,13 @@ fn test_command_with_multiple_tags() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["tag1".to_string(), "tag2".to_string(), "tag3".to_string()],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     
     db.add_command(&command)?;
@@ -429,6 +715,13 @@ fn test_command_with_special_chars() -> Result<()> {
                 Some("Directory to search in".to_string())
             ),
         ],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     
     db.add_command(&command)?;
@@ -443,16 +736,29 @@ fn test_command_with_special_chars() -> Result<()> {
 }
 
 #[test]
+#[serial]
 fn test_handle_command_debug() -> Result<()> {
     let (mut db, _db_dir) = create_test_db()?;
     let temp_dir = tempdir()?;
     let test_dir = temp_dir.path().canonicalize()?;
+    let original_dir = std::env::current_dir()?;
     std::env::set_current_dir(&test_dir)?;
     
     // First add a simple command that works in any shell
     let add_command = Commands::Add {
         command: vec!["echo".to_string(), "test".to_string()],
         tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
     };
     handle_command(add_command, &mut db, true)?;
 
@@ -461,9 +767,11 @@ fn test_handle_command_debug() -> Result<()> {
     let id = commands[0].id.unwrap();
 
     // Execute the command in debug mode
-    let exec_command = Commands::Exec { command_id: id, debug: true };
+    let exec_command = Commands::Exec { command_id: id.into(), debug: true, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false };
     handle_command(exec_command, &mut db, true)?;
 
+    // Don't leave the working directory pointing at a deleted temp dir
+    std::env::set_current_dir(original_dir)?;
     Ok(())
 }
 
@@ -479,6 +787,13 @@ fn test_handle_command_delete() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec![],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     
@@ -520,6 +835,13 @@ fn test_handle_command_delete_with_tags() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["test".to_string(), "example".to_string()],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&command)?;
     
@@ -540,3 +862,852 @@ fn test_handle_command_delete_with_tags() -> Result<()> {
     assert_eq!(tags.len(), 0);
     Ok(())
 }
+
+#[test]
+fn test_handle_command_clone() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+
+    let command = Command {
+        id: None,
+        command: "echo @message".to_string(),
+        timestamp: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+        directory: "/test".to_string(),
+        tags: vec!["test".to_string(), "example".to_string()],
+        parameters: vec![Parameter::with_description(
+            "message".to_string(),
+            Some("Message to print".to_string())
+        )],
+        note: None,
+        alias: Some("greet".to_string()),
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    };
+    let id = db.add_command(&command)?;
+
+    handle_command(Commands::Clone { command_id: id }, &mut db, false)?;
+
+    let commands = db.list_commands(10, false)?;
+    assert_eq!(commands.len(), 2);
+
+    // The clone gets a fresh id and timestamp, so it is listed first
+    let clone = db.get_command(commands[0].id.unwrap())?.unwrap();
+    assert_ne!(clone.id, Some(id));
+    assert!(clone.timestamp > command.timestamp);
+    assert_eq!(clone.command, command.command);
+    assert_eq!(clone.directory, command.directory);
+    assert_eq!(clone.tags, command.tags);
+    assert_eq!(clone.parameters, command.parameters);
+    // Aliases are unique, so the clone doesn't get one
+    assert_eq!(clone.alias, None);
+
+    // The original is untouched
+    let original = db.get_command(id)?.unwrap();
+    assert_eq!(original.timestamp, command.timestamp);
+    assert_eq!(original.alias, Some("greet".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_handle_command_clone_nonexistent() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+
+    let result = handle_command(Commands::Clone { command_id: 999 }, &mut db, false);
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("Command with ID 999 not found"));
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_add_duplicate_command() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(temp_dir.path())?;
+
+    let add = |allow_duplicate| Commands::Add {
+        command: vec!["echo".to_string(), "duplicate".to_string()],
+        tags: vec![],
+        allow_duplicate,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    handle_command(add(false), &mut db, false)?;
+    let existing_id = db.list_commands(0, false)?[0].id.unwrap();
+
+    // Adding the same command in the same directory is refused
+    handle_command(add(false), &mut db, false)?;
+    let commands = db.list_commands(0, false)?;
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].id, Some(existing_id));
+
+    // --allow-duplicate stores it anyway
+    handle_command(add(true), &mut db, false)?;
+    assert_eq!(db.list_commands(0, false)?.len(), 2);
+
+    env::set_current_dir(original_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_explain_output() -> Result<()> {
+    let (sql, params) = Database::search_commands_query("git", 10);
+    let output = format_explain(&sql, &params);
+    assert!(output.contains("SELECT c.id, c.command"));
+    assert!(output.contains("WHERE c.command LIKE '%' || ?1 || '%'"));
+    assert!(output.contains("?1 = \"git\""));
+    assert!(output.contains("?2 = \"10\""));
+
+    // A limit of 0 lists everything, so there is no LIMIT clause
+    let (sql, params) = Database::list_commands_query(0, true);
+    let output = format_explain(&sql, &params);
+    assert!(output.contains("ORDER BY c.timestamp ASC"));
+    assert!(!output.contains("LIMIT"));
+    assert!(output.contains("Parameters: (none)"));
+
+    // --explain doesn't run the query or start the TUI
+    let (mut db, _dir) = create_test_db()?;
+    handle_command(Commands::Search { query: "git".to_string(), limit: 10, case_sensitive: false, sort: SortOrder::Newest, explain: true, stay: false, count: false }, &mut db, false)?;
+    handle_command(Commands::Ls { limit: Some(5), asc: false, recent: false, explain: true, oneline: false, stay: false, after_id: None, before_id: None, host: None, count: false }, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+fn test_ls_oneline() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    db.add_command(&Command {
+        id: None,
+        command: "echo oneline".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Doesn't start the TUI
+    handle_command(Commands::Ls { limit: Some(0), asc: false, recent: false, explain: false, oneline: true, stay: false, after_id: None, before_id: None, host: None, count: false }, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+fn test_count() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    db.add_command(&Command {
+        id: None,
+        command: "git status".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec!["git".to_string()],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Each prints a number instead of starting the TUI
+    handle_command(Commands::Ls { limit: Some(1), asc: false, recent: false, explain: false, oneline: false, stay: false, after_id: None, before_id: None, host: None, count: true }, &mut db, false)?;
+    handle_command(Commands::Search { query: "git".to_string(), limit: 10, case_sensitive: false, sort: SortOrder::Newest, explain: false, stay: false, count: true }, &mut db, false)?;
+    handle_command(Commands::Tag { action: TagCommands::Search { tags: vec!["git".to_string()], all: false, limit: 10, count: true } }, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+fn test_add_command_directory_validation() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+
+    let add = |directory: &str, no_validate_dir| Commands::Add {
+        command: vec!["make".to_string(), "build".to_string()],
+        tags: vec![],
+        allow_duplicate: true,
+        directory: Some(directory.to_string()),
+        no_validate_dir,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    // Missing directories are rejected
+    let result = handle_command(add("/nope/does/not/exist", false), &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("Directory does not exist"));
+
+    // So are files
+    let file = temp_dir.path().join("file.txt");
+    std::fs::write(&file, "")?;
+    let result = handle_command(add(file.to_str().unwrap(), false), &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("Not a directory"));
+    assert!(db.list_commands(0, false)?.is_empty());
+
+    // A valid directory is stored with the command
+    let dir = temp_dir.path().canonicalize()?;
+    handle_command(add(dir.to_str().unwrap(), false), &mut db, false)?;
+    let commands = db.list_commands(0, false)?;
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].directory, dir.to_string_lossy());
+
+    // --no-validate-dir allows paths that don't exist yet
+    handle_command(add("/nope/later", true), &mut db, false)?;
+    assert_eq!(db.list_commands(0, false)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_add_command_directory_expansion() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let dir = temp_dir.path().canonicalize()?;
+    std::fs::create_dir(dir.join("project"))?;
+    std::env::set_var("CV_TEST_PROJECTS", &dir);
+
+    let add = |directory: &str| Commands::Add {
+        command: vec!["make".to_string()],
+        tags: vec![],
+        allow_duplicate: true,
+        directory: Some(directory.to_string()),
+        no_validate_dir: true,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    let result = handle_command(add("$CV_TEST_PROJECTS/project"), &mut db, false);
+    std::env::remove_var("CV_TEST_PROJECTS");
+    result?;
+    handle_command(add("~/later"), &mut db, false)?;
+
+    let commands = db.list_commands(0, true)?;
+    assert_eq!(commands[0].directory, dir.join("project").to_string_lossy());
+    let home = dirs::home_dir().expect("home directory");
+    assert_eq!(commands[1].directory, home.join("later").to_string_lossy());
+    Ok(())
+}
+
+#[test]
+fn test_add_records_hostname() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    handle_command(Commands::Add {
+        command: vec!["uptime".to_string()],
+        tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    }, &mut db, false)?;
+
+    let commands = db.list_commands(0, false)?;
+    assert_eq!(commands[0].hostname, command_vault::utils::host::hostname());
+
+    let ls = |host: &str| Commands::Ls {
+        limit: None, asc: false, recent: false, explain: false, oneline: true, stay: false,
+        after_id: None, before_id: None, host: Some(host.to_string()), count: false,
+    };
+    handle_command(ls("some-other-machine"), &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+fn test_exec_missing_directory() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let missing = temp_dir.path().join("removed");
+
+    let id = db.add_command(&Command {
+        id: None,
+        command: "echo hello".to_string(),
+        timestamp: Utc::now(),
+        directory: missing.to_string_lossy().to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Non-interactive runs don't recreate the directory on their own
+    let result = handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("--create-dir"));
+    assert!(!missing.exists());
+
+    // --fallback-cwd runs in the current directory instead
+    handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: true, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    assert!(!missing.exists());
+
+    // --create-dir opts in
+    handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: true, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    assert!(missing.is_dir());
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_add_strips_prompt_marker() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(temp_dir.path())?;
+
+    let add = |marker: &str, keep_prompt| Commands::Add {
+        command: vec![marker.to_string(), "ls".to_string(), "-la".to_string()],
+        tags: vec![],
+        allow_duplicate: true,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    handle_command(add("$", false), &mut db, false)?;
+    handle_command(add("#", false), &mut db, false)?;
+    handle_command(add("$", true), &mut db, false)?;
+
+    let mut commands: Vec<String> = db.list_commands(0, false)?
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    commands.sort();
+    assert_eq!(commands, vec!["$ ls -la", "ls -la", "ls -la"]);
+
+    env::set_current_dir(original_dir)?;
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_add_command_too_long() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(temp_dir.path())?;
+
+    let add = |len: usize| Commands::Add {
+        command: vec!["echo".to_string(), "x".repeat(len)],
+        tags: vec![],
+        allow_duplicate: false,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    // Over the default 16 KiB limit
+    let result = handle_command(add(20_000), &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("too long"));
+    assert!(db.list_commands(0, false)?.is_empty());
+
+    // Under it
+    handle_command(add(1000), &mut db, false)?;
+    assert_eq!(db.list_commands(0, false)?.len(), 1);
+
+    env::set_current_dir(original_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_format_completion_ids() {
+    let commands = vec![
+        (3, "ls -la".to_string()),
+        (2, "git log --oneline --graph --decorate --all --color=always".to_string()),
+        (1, "echo one\n  echo two".to_string()),
+    ];
+    let lines = format_completion_ids(&commands);
+    assert_eq!(lines, vec![
+        "3:ls -la".to_string(),
+        "2:git log --oneline --graph --decorate...".to_string(),
+        "1:echo one echo two".to_string(),
+    ]);
+    assert!(lines.iter().all(|line| line.chars().count() <= 42));
+}
+
+#[test]
+fn test_format_tags_porcelain() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    for (command, tags) in [("git status", vec!["git"]), ("git push", vec!["git", "deploy"])] {
+        db.add_command(&Command {
+            id: None,
+            command: command.to_string(),
+            timestamp: Utc::now(),
+            directory: "/tmp".to_string(),
+            tags: tags.into_iter().map(String::from).collect(),
+            parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
+        })?;
+    }
+
+    let lines = format_tags_porcelain(&db.list_tags()?);
+    assert_eq!(lines.join("\n"), "git\t2\ndeploy\t1");
+    assert!(format_tags_porcelain(&[]).is_empty());
+
+    handle_command(Commands::Tag { action: TagCommands::List { prefix: None, porcelain: true } }, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+fn test_params_command() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let mut env_param = Parameter::with_description("env".to_string(), Some("Target environment".to_string()));
+    env_param.default_value = Some("staging".to_string());
+    let version = Parameter { name: "version".to_string(), description: None, pattern: Some("^v[0-9]+$".to_string()), default_value: None, secret: false };
+    let id = db.add_command(&Command {
+        id: None,
+        command: "deploy @env --version @version:/^v[0-9]+$/".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec![],
+        parameters: vec![env_param.clone(), version.clone()],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    assert_eq!(format_parameters(&[env_param, version]), vec![
+        "@env      Target environment  default: staging".to_string(),
+        "@version  -  pattern: /^v[0-9]+$/".to_string(),
+    ]);
+    assert!(format_parameters(&[]).is_empty());
+
+    handle_command(Commands::Params { command_id: id.into() }, &mut db, false)?;
+    assert!(handle_command(Commands::Params { command_id: "missing".parse()? }, &mut db, false).is_err());
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_print_multiline_command() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    db.add_command(&Command {
+        id: None,
+        command: "docker run \\\n  -it \\\n  ubuntu".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec!["docker".to_string()],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Printed through `print_commands`, one line per line of the command
+    std::env::set_var("COMMAND_VAULT_NO_TUI", "1");
+    let result = handle_command(Commands::Tag { action: TagCommands::Search { tags: vec!["docker".to_string()], all: false, limit: 10, count: false } }, &mut db, false);
+    std::env::remove_var("COMMAND_VAULT_NO_TUI");
+    result?;
+    Ok(())
+}
+
+#[test]
+fn test_vacuum_command() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    handle_command(Commands::Vacuum, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_add_inline_tags() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(temp_dir.path())?;
+
+    let add = |command: Vec<&str>, tags: Vec<&str>| Commands::Add {
+        command: command.into_iter().map(String::from).collect(),
+        tags: tags.into_iter().map(String::from).collect(),
+        allow_duplicate: true,
+        directory: None,
+        no_validate_dir: false,
+        keep_prompt: false,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        no_param_prompt: false,
+        stdin: false,
+        confirm: false,
+    };
+
+    // As separate arguments, merged with --tags
+    handle_command(add(vec!["git", "push", "#git", "#deploy"], vec!["git", "work"]), &mut db, false)?;
+    // As a single string
+    handle_command(add(vec!["cargo build --release #rust"], vec![]), &mut db, false)?;
+
+    let mut commands = db.list_commands(0, false)?;
+    commands.sort_by(|a, b| a.command.cmp(&b.command));
+    assert_eq!(commands[0].command, "cargo build --release");
+    assert_eq!(commands[0].tags, vec!["rust"]);
+    assert_eq!(commands[1].command, "git push");
+    let mut tags = commands[1].tags.clone();
+    tags.sort();
+    assert_eq!(tags, vec!["deploy", "git", "work"]);
+
+    env::set_current_dir(original_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_read_command() -> Result<()> {
+    // Only the trailing newline goes; the command itself is kept verbatim
+    let input = "jq '.items[] | select(.name == \"a b\")' data.json\n";
+    assert_eq!(read_command(input.as_bytes())?, "jq '.items[] | select(.name == \"a b\")' data.json");
+    assert_eq!(read_command("set -e\r\nmake\r\n".as_bytes())?, "set -e\r\nmake");
+    assert_eq!(read_command("".as_bytes())?, "");
+    Ok(())
+}
+
+#[test]
+fn test_exec_save_output() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+    std::env::set_var("COMMAND_VAULT_TEST", "1");
+
+    let id = db.add_command(&Command {
+        id: None,
+        command: "echo saved".to_string(),
+        timestamp: Utc::now(),
+        directory: test_dir.path().to_string_lossy().to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Nothing is kept unless asked for
+    handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: false, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    assert_eq!(db.last_output(id)?, None);
+    let result = handle_command(Commands::Get { command_id: id.into(), field: Field::Output, as_exec: false }, &mut db, false);
+    assert!(result.unwrap_err().to_string().contains("has no output"));
+    // A command without output can still be printed as an exec line
+    handle_command(Commands::Get { command_id: id.into(), field: Field::Output, as_exec: true }, &mut db, false)?;
+
+    handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: true, shell: None, use_defaults: false, capture: false, yes: false }, &mut db, false)?;
+    assert_eq!(db.last_output(id)?, Some("saved\n".to_string()));
+    handle_command(Commands::Get { command_id: id.into(), field: Field::Output, as_exec: false }, &mut db, false)?;
+
+    // --capture output can be saved too
+    db.record_output(id, "")?;
+    handle_command(Commands::Exec { command_id: id.into(), debug: false, create_dir: false, fallback_cwd: false, save_output: true, shell: None, use_defaults: false, capture: true, yes: false }, &mut db, false)?;
+    assert_eq!(db.last_output(id)?, Some("saved\n".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_recent() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    db.add_command(&Command {
+        id: None,
+        command: "echo recent".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    // Doesn't start the TUI, even without COMMAND_VAULT_NO_TUI
+    handle_command(Commands::Recent { limit: 10 }, &mut db, false)?;
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_edit_in_editor() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let id = db.add_command(&Command {
+        id: None,
+        command: "echo hello".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec!["greeting".to_string()],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    })?;
+
+    env::set_var("VISUAL", "sed -i s/hello/@name/");
+    let result = handle_command(Commands::Edit { command_id: id.into(), editor: true, confirm: None, alias: None, note: None }, &mut db, false);
+    env::remove_var("VISUAL");
+    result?;
+
+    let cmd = db.get_command(id)?.unwrap();
+    assert_eq!(cmd.command, "echo @name");
+    assert_eq!(cmd.parameters.len(), 1);
+    assert_eq!(cmd.parameters[0].name, "name");
+    assert_eq!(cmd.tags, vec!["greeting".to_string()]);
+
+    // Closing the editor without changes leaves the command alone
+    env::set_var("VISUAL", "true");
+    let result = handle_command(Commands::Edit { command_id: id.into(), editor: true, confirm: None, alias: None, note: None }, &mut db, false);
+    env::remove_var("VISUAL");
+    result?;
+    assert_eq!(db.get_command(id)?.unwrap().command, "echo @name");
+
+    // Parameters still in the text keep their description and default
+    let mut cmd = db.get_command(id)?.unwrap();
+    cmd.parameters[0].description = Some("Who to greet".to_string());
+    cmd.parameters[0].default_value = Some("world".to_string());
+    db.update_command(&cmd)?;
+    env::set_var("VISUAL", "sed -i s/echo/printf/");
+    let result = handle_command(Commands::Edit { command_id: id.into(), editor: true, confirm: None, alias: None, note: None }, &mut db, false);
+    env::remove_var("VISUAL");
+    result?;
+    let cmd = db.get_command(id)?.unwrap();
+    assert_eq!(cmd.command, "printf @name");
+    assert_eq!(cmd.parameters[0].description.as_deref(), Some("Who to greet"));
+    assert_eq!(cmd.parameters[0].default_value.as_deref(), Some("world"));
+    Ok(())
+}
+
+#[test]
+fn test_tag_matching_commands() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    for command in ["kubectl get pods", "kubectl logs app", "ls -la"] {
+        db.add_command(&Command {
+            id: None,
+            command: command.to_string(),
+            timestamp: Utc::now(),
+            directory: "/tmp".to_string(),
+            tags: vec![],
+            parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
+        })?;
+    }
+
+    let add = Commands::Tag { action: TagCommands::AddMatching { tag: "k8s".to_string(), query: "kubectl".to_string() } };
+    handle_command(add, &mut db, false)?;
+    assert_eq!(db.search_by_tag("k8s", 10)?.len(), 2);
+
+    let remove = Commands::Tag { action: TagCommands::RemoveMatching { tag: "k8s".to_string(), query: "logs".to_string() } };
+    handle_command(remove, &mut db, false)?;
+    let tagged = db.search_by_tag("k8s", 10)?;
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].command, "kubectl get pods");
+    Ok(())
+}
+
+#[test]
+fn test_import_history() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let history_dir = tempdir()?;
+    let file = history_dir.path().join(".zsh_history");
+    std::fs::write(&file, ": 1700000000:0;ssh me@example.com\n: 1700000001:0;ls\n: 1700000002:0;make test\n: 1700000003:0;make test\n")?;
+
+    let import = || Commands::ImportHistory { file: file.clone(), tags: vec!["imported".to_string()], limit: None, all: false };
+    handle_command(import(), &mut db, false)?;
+
+    let commands = db.list_commands(0, true)?;
+    let texts: Vec<&str> = commands.iter().map(|c| c.command.as_str()).collect();
+    assert_eq!(texts, vec!["ssh me@@example.com", "make test"]);
+    assert!(commands.iter().all(|c| c.tags == vec!["imported".to_string()] && c.directory == "~"));
+    // `@` in history is literal
+    assert!(commands[0].parameters.is_empty());
+
+    // Importing again doesn't duplicate anything
+    handle_command(import(), &mut db, false)?;
+    assert_eq!(db.count_commands()?, 2);
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_promote_command() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let temp_dir = tempdir()?;
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(temp_dir.path().canonicalize()?)?;
+
+    let promote = || Commands::Promote {
+        command: vec!["ssh deploy@web1".to_string()],
+        tags: vec!["ops".to_string()],
+    };
+    handle_command(promote(), &mut db, false)?;
+
+    let commands = db.list_commands(0, false)?;
+    assert_eq!(commands.len(), 1);
+    // It ran as typed, so `@web1` isn't a parameter
+    assert_eq!(commands[0].command, "ssh deploy@@web1");
+    assert!(commands[0].parameters.is_empty());
+    assert_eq!(commands[0].tags, vec!["ops", PROMOTED_TAG]);
+    assert_eq!(commands[0].directory, env::current_dir()?.to_string_lossy());
+
+    // Promoting it again doesn't store a second copy
+    db.remove_tag_from_command(commands[0].id.unwrap(), PROMOTED_TAG)?;
+    handle_command(promote(), &mut db, false)?;
+    let commands = db.list_commands(0, false)?;
+    assert_eq!(commands.len(), 1);
+    assert!(commands[0].tags.contains(&PROMOTED_TAG.to_string()));
+
+    env::set_current_dir(original_dir)?;
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_dedupe() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let now = Utc::now();
+    let mut ids = Vec::new();
+    for (age, tags) in [(3, vec![PROMOTED_TAG.to_string()]), (2, vec![]), (1, vec![])] {
+        ids.push(db.add_copy(&Command {
+            id: None,
+            command: "cargo build".to_string(),
+            timestamp: now - chrono::Duration::hours(age),
+            directory: "/test".to_string(),
+            tags,
+            parameters: Vec::new(),
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
+        })?);
+    }
+
+    // The favorite wins over the newest
+    let groups = db.find_duplicates(false)?;
+    assert_eq!(duplicate_to_keep(&groups[0]), ids[0]);
+    assert_eq!(duplicate_to_keep(&groups[0][..2]), ids[2]);
+
+    // A dry run leaves everything in place
+    handle_command(Commands::Dedupe { dry_run: true, apply: false, ignore_directory: false }, &mut db, false)?;
+    assert_eq!(db.count_commands()?, 3);
+
+    handle_command(Commands::Dedupe { dry_run: false, apply: true, ignore_directory: false }, &mut db, false)?;
+    let remaining: Vec<i64> = db.list_commands(0, false)?.iter().filter_map(|cmd| cmd.id).collect();
+    assert_eq!(remaining, vec![ids[0]]);
+    assert!(db.find_duplicates(false)?.is_empty());
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_rehome_resolves_directories() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let test_dir = tempdir()?;
+    let root = test_dir.path().canonicalize()?;
+    std::fs::create_dir(root.join("new"))?;
+    let add = |db: &mut Database, directory: String| db.add_command(&Command {
+        id: None,
+        command: "make".to_string(),
+        timestamp: Utc::now(),
+        directory,
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    });
+    let moved = add(&mut db, root.join("old/sub").to_string_lossy().to_string())?;
+
+    // Relative paths are taken from the current directory, whether or not they still exist
+    let original_dir = env::current_dir()?;
+    env::set_current_dir(root.join("new"))?;
+    let result = handle_command(Commands::Rehome { from: "../old".to_string(), to: ".".to_string() }, &mut db, false);
+    env::set_current_dir(original_dir)?;
+    result?;
+    assert_eq!(db.get_command(moved)?.unwrap().directory, root.join("new/sub").to_string_lossy());
+
+    // `~` is expanded, as it is for `add`
+    let home = dirs::home_dir().unwrap();
+    let at_home = add(&mut db, home.join("command-vault-old-project").to_string_lossy().to_string())?;
+    handle_command(Commands::Rehome { from: "~/command-vault-old-project".to_string(), to: root.to_string_lossy().to_string() }, &mut db, false)?;
+    assert_eq!(db.get_command(at_home)?.unwrap().directory, root.to_string_lossy());
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use command_vault::cli::args::Commands;
+use command_vault::config::{apply_env, write_sample, Config, LsSort, LS_LIMIT_ENV_VAR, SAMPLE_CONFIG};
+use command_vault::ui::keys::{Action, KeyBindings};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tempfile::tempdir;
+
+fn ls(limit: Option<usize>, asc: bool, recent: bool) -> Commands {
+    Commands::Ls { limit, asc, recent, explain: false, oneline: false, stay: false, after_id: None, before_id: None, host: None, count: false }
+}
+
+#[test]
+fn test_parse_config() -> Result<()> {
+    let config = Config::parse(r#"
+        ls_limit = 0
+        ls_sort = "recent"
+        no_tui = true
+        wrap_navigation = true
+        clipboard = "wl-copy --trim-newline"
+        color = false
+        lowercase_tags = false
+        load_shell_rc = false
+        allowed_root = "~/src"
+    "#)?;
+    assert_eq!(config.ls_limit, Some(0));
+    assert_eq!(config.ls_sort, Some(LsSort::Recent));
+    assert!(config.no_tui);
+    assert!(!config.remember_filter);
+    assert!(config.wrap_navigation);
+    assert_eq!(config.clipboard.as_deref(), Some("wl-copy --trim-newline"));
+    assert_eq!(config.color, Some(false));
+    assert_eq!(config.lowercase_tags, Some(false));
+    assert_eq!(config.load_shell_rc, Some(false));
+    assert_eq!(config.allowed_root.as_deref(), Some("~/src"));
+
+    // Typos are reported rather than silently ignored
+    assert!(Config::parse("ls_limt = 5").is_err());
+    assert!(Config::parse(r#"ls_sort = "sideways""#).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_sample_config() -> Result<()> {
+    // Everything in the sample is commented out
+    assert_eq!(Config::parse(SAMPLE_CONFIG)?, Config::default());
+
+    let dir = tempdir()?;
+    let path = dir.path().join("command-vault").join("config.toml");
+    assert_eq!(Config::load(&path)?, Config::default());
+
+    write_sample(&path)?;
+    assert_eq!(std::fs::read_to_string(&path)?, SAMPLE_CONFIG);
+
+    // An existing file is left alone
+    std::fs::write(&path, "ls_limit = 5\n")?;
+    write_sample(&path)?;
+    assert_eq!(Config::load(&path)?.ls_limit, Some(5));
+
+    std::fs::write(&path, "ls_limit = \"many\"\n")?;
+    assert!(Config::load(&path).unwrap_err().to_string().starts_with("Invalid config file"));
+    Ok(())
+}
+
+#[test]
+fn test_ls_limit_from_env() -> Result<()> {
+    std::env::set_var(LS_LIMIT_ENV_VAR, "0");
+    let mut command = ls(None, false, false);
+    apply_env(&mut command)?;
+    // The environment wins over the config file...
+    Config { ls_limit: Some(20), ..Config::default() }.apply_to(&mut command);
+    assert!(matches!(command, Commands::Ls { limit: Some(0), .. }));
+
+    // ...and --limit wins over both
+    let mut command = ls(Some(5), false, false);
+    apply_env(&mut command)?;
+    assert!(matches!(command, Commands::Ls { limit: Some(5), .. }));
+
+    std::env::set_var(LS_LIMIT_ENV_VAR, "all");
+    let err = apply_env(&mut ls(None, false, false)).unwrap_err();
+    assert!(err.to_string().contains(LS_LIMIT_ENV_VAR));
+
+    std::env::remove_var(LS_LIMIT_ENV_VAR);
+    let mut command = ls(None, false, false);
+    apply_env(&mut command)?;
+    assert!(matches!(command, Commands::Ls { limit: None, .. }));
+    Ok(())
+}
+
+#[test]
+fn test_key_bindings() -> Result<()> {
+    let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+    // The defaults are the built-in keys
+    let defaults = KeyBindings::default();
+    assert_eq!(defaults.action(&press(KeyCode::Char('j'))), Some(Action::Next));
+    assert_eq!(defaults.action(&press(KeyCode::Up)), Some(Action::Prev));
+    assert_eq!(defaults.action(&press(KeyCode::Enter)), Some(Action::Execute));
+    assert_eq!(defaults.action(&press(KeyCode::Char('x'))), None);
+    assert_eq!(defaults.action(&KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)), None);
+    assert_eq!(defaults.label(Action::Next), "↓/j");
+
+    // Arrow keys only, with the letters free for typing a filter
+    let config = Config::parse(r#"
+        [keys]
+        next = ["down"]
+        prev = ["Up"]
+        delete = ["delete"]
+        filter = ["tab"]
+    "#)?;
+    assert_eq!(config.keys.action(&press(KeyCode::Char('j'))), None);
+    assert_eq!(config.keys.action(&press(KeyCode::Up)), Some(Action::Prev));
+    assert_eq!(config.keys.action(&press(KeyCode::Delete)), Some(Action::Delete));
+    assert_eq!(config.keys.action(&press(KeyCode::Tab)), Some(Action::Filter));
+    // Actions that aren't listed keep their default keys
+    assert_eq!(config.keys.action(&press(KeyCode::Char('c'))), Some(Action::Copy));
+
+    let err = Config::parse("[keys]\nnext = [\"dwn\"]").unwrap_err();
+    assert_eq!(err.to_string(), "Unknown key 'dwn' in keys.next");
+    assert!(Config::parse("[keys]\njump = [\"g\"]").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_apply_config() {
+    let config = Config { ls_limit: Some(0), ls_sort: Some(LsSort::Oldest), ..Config::default() };
+
+    let mut command = ls(None, false, false);
+    config.apply_to(&mut command);
+    assert!(matches!(command, Commands::Ls { limit: Some(0), asc: true, recent: false, .. }));
+
+    // Flags win over the config
+    let mut command = ls(Some(5), false, true);
+    config.apply_to(&mut command);
+    assert!(matches!(command, Commands::Ls { limit: Some(5), asc: false, recent: true, .. }));
+}
@@ -1,10 +1,11 @@
 use anyhow::Result;
 use chrono::Utc;
 use command_vault::db::{
-    models::{Command, Parameter},
+    models::{Command, Parameter, SortOrder},
     Database,
 };
-use std::fs;
+use command_vault::utils::limits::MAX_OUTPUT_LENGTH;
+use command_vault::utils::params::{substitute_parameters_using_defaults, substitute_parameters_with_history};
 use tempfile::tempdir;
 
 fn create_test_command(command: &str, tags: Vec<String>, parameters: Vec<Parameter>) -> Command {
@@ -15,6 +16,13 @@ fn create_test_command(command: &str, tags: Vec<String>, parameters: Vec<Paramet
         directory: "/test/dir".to_string(),
         tags,
         parameters,
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     }
 }
 
@@ -69,7 +77,7 @@ fn test_tag_operations() -> Result<()> {
     let id = db.add_command(&cmd)?;
 
     // Add more tags
-    db.add_tags_to_command(id, &vec!["vcs".to_string(), "status".to_string()])?;
+    db.add_tags_to_command(id, &["vcs".to_string(), "status".to_string()])?;
     let cmd = db.get_command(id)?.unwrap();
     assert!(cmd.tags.contains(&"git".to_string()));
     assert!(cmd.tags.contains(&"vcs".to_string()));
@@ -90,6 +98,143 @@ fn test_tag_operations() -> Result<()> {
     assert!(tags.iter().any(|(name, _)| name == "git"));
     assert!(tags.iter().any(|(name, _)| name == "vcs"));
 
+    assert_eq!(db.count_commands()?, 1);
+
+    let tags = db.list_tags_with_prefix("gi")?;
+    assert_eq!(tags, vec![("git".to_string(), 1)]);
+    assert!(db.list_tags_with_prefix("g_t")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_tags_are_normalized() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let mut cmd = create_test_command("git status", vec![" Git ".to_string(), "git".to_string(), " ".to_string()], vec![]);
+    let id = db.add_command(&cmd)?;
+    assert_eq!(db.get_command(id)?.unwrap().tags, vec!["git"]);
+
+    db.add_tags_to_command(id, &["GIT".to_string(), " VCS".to_string()])?;
+    assert_eq!(db.get_command(id)?.unwrap().tags, vec!["git", "vcs"]);
+
+    cmd.id = Some(id);
+    cmd.tags = vec!["Status ".to_string()];
+    db.update_command(&cmd)?;
+    assert_eq!(db.get_command(id)?.unwrap().tags, vec!["status"]);
+
+    assert_eq!(db.add_tag_to_commands(&[id], " Review")?, 1);
+    assert!(db.add_tag_to_commands(&[id], "  ").is_err());
+    let tags: Vec<String> = db.list_tags()?.into_iter().map(|(name, _)| name).collect();
+    assert!(tags.contains(&"review".to_string()));
+    assert!(!tags.contains(&"Git".to_string()));
+
+    // Tags are found the same way they're stored
+    assert_eq!(db.search_by_tag("Status", 0)?.len(), 1);
+    assert_eq!(db.search_by_tags(&[" REVIEW".to_string(), "status".to_string()], true, 0)?.len(), 1);
+    db.set_tag_color("Review", "red")?;
+    assert_eq!(db.tag_colors()?.get("review").map(String::as_str), Some("red"));
+    db.merge_tags("STATUS", " Checks")?;
+    let mut tags = db.get_command(id)?.unwrap().tags;
+    tags.sort();
+    assert_eq!(tags, vec!["checks", "review"]);
+
+    db.remove_tag_from_command(id, "Checks")?;
+    assert_eq!(db.remove_tag_from_commands(&[id], "REVIEW ")?, 1);
+    assert!(db.get_command(id)?.unwrap().tags.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_merge_tags() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let tags = |names: &[&str]| names.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    let only_k8s = db.add_command(&create_test_command("kubectl get pods", tags(&["k8s", "ops"]), vec![]))?;
+    let both = db.add_command(&create_test_command("kubectl apply", tags(&["kubernetes", "k8s"]), vec![]))?;
+    let untouched = db.add_command(&create_test_command("helm list", tags(&["kubernetes"]), vec![]))?;
+
+    assert_eq!(db.merge_tags("k8s", "kubernetes")?, 2);
+    let mut merged = db.get_command(only_k8s)?.unwrap().tags;
+    merged.sort();
+    assert_eq!(merged, vec!["kubernetes", "ops"]);
+    assert_eq!(db.get_command(both)?.unwrap().tags, vec!["kubernetes"]);
+    assert_eq!(db.get_command(untouched)?.unwrap().tags, vec!["kubernetes"]);
+
+    let listed = db.list_tags()?;
+    assert!(listed.contains(&("kubernetes".to_string(), 3)));
+    assert!(!listed.iter().any(|(name, _)| name == "k8s"));
+    assert_eq!(db.search_by_tag("kubernetes", 0)?.len(), 3);
+
+    assert!(db.merge_tags("k8s", "kubernetes").is_err());
+    assert!(db.merge_tags("ops", "ops").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_rehome_directories() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let mut add = |command: &str, directory: &str| -> Result<i64> {
+        let mut cmd = create_test_command(command, vec![], vec![]);
+        cmd.directory = directory.to_string();
+        db.add_command(&cmd)
+    };
+    let root = add("make", "/home/me/old/project")?;
+    let nested = add("cargo test", "/home/me/old/project/crates/core")?;
+    let sibling = add("ls", "/home/me/old/project2")?;
+    let elsewhere = add("pwd", "/tmp")?;
+
+    // A trailing slash on either side is fine
+    assert_eq!(db.rehome_directories("/home/me/old/project/", "/home/me/new/project")?, 2);
+    let directory = |id| db.get_command(id).map(|cmd| cmd.unwrap().directory);
+    assert_eq!(directory(root)?, "/home/me/new/project");
+    assert_eq!(directory(nested)?, "/home/me/new/project/crates/core");
+    assert_eq!(directory(sibling)?, "/home/me/old/project2");
+    assert_eq!(directory(elsewhere)?, "/tmp");
+
+    // Moved commands are found as duplicates in their new directory
+    let mut copy = create_test_command("make", vec![], vec![]);
+    copy.directory = "/home/me/new/project".to_string();
+    copy.timestamp = db.get_command(root)?.unwrap().timestamp;
+    assert_eq!(db.add_command(&copy)?, root);
+
+    assert_eq!(db.rehome_directories("/nowhere", "/somewhere")?, 0);
+    assert!(db.rehome_directories("/tmp", "/tmp/").is_err());
+    assert!(db.rehome_directories("", "/tmp").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_find_duplicates() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let now = Utc::now();
+    let mut add = |command: &str, directory: &str, age: i64| -> Result<i64> {
+        let mut cmd = create_test_command(command, vec![], vec![]);
+        cmd.directory = directory.to_string();
+        cmd.timestamp = now - chrono::Duration::hours(age);
+        db.add_copy(&cmd)
+    };
+    let old = add("git status", "/a", 2)?;
+    let new = add("git status", "/a", 1)?;
+    let elsewhere = add("git status", "/b", 3)?;
+    add("make", "/a", 1)?;
+
+    let ids = |groups: Vec<Vec<Command>>| -> Vec<Vec<i64>> {
+        groups.iter().map(|group| group.iter().filter_map(|cmd| cmd.id).collect()).collect()
+    };
+    assert_eq!(ids(db.find_duplicates(false)?), vec![vec![new, old]]);
+    assert_eq!(ids(db.find_duplicates(true)?), vec![vec![new, old, elsewhere]]);
     Ok(())
 }
 
@@ -201,7 +346,7 @@ fn test_edge_cases() -> Result<()> {
     assert!(db.delete_command(9999).is_err());
 
     // Test adding tags to non-existent command
-    assert!(db.add_tags_to_command(9999, &vec!["test".to_string()]).is_err());
+    assert!(db.add_tags_to_command(9999, &["test".to_string()]).is_err());
 
     // Test removing non-existent tag
     let cmd = create_test_command("test", vec!["tag1".to_string()], vec![]);
@@ -215,7 +360,7 @@ fn test_edge_cases() -> Result<()> {
 fn test_database_init() -> Result<()> {
     let temp_dir = tempdir()?;
     let db_path = temp_dir.path().join("test.db");
-    let db = Database::new(db_path.to_str().unwrap())?;
+    let _db = Database::new(db_path.to_str().unwrap())?;
 
     // Verify tables exist by attempting to use them
     let conn = rusqlite::Connection::open(db_path)?;
@@ -271,6 +416,13 @@ fn test_list_commands_no_limit() -> Result<()> {
             directory: "/test".to_string(),
             tags: vec![],
             parameters: Vec::new(),
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         };
         db.add_command(&command)?;
     }
@@ -311,6 +463,13 @@ fn test_tag_cleanup_after_deletion() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["tag1".to_string(), "tag2".to_string()],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let cmd2 = Command {
         id: None,
@@ -319,6 +478,13 @@ fn test_tag_cleanup_after_deletion() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["tag2".to_string(), "tag3".to_string()],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
 
     let id1 = db.add_command(&cmd1)?;
@@ -365,6 +531,13 @@ fn test_transaction_rollback() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["tag1".to_string(), "tag2".to_string()],
         parameters: Vec::new(),
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&cmd)?;
 
@@ -396,7 +569,7 @@ fn test_transaction_rollback() -> Result<()> {
     assert_eq!(command.tags.len(), 2);
 
     // Try to add tags to non-existent command
-    let result = db.add_tags_to_command(9999, &vec!["tag3".to_string()]);
+    let result = db.add_tags_to_command(9999, &["tag3".to_string()]);
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("not found"));
 
@@ -419,7 +592,7 @@ fn test_parameter_handling() -> Result<()> {
     // Test command with valid parameters
     let mut cmd = Command {
         id: None,
-        command: "test command".to_string(),
+        command: "test command @param1 @param2".to_string(),
         timestamp: Utc::now(),
         directory: "/test".to_string(),
         tags: vec![],
@@ -427,6 +600,13 @@ fn test_parameter_handling() -> Result<()> {
             Parameter::new("param1".to_string()),
             Parameter::with_description("param2".to_string(), Some("description".to_string())),
         ],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&cmd)?;
 
@@ -439,6 +619,7 @@ fn test_parameter_handling() -> Result<()> {
 
     // Test updating parameters
     cmd.id = Some(id);
+    cmd.command = "test command @new_param".to_string();
     cmd.parameters = vec![Parameter::new("new_param".to_string())];
     db.update_command(&cmd)?;
 
@@ -475,6 +656,13 @@ fn test_concurrent_access() -> Result<()> {
         directory: "/test".to_string(),
         tags: vec!["tag1".to_string()],
         parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
     let id = db.add_command(&cmd)?;
     let db_path = Arc::new(db_path.to_str().unwrap().to_string());
@@ -493,14 +681,21 @@ fn test_concurrent_access() -> Result<()> {
             // Try to update the command with retries
             let mut retries = 3;
             while retries > 0 {
-                if let Ok(_) = db.update_command(&Command {
+                if db.update_command(&Command {
                     id: Some(id),
                     command: format!("updated by thread {}", i),
                     timestamp: Utc::now(),
                     directory: "/test".to_string(),
                     tags: vec![],
                     parameters: vec![],
-                }) {
+                    note: None,
+                    alias: None,
+                    shell: None,
+                    interactive: None,
+                    hostname: None,
+                    created_at: None,
+                    confirm_before_run: false,
+                }).is_ok() {
                     break;
                 }
                 retries -= 1;
@@ -510,7 +705,7 @@ fn test_concurrent_access() -> Result<()> {
             // Try to add a new tag with retries
             let mut retries = 3;
             while retries > 0 {
-                if let Ok(_) = db.add_tags_to_command(id, &vec![format!("tag{}", i)]) {
+                if db.add_tags_to_command(id, &[format!("tag{}", i)]).is_ok() {
                     break;
                 }
                 retries -= 1;
@@ -540,3 +735,765 @@ fn test_concurrent_access() -> Result<()> {
     
     Ok(())
 }
+
+#[test]
+fn test_find_exact() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let id = db.add_command(&create_test_command("git status", vec![], vec![]))?;
+
+    assert_eq!(db.find_exact("git status", "/test/dir")?, Some(id));
+    // Same command, different directory
+    assert_eq!(db.find_exact("git status", "/other/dir")?, None);
+    // Substrings don't count as exact matches
+    assert_eq!(db.find_exact("git", "/test/dir")?, None);
+    Ok(())
+}
+
+#[test]
+fn test_import_is_idempotent() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let commands = vec![
+        create_test_command("git status", vec!["git".to_string()], vec![]),
+        create_test_command("cargo build", vec![], vec![]),
+        create_test_command("ls -la", vec![], vec![]),
+    ];
+
+    assert_eq!(db.import_commands(&commands)?, 3);
+    // Importing the same set again adds nothing
+    assert_eq!(db.import_commands(&commands)?, 0);
+    assert_eq!(db.list_commands(0, false)?.len(), 3);
+
+    // Even when it's imported again later, with new timestamps
+    let later: Vec<Command> = commands.iter()
+        .map(|c| Command { timestamp: c.timestamp + chrono::Duration::hours(1), ..c.clone() })
+        .collect();
+    assert_eq!(db.import_commands(&later)?, 0);
+    assert_eq!(db.list_commands(0, false)?.len(), 3);
+
+    // Re-adding an exact copy returns the existing ID
+    let existing = db.list_commands(0, false)?.into_iter()
+        .find(|c| c.command == "git status")
+        .unwrap();
+    assert_eq!(db.add_command(&existing)?, existing.id.unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_add_copy() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let command = create_test_command("cargo build", vec![], vec![]);
+    let id = db.add_command(&command)?;
+
+    // A deliberate copy is stored next to the original
+    let copy = db.add_copy(&command)?;
+    assert_ne!(copy, id);
+    assert_eq!(db.count_commands()?, 2);
+    // ...and imports still skip the command
+    assert_eq!(db.import_commands(std::slice::from_ref(&command))?, 0);
+
+    // Editing a command into a copy of another keeps both
+    let other = db.add_command(&create_test_command("cargo test", vec![], vec![]))?;
+    db.update_command(&Command { id: Some(other), ..command.clone() })?;
+    assert_eq!(db.count_commands()?, 3);
+
+    // A copy of a command that isn't stored yet is keyed like any other
+    let new = create_test_command("cargo fmt", vec![], vec![]);
+    let copy = db.add_copy(&new)?;
+    assert_eq!(db.add_command(&new)?, copy);
+    Ok(())
+}
+
+#[test]
+fn test_confirm_before_run_is_stored() -> Result<()> {
+    let dir = tempdir()?;
+    let mut db = Database::new(dir.path().join("test.db").to_str().unwrap())?;
+    let mut command = create_test_command("rm -rf build", vec![], vec![]);
+    command.confirm_before_run = true;
+    let id = db.add_command(&command)?;
+
+    let mut cmd = db.get_command(id)?.unwrap();
+    assert!(cmd.confirm_before_run);
+    assert!(db.list_commands(0, false)?[0].confirm_before_run);
+
+    cmd.confirm_before_run = false;
+    db.update_command(&cmd)?;
+    assert!(!db.get_command(id)?.unwrap().confirm_before_run);
+    Ok(())
+}
+
+#[test]
+fn test_created_at_survives_updates() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let id = db.add_command(&create_test_command("make", vec![], vec![]))?;
+    let mut cmd = db.get_command(id)?.unwrap();
+    let added = cmd.timestamp;
+    assert_eq!(cmd.created_at, Some(added));
+
+    cmd.command = "make test".to_string();
+    cmd.timestamp = added + chrono::Duration::hours(2);
+    db.update_command(&cmd)?;
+
+    let cmd = db.get_command(id)?.unwrap();
+    assert_eq!(cmd.timestamp, added + chrono::Duration::hours(2));
+    assert_eq!(cmd.created_at, Some(added));
+    assert_eq!(db.list_commands(0, false)?[0].created_at, Some(added));
+    Ok(())
+}
+
+#[test]
+fn test_content_hash_migration() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+
+    // Database created before the content_hash column existed
+    {
+        let conn = rusqlite::Connection::open(&db_path)?;
+        conn.execute(
+            "CREATE TABLE commands (
+                id INTEGER PRIMARY KEY,
+                command TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                directory TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '',
+                parameters TEXT NOT NULL DEFAULT '[]'
+            )",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO commands (command, timestamp, directory) VALUES ('echo old', ?1, '/test/dir')",
+            [Utc::now().to_rfc3339()],
+        )?;
+    }
+
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+    let old = db.get_command(1)?.unwrap();
+    assert_eq!(old.command, "echo old");
+
+    assert_eq!(old.note, None);
+    // Old commands were added when they were last changed, as far as anyone knows
+    assert_eq!(old.created_at, Some(old.timestamp));
+
+    // The backfilled hash makes re-adding the old command a no-op
+    assert_eq!(db.add_command(&old)?, 1);
+    assert_eq!(db.list_commands(0, false)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_content_hash_rehash_migration() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+
+    // Database whose hashes still included the timestamp
+    {
+        let db = Database::new(db_path.to_str().unwrap())?;
+        drop(db);
+        let conn = rusqlite::Connection::open(&db_path)?;
+        for (hash, timestamp) in [("old-1", "2024-01-01T00:00:00+00:00"), ("old-2", "2024-01-02T00:00:00+00:00")] {
+            conn.execute(
+                "INSERT INTO commands (command, timestamp, directory, content_hash) VALUES ('make', ?1, '/test/dir', ?2)",
+                [timestamp, hash],
+            )?;
+        }
+        conn.execute_batch("PRAGMA user_version = 0")?;
+    }
+
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+    // Both copies survive, and the oldest one now stands for the command
+    assert_eq!(db.count_commands()?, 2);
+    let mut command = create_test_command("make", vec![], vec![]);
+    command.timestamp = Utc::now();
+    assert_eq!(db.add_command(&command)?, 1);
+    assert_eq!(db.count_commands()?, 2);
+    Ok(())
+}
+
+#[test]
+fn test_search_by_multiple_tags() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    db.add_command(&create_test_command("git push", vec!["git".to_string(), "deploy".to_string()], vec![]))?;
+    db.add_command(&create_test_command("git status", vec!["git".to_string()], vec![]))?;
+    db.add_command(&create_test_command("kubectl apply", vec!["deploy".to_string()], vec![]))?;
+    db.add_command(&create_test_command("ls", vec!["fs".to_string()], vec![]))?;
+
+    let tags = vec!["git".to_string(), "deploy".to_string()];
+
+    // Any of the tags
+    let results = db.search_by_tags(&tags, false, 10)?;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|c| c.command != "ls"));
+
+    // All of the tags
+    let results = db.search_by_tags(&tags, true, 10)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].command, "git push");
+
+    // Repeated tags don't make the AND case impossible to satisfy
+    let results = db.search_by_tags(&["git".to_string(), "git".to_string()], true, 10)?;
+    assert_eq!(results.len(), 2);
+
+    // Limit still applies
+    assert_eq!(db.search_by_tags(&tags, false, 2)?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_list_command_summaries() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    for (i, command) in ["git status", "cargo build", "ls -la"].iter().enumerate() {
+        let mut cmd = create_test_command(command, vec!["tag".to_string()], vec![]);
+        cmd.timestamp = Utc::now() + chrono::Duration::seconds(i as i64);
+        db.add_command(&cmd)?;
+    }
+
+    for (limit, ascending) in [(0, false), (0, true), (2, false), (2, true)] {
+        let full: Vec<(i64, String)> = db.list_commands(limit, ascending)?
+            .into_iter()
+            .map(|c| (c.id.unwrap(), c.command))
+            .collect();
+        assert_eq!(db.list_command_summaries(limit, ascending)?, full);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_list_by_last_run() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let mut ids = Vec::new();
+    for (i, command) in ["git status", "cargo build", "ls -la"].iter().enumerate() {
+        let mut cmd = create_test_command(command, vec![], vec![]);
+        cmd.timestamp = Utc::now() + chrono::Duration::seconds(i as i64);
+        ids.push(db.add_command(&cmd)?);
+    }
+
+    // Nothing has run yet, so this is newest first
+    let order: Vec<String> = db.list_by_last_run(0)?.into_iter().map(|c| c.command).collect();
+    assert_eq!(order, vec!["ls -la", "cargo build", "git status"]);
+
+    db.record_run(ids[1])?;
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    db.record_run(ids[0])?;
+
+    // Run commands first, most recent run first; never-run ones after
+    let order: Vec<String> = db.list_by_last_run(0)?.into_iter().map(|c| c.command).collect();
+    assert_eq!(order, vec!["git status", "cargo build", "ls -la"]);
+    assert_eq!(db.list_by_last_run(1)?.len(), 1);
+
+    let (sql, params) = Database::list_by_last_run_query(5);
+    assert!(sql.contains("NULLS LAST"));
+    assert_eq!(params, vec!["5".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn test_list_commands_paged() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        ids.push(db.add_command(&create_test_command(&format!("echo {}", i), vec![], vec![]))?);
+    }
+    let page_ids = |commands: Vec<Command>| commands.into_iter().map(|c| c.id.unwrap()).collect::<Vec<_>>();
+
+    // Walking forwards, each page starts after the last ID of the previous one
+    assert_eq!(page_ids(db.list_commands_paged(Some(0), None, 2)?), vec![ids[0], ids[1]]);
+    assert_eq!(page_ids(db.list_commands_paged(Some(ids[1]), None, 2)?), vec![ids[2], ids[3]]);
+    assert_eq!(page_ids(db.list_commands_paged(Some(ids[3]), None, 2)?), vec![ids[4]]);
+    assert!(db.list_commands_paged(Some(ids[4]), None, 2)?.is_empty());
+
+    // Walking backwards
+    assert_eq!(page_ids(db.list_commands_paged(None, Some(ids[4]), 2)?), vec![ids[3], ids[2]]);
+    assert_eq!(page_ids(db.list_commands_paged(None, Some(ids[2]), 0)?), vec![ids[1], ids[0]]);
+
+    // A range, in ID order
+    assert_eq!(page_ids(db.list_commands_paged(Some(ids[0]), Some(ids[4]), 0)?), vec![ids[1], ids[2], ids[3]]);
+
+    let (sql, params) = Database::list_commands_paged_query(Some(10), None, 5);
+    assert!(sql.contains("c.id > ?1"));
+    assert!(sql.contains("LIMIT ?2"));
+    assert_eq!(params, vec!["10".to_string(), "5".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn test_parameter_history() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let cmd = create_test_command("git checkout @branch", vec![], vec![]);
+    let id = db.add_command(&cmd)?;
+
+    assert_eq!(db.last_param_value(id, "branch")?, None);
+
+    db.record_param_value(id, "branch", "main")?;
+    db.record_param_value(id, "branch", "develop")?;
+    assert_eq!(db.last_param_value(id, "branch")?, Some("develop".to_string()));
+    assert_eq!(db.last_param_value(id, "other")?, None);
+
+    // History goes away with its command
+    db.delete_command(id)?;
+    assert_eq!(db.last_param_value(id, "branch")?, None);
+    Ok(())
+}
+
+#[test]
+fn test_parameters_follow_command_text() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    // `stale` isn't in the text and `branch` is missing from the list
+    let cmd = create_test_command(
+        "git push @remote @branch:Branch",
+        vec![],
+        vec![
+            Parameter::with_description("stale".to_string(), None),
+            Parameter::with_description("remote".to_string(), Some("Remote name".to_string())),
+        ],
+    );
+    let id = db.add_command(&cmd)?;
+    let saved = db.get_command(id)?.unwrap();
+    let names: Vec<&str> = saved.parameters.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["remote", "branch"]);
+    // Descriptions the text doesn't give are kept
+    assert_eq!(saved.parameters[0].description, Some("Remote name".to_string()));
+    assert_eq!(saved.parameters[1].description, Some("Branch".to_string()));
+
+    // Updating re-derives them from the new text
+    let mut updated = saved.clone();
+    updated.command = "git pull @remote".to_string();
+    db.update_command(&updated)?;
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.parameters.len(), 1);
+    assert_eq!(saved.parameters[0].name, "remote");
+    Ok(())
+}
+
+#[test]
+fn test_parameter_defaults() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut branch = Parameter::with_description("branch".to_string(), Some("Branch to push".to_string()));
+    branch.default_value = Some("main".to_string());
+    let id = db.add_command(&create_test_command("git push origin @branch", vec![], vec![branch]))?;
+
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.parameters[0].description, Some("Branch to push".to_string()));
+    assert_eq!(saved.parameters[0].default_value, Some("main".to_string()));
+
+    // Without input, the stored default is used
+    let result = substitute_parameters_with_history(&mut db, Some(id), &saved.command, Some(""))?.command;
+    assert_eq!(result, "git push origin main");
+    let result = substitute_parameters_with_history(&mut db, Some(id), &saved.command, Some("dev"))?.command;
+    assert_eq!(result, "git push origin dev");
+    Ok(())
+}
+
+#[test]
+fn test_search_limit_zero_returns_everything() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    for i in 0..60 {
+        let mut cmd = create_test_command(&format!("echo x{}", i), vec!["x".to_string(), "y".to_string()], vec![]);
+        cmd.timestamp = Utc::now() + chrono::Duration::seconds(i);
+        db.add_command(&cmd)?;
+    }
+
+    assert_eq!(db.search_commands("x", 0)?.len(), 60);
+    assert_eq!(db.search_commands("x", 10)?.len(), 10);
+    assert_eq!(db.search_by_tag("x", 0)?.len(), 60);
+    assert_eq!(db.search_by_tags(&["x".to_string(), "y".to_string()], true, 0)?.len(), 60);
+
+    let (sql, params) = Database::search_commands_query("x", 0);
+    assert!(!sql.contains("LIMIT"));
+    assert_eq!(params, vec!["x".to_string()]);
+
+    let (sql, params) = Database::search_by_tag_query("x", 5);
+    assert!(sql.contains("LIMIT ?2"));
+    assert_eq!(params, vec!["x".to_string(), "5".to_string()]);
+    let tags = ["y".to_string(), "x".to_string(), "y".to_string()];
+    let (sql, params) = Database::search_by_tags_query(&tags, true, 5);
+    assert!(sql.contains("HAVING COUNT(DISTINCT t.name) = 2"));
+    assert!(sql.contains("LIMIT ?3"));
+    assert_eq!(params, vec!["x".to_string(), "y".to_string(), "5".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn test_search_commands_cased() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    db.add_command(&create_test_command("echo $HOME", vec![], vec![]))?;
+    db.add_command(&create_test_command("echo $home", vec![], vec![]))?;
+    db.add_command(&create_test_command("ls *.rs", vec![], vec![]))?;
+
+    assert_eq!(db.search_commands("HOME", 0)?.len(), 2);
+
+    let results = db.search_commands_cased("HOME", 0)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].command, "echo $HOME");
+
+    // Glob characters in the query match literally
+    assert_eq!(db.search_commands_cased("*.rs", 0)?.len(), 1);
+    assert!(db.search_commands_cased("l?", 0)?.is_empty());
+
+    let (sql, params) = Database::search_commands_cased_query("[x]", 5);
+    assert!(sql.contains("GLOB"));
+    assert_eq!(params, vec!["[[]x]".to_string(), "5".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn test_search_commands_sorted() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let now = Utc::now();
+    for (age, command) in [(3, "git log"), (2, "tig"), (1, "echo git")] {
+        let mut cmd = create_test_command(command, vec![], vec![]);
+        cmd.timestamp = now - chrono::Duration::hours(age);
+        db.add_command(&cmd)?;
+    }
+    let mut cmd = create_test_command("ls", vec![], vec![]);
+    cmd.note = Some("not git".to_string());
+    db.add_command(&cmd)?;
+
+    let commands = |order, case_sensitive| -> Result<Vec<String>> {
+        Ok(db.search_commands_sorted("GIT", 0, order, case_sensitive)?.into_iter().map(|c| c.command).collect())
+    };
+    assert_eq!(commands(SortOrder::Newest, false)?, vec!["ls", "echo git", "git log"]);
+    assert_eq!(commands(SortOrder::Oldest, false)?, vec!["git log", "echo git", "ls"]);
+    assert_eq!(commands(SortOrder::Command, false)?, vec!["echo git", "git log", "ls"]);
+    // Earliest match first; a match only in the note comes last
+    assert_eq!(commands(SortOrder::Relevance, false)?, vec!["git log", "echo git", "ls"]);
+    assert!(commands(SortOrder::Relevance, true)?.is_empty());
+
+    // The limit comes after the relevance parameter
+    let (sql, params) = Database::search_commands_sorted_query("git", 1, SortOrder::Relevance, false);
+    assert!(sql.contains("LIMIT ?3"));
+    assert_eq!(params, vec!["git", "git", "1"]);
+    assert_eq!(db.search_commands_sorted("git", 1, SortOrder::Relevance, false)?[0].command, "git log");
+
+    // The default order is unchanged
+    assert_eq!(
+        Database::search_commands_sorted_query("git", 10, SortOrder::Newest, false),
+        Database::search_commands_query("git", 10)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_command_alias() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let mut cmd = create_test_command("kubectl rollout restart deploy/api", vec![], vec![]);
+    cmd.alias = Some("restart-api".to_string());
+    let id = db.add_command(&cmd)?;
+
+    let found = db.get_command_by_alias("restart-api")?.unwrap();
+    assert_eq!(found.id, Some(id));
+    assert_eq!(found.alias, Some("restart-api".to_string()));
+    assert_eq!(db.list_commands(0, false)?[0].alias, Some("restart-api".to_string()));
+    assert!(db.get_command_by_alias("other")?.is_none());
+
+    // Re-adding the very same command (e.g. an import) keeps working
+    assert_eq!(db.add_command(&cmd)?, id);
+
+    // Another command can't take the alias
+    let mut other = create_test_command("kubectl get pods", vec![], vec![]);
+    other.alias = Some("restart-api".to_string());
+    let err = db.add_command(&other).unwrap_err();
+    assert!(err.to_string().contains(&format!("Alias 'restart-api' is already used by command {}", id)));
+
+    other.alias = None;
+    let other_id = db.add_command(&other)?;
+    let mut other = db.get_command(other_id)?.unwrap();
+    other.alias = Some("restart-api".to_string());
+    assert!(db.update_command(&other).is_err());
+
+    // A command can keep its own alias when it is updated
+    let mut found = found;
+    found.note = Some("after a config change".to_string());
+    db.update_command(&found)?;
+    assert_eq!(db.get_command(id)?.unwrap().alias, Some("restart-api".to_string()));
+
+    // Numbers would be read as IDs
+    for alias in ["42", "-5", "+5", "", "two words"] {
+        other.alias = Some(alias.to_string());
+        assert!(db.update_command(&other).unwrap_err().to_string().contains("Invalid alias"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_command_note() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    let mut cmd = create_test_command("kubectl rollout restart deploy/api", vec![], vec![]);
+    cmd.note = Some("Use after rotating the API secrets".to_string());
+    let id = db.add_command(&cmd)?;
+    db.add_command(&create_test_command("echo unrelated", vec![], vec![]))?;
+
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.note, cmd.note);
+    assert_eq!(db.list_commands(0, false)?.iter().filter(|c| c.note.is_some()).count(), 1);
+
+    // Notes are searchable
+    let results = db.search_commands("rotating", 10)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, Some(id));
+
+    // And can be changed or cleared
+    let mut updated = saved.clone();
+    updated.note = None;
+    db.update_command(&updated)?;
+    assert_eq!(db.get_command(id)?.unwrap().note, None);
+    assert!(db.search_commands("rotating", 10)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_maintain() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test.db");
+    let mut db = Database::new(db_path.to_str().unwrap())?;
+
+    // Safe on an empty database
+    let (before, after) = db.maintain()?;
+    assert!(before > 0 && after > 0);
+
+    let big = "x".repeat(4096);
+    let mut ids = Vec::new();
+    for i in 0..50 {
+        let mut cmd = create_test_command(&format!("echo {} {}", i, big), vec!["tag".to_string()], vec![]);
+        cmd.timestamp = Utc::now() + chrono::Duration::seconds(i);
+        ids.push(db.add_command(&cmd)?);
+    }
+    for id in &ids[1..] {
+        db.delete_command(*id)?;
+    }
+
+    // Deleted pages are given back, and the remaining data is intact
+    let (before, after) = db.maintain()?;
+    assert!(after < before, "expected {} < {}", after, before);
+    assert_eq!(db.list_commands(0, false)?.len(), 1);
+    assert!(db.get_command(ids[0])?.is_some());
+    Ok(())
+}
+
+#[test]
+fn test_tag_colors() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    assert!(db.tag_colors()?.is_empty());
+
+    db.set_tag_color("prod", "red")?;
+    db.set_tag_color("safe", "green")?;
+    // Setting a color again replaces it
+    db.set_tag_color("prod", "#ff8800")?;
+
+    let colors = db.tag_colors()?;
+    assert_eq!(colors.len(), 2);
+    assert_eq!(colors["prod"], "#ff8800");
+    assert_eq!(colors["safe"], "green");
+    Ok(())
+}
+
+#[test]
+fn test_record_output() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let id = db.add_command(&create_test_command("date", vec![], vec![]))?;
+    assert_eq!(db.last_output(id)?, None);
+
+    db.record_output(id, "first\n")?;
+    db.record_output(id, "second\n")?;
+    assert_eq!(db.last_output(id)?, Some("second\n".to_string()));
+
+    // Large output is capped
+    db.record_output(id, &"x".repeat(MAX_OUTPUT_LENGTH * 2))?;
+    assert_eq!(db.last_output(id)?.unwrap().len(), MAX_OUTPUT_LENGTH);
+    Ok(())
+}
+
+#[test]
+fn test_command_hostname() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut cmd = create_test_command("uptime", vec![], vec![]);
+    cmd.hostname = Some("build-box".to_string());
+    let id = db.add_command(&cmd)?;
+
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.hostname.as_deref(), Some("build-box"));
+    assert_eq!(db.list_commands(0, false)?[0].hostname.as_deref(), Some("build-box"));
+    assert_eq!(db.search_commands("uptime", 0)?[0].hostname.as_deref(), Some("build-box"));
+
+    // Editing a command keeps where it came from
+    let mut updated = saved.clone();
+    updated.command = "uptime -p".to_string();
+    db.update_command(&updated)?;
+    assert_eq!(db.get_command(id)?.unwrap().hostname.as_deref(), Some("build-box"));
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_tags_are_stored_once() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let id = db.add_command(&create_test_command("git status", vec!["git".to_string(), "git".to_string()], vec![]))?;
+
+    assert_eq!(db.get_command(id)?.unwrap().tags, vec!["git"]);
+    assert_eq!(db.list_commands(0, false)?[0].tags, vec!["git"]);
+    assert_eq!(db.list_tags()?, vec![("git".to_string(), 1)]);
+
+    let mut cmd = db.get_command(id)?.unwrap();
+    cmd.tags = vec!["vcs".to_string(), "git".to_string(), "vcs".to_string()];
+    db.update_command(&cmd)?;
+    assert_eq!(db.list_commands(0, false)?[0].tags, vec!["vcs", "git"]);
+    assert_eq!(db.list_tags()?, vec![("git".to_string(), 1), ("vcs".to_string(), 1)]);
+    Ok(())
+}
+
+#[test]
+fn test_command_shell() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut cmd = create_test_command("echo $BASH_VERSION", vec![], vec![]);
+    cmd.shell = Some("bash".to_string());
+    let id = db.add_command(&cmd)?;
+
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.shell, Some("bash".to_string()));
+    assert_eq!(db.list_commands(0, false)?[0].shell, Some("bash".to_string()));
+
+    let mut updated = saved.clone();
+    updated.shell = None;
+    db.update_command(&updated)?;
+    assert_eq!(db.get_command(id)?.unwrap().shell, None);
+    Ok(())
+}
+
+#[test]
+fn test_command_interactive_flag() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut level = Parameter::new("level".to_string());
+    level.default_value = Some("info".to_string());
+    let mut cmd = create_test_command(
+        "notify @channel --level @level",
+        vec![],
+        vec![Parameter::new("channel".to_string()), level],
+    );
+    cmd.interactive = Some(false);
+    let id = db.add_command(&cmd)?;
+
+    let saved = db.get_command(id)?.unwrap();
+    assert_eq!(saved.interactive, Some(false));
+    assert_eq!(db.list_commands(0, false)?[0].interactive, Some(false));
+
+    // Never prompts: defaults are filled in, other parameters stay as written
+    let result = substitute_parameters_with_history(&mut db, Some(id), &saved.command, Some("ignored"))?.command;
+    assert_eq!(result, "notify @channel --level info");
+
+    let mut updated = saved.clone();
+    updated.interactive = Some(true);
+    db.update_command(&updated)?;
+    assert_eq!(db.get_command(id)?.unwrap().interactive, Some(true));
+    let result = substitute_parameters_with_history(&mut db, Some(id), &saved.command, Some("x\ndebug"))?.command;
+    assert_eq!(result, "notify x --level debug");
+    Ok(())
+}
+
+#[test]
+fn test_substitute_parameters_using_defaults() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let mut level = Parameter::new("level".to_string());
+    level.default_value = Some("info".to_string());
+    let cmd = create_test_command(
+        "notify @channel --level @level",
+        vec![],
+        vec![Parameter::new("channel".to_string()), level],
+    );
+    let id = db.add_command(&cmd)?;
+
+    // Only the parameter without a default is asked for
+    let result = substitute_parameters_using_defaults(&mut db, Some(id), &cmd.command, Some("ops\ndebug"))?.command;
+    assert_eq!(result, "notify ops --level info");
+
+    // Without the flag every parameter is asked for
+    let result = substitute_parameters_with_history(&mut db, Some(id), &cmd.command, Some("ops\ndebug"))?.command;
+    assert_eq!(result, "notify ops --level debug");
+
+    // `@@` stays a literal `@` rather than becoming a parameter once defaults are in
+    let mut host = Parameter::new("host".to_string());
+    host.default_value = Some("example.com".to_string());
+    let cmd = create_test_command("mail @@team --host @host", vec![], vec![host]);
+    let id = db.add_command(&cmd)?;
+    let result = substitute_parameters_using_defaults(&mut db, Some(id), &cmd.command, Some("ignored"))?.command;
+    assert_eq!(result, "mail @team --host example.com");
+    Ok(())
+}
+
+#[test]
+fn test_substitution_masks_secrets() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let cmd = create_test_command("curl -u admin:@password! @url", vec![], vec![]);
+    let id = db.add_command(&cmd)?;
+
+    // Only the command that runs has the secret value
+    let result = substitute_parameters_with_history(&mut db, Some(id), &cmd.command, Some("hunter2\nexample.com"))?;
+    assert_eq!(result.command, "curl -u admin:hunter2 example.com");
+    assert_eq!(result.display, "curl -u admin:**** example.com");
+    Ok(())
+}
+
+#[test]
+fn test_batch_tagging() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    let docker = db.add_command(&create_test_command("docker ps", vec!["ops".to_string()], vec![]))?;
+    let compose = db.add_command(&create_test_command("docker compose up", vec![], vec![]))?;
+    let git = db.add_command(&create_test_command("git status", vec![], vec![]))?;
+
+    // Missing IDs are skipped
+    assert_eq!(db.add_tag_to_commands(&[docker, compose, 999], "docker")?, 2);
+    assert_eq!(db.get_command(docker)?.unwrap().tags, vec!["ops".to_string(), "docker".to_string()]);
+    assert_eq!(db.search_by_tag("docker", 10)?.len(), 2);
+    assert_eq!(db.list_commands(0, false)?.iter().filter(|c| c.tags.contains(&"docker".to_string())).count(), 2);
+
+    // Already tagged
+    assert_eq!(db.add_tag_to_commands(&[docker], "docker")?, 0);
+
+    assert_eq!(db.remove_tag_from_commands(&[docker, compose, git], "docker")?, 2);
+    assert_eq!(db.get_command(docker)?.unwrap().tags, vec!["ops".to_string()]);
+    assert!(db.list_commands(0, false)?.iter().all(|c| !c.tags.contains(&"docker".to_string())));
+    assert!(!db.list_tags()?.iter().any(|(name, _)| name == "docker"));
+
+    // Nothing matched, so no unused tag is created
+    assert_eq!(db.add_tag_to_commands(&[], "unused")?, 0);
+    assert!(!db.list_tags()?.iter().any(|(name, _)| name == "unused"));
+    Ok(())
+}
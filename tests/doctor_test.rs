@@ -0,0 +1,44 @@
+use anyhow::Result;
+use command_vault::cli::doctor::{format_check, run_checks, Check, Status};
+use tempfile::tempdir;
+
+fn find<'a>(checks: &'a [Check], name: &str) -> &'a Check {
+    checks.iter().find(|check| check.name == name).expect("check is run")
+}
+
+#[test]
+fn test_doctor_checks() -> Result<()> {
+    let dir = tempdir()?;
+    let db_path = dir.path().join("commands.db");
+
+    let checks = run_checks(&db_path);
+    let names: Vec<&str> = checks.iter().map(|check| check.name).collect();
+    assert_eq!(names, vec!["Shell", "Shell integration", "Clipboard", "Database", "SQLite"]);
+
+    // The database is created on first use
+    assert_eq!(find(&checks, "Database").status, Status::Warn);
+    std::fs::write(&db_path, "")?;
+    assert_eq!(find(&run_checks(&db_path), "Database").status, Status::Ok);
+
+    // A directory can't be opened for writing
+    assert_eq!(find(&run_checks(dir.path()), "Database").status, Status::Fail);
+
+    // The scripts ship with the source tree
+    assert_eq!(find(&checks, "Shell integration").status, Status::Ok);
+    assert!(find(&checks, "SQLite").detail.starts_with(rusqlite_version().as_str()));
+    Ok(())
+}
+
+#[test]
+fn test_format_check() {
+    colored::control::set_override(false);
+    let check = Check { name: "Clipboard", status: Status::Warn, detail: "xclip (default) not found on $PATH".to_string() };
+    assert_eq!(format_check(&check), "[WARN] Clipboard: xclip (default) not found on $PATH");
+    let check = Check { name: "Shell", status: Status::Ok, detail: "zsh".to_string() };
+    assert_eq!(format_check(&check), "[ OK ] Shell: zsh");
+}
+
+fn rusqlite_version() -> String {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.query_row("SELECT sqlite_version()", [], |row| row.get(0)).unwrap()
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use command_vault::ui::editor::edit_with;
+
+#[test]
+fn test_edit_with_changes() -> Result<()> {
+    let edited = edit_with("sed -i s/world/there/", "echo hello world")?;
+    assert_eq!(edited.as_deref(), Some("echo hello there"));
+
+    // Newlines inside the command survive, only the final one is dropped
+    let edited = edit_with("sed -i s/one/two/", "echo one &&\necho three")?;
+    assert_eq!(edited.as_deref(), Some("echo two &&\necho three"));
+    Ok(())
+}
+
+#[test]
+fn test_edit_with_abandoned() -> Result<()> {
+    // Saved unchanged
+    assert_eq!(edit_with("true", "echo hello")?, None);
+    // Emptied
+    assert_eq!(edit_with("sed -i d", "echo hello")?, None);
+    // Editor exited with an error
+    assert_eq!(edit_with("sed -i s/hello/bye/;q1", "echo hello")?, None);
+
+    assert!(edit_with("command-vault-no-such-editor", "echo hello").is_err());
+    assert!(edit_with("", "echo hello").is_err());
+    Ok(())
+}
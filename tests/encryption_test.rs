@@ -0,0 +1,100 @@
+use anyhow::Result;
+use command_vault::db::{Command, Database};
+use chrono::Utc;
+use tempfile::tempdir;
+
+fn sample_command() -> Command {
+    Command {
+        id: None,
+        command: "curl -H 'Authorization: secret-token' example.com".to_string(),
+        timestamp: Utc::now(),
+        directory: "/tmp".to_string(),
+        tags: vec!["api".to_string()],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    }
+}
+
+#[test]
+fn test_plain_database_is_not_encrypted() -> Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("test.db");
+    let path = path.to_str().unwrap();
+
+    // Missing files and in-memory databases stay unencrypted
+    assert!(!Database::is_encrypted(path));
+    assert!(!Database::is_encrypted(":memory:"));
+
+    let mut db = Database::new(path)?;
+    db.add_command(&sample_command())?;
+    assert!(!Database::is_encrypted(path));
+    Ok(())
+}
+
+#[test]
+fn test_new_rejects_encrypted_file() -> Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("test.db");
+    // Anything without the SQLite header looks encrypted
+    std::fs::write(&path, [0x5a; 64])?;
+
+    let path = path.to_str().unwrap();
+    assert!(Database::is_encrypted(path));
+    let err = Database::new(path).err().expect("opening should fail");
+    assert!(err.to_string().contains("COMMAND_VAULT_KEY"));
+    Ok(())
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_encrypted_round_trip() -> Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("test.db");
+    let path = path.to_str().unwrap();
+
+    {
+        let mut db = Database::open_encrypted(path, "hunter2")?;
+        db.add_command(&sample_command())?;
+    }
+
+    // The secret isn't readable from the file
+    let raw = std::fs::read(path)?;
+    assert!(!raw.windows(12).any(|w| w == b"secret-token"));
+    assert!(Database::is_encrypted(path));
+
+    assert!(Database::open_encrypted(path, "wrong").is_err());
+    let db = Database::open_encrypted(path, "hunter2")?;
+    assert_eq!(db.search_commands("secret", 10)?.len(), 1);
+    Ok(())
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_lock_and_unlock() -> Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("test.db");
+    let path = path.to_str().unwrap();
+
+    let mut db = Database::new(path)?;
+    db.add_command(&sample_command())?;
+
+    db.set_passphrase(Some("hunter2"))?;
+    assert!(Database::is_encrypted(path));
+    assert_eq!(db.list_commands(0, false)?.len(), 1);
+    drop(db);
+
+    let mut db = Database::open_encrypted(path, "hunter2")?;
+    db.set_passphrase(None)?;
+    assert!(!Database::is_encrypted(path));
+    drop(db);
+
+    let db = Database::new(path)?;
+    assert_eq!(db.list_commands(0, false)?[0].tags, vec!["api"]);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use command_vault::exec::execute_command;
+use command_vault::exec::{capture_shell_command, exec_invocation, execute_command, execute_shell_command, find_shell, is_path_traversal_attempt, looks_binary, resolve_directory, wrap_command, ExecutionContext};
 use command_vault::db::models::{Command, Parameter};
 use std::env;
 use std::fs;
@@ -7,7 +7,6 @@ use tempfile::TempDir;
 use chrono::Utc;
 use std::thread;
 use std::time::Duration;
-use std::io::Cursor;
 
 #[cfg(test)]
 mod tests {
@@ -21,6 +20,13 @@ mod tests {
             timestamp: Utc::now(),
             tags: vec![],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         }
     }
 
@@ -52,8 +58,7 @@ mod tests {
         
         // Verify the directory exists and is accessible
         if !temp_path.exists() || !temp_path.is_dir() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            return Err(std::io::Error::other(
                 "Failed to create temporary directory"
             ));
         }
@@ -73,8 +78,7 @@ mod tests {
         
         // Verify the file was created
         if !test_file.exists() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            return Err(std::io::Error::other(
                 "Failed to create test file"
             ));
         }
@@ -138,6 +142,9 @@ mod tests {
             Parameter {
                 name: "message".to_string(),
                 description: Some("Test message".to_string()),
+                pattern: None,
+                default_value: None,
+                secret: false,
             },
         ];
         
@@ -152,6 +159,134 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_non_interactive_command_uses_defaults() -> std::io::Result<()> {
+        let (temp_dir, temp_path) = get_safe_temp_dir()?;
+        let dir_path = temp_path.canonicalize()?.to_string_lossy().to_string();
+        ensure_directory_exists(&temp_path)?;
+
+        setup_test_env();
+        let mut command = create_test_command("echo @greeting > @file");
+        command.directory = dir_path;
+        command.interactive = Some(false);
+        command.parameters = vec![
+            Parameter {
+                name: "greeting".to_string(),
+                description: None,
+                pattern: None,
+                default_value: Some("hello".to_string()),
+                secret: false,
+            },
+            Parameter {
+                name: "file".to_string(),
+                description: None,
+                pattern: None,
+                default_value: Some("out.txt".to_string()),
+                secret: false,
+            },
+        ];
+
+        let result = execute_command(&command);
+        cleanup_test_env();
+        assert!(result.is_ok(), "Command failed: {:?}", result.err());
+
+        // The test input was never asked for
+        assert_eq!(fs::read_to_string(temp_path.join("out.txt"))?.trim(), "hello");
+        drop(temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_leading_assignment_is_left_alone() {
+        // Nothing is prepended to the command itself, so an assignment at the
+        // start is still an assignment followed by the rest of the command
+        let command = "FOO=bar; echo $FOO";
+        assert!(wrap_command(command, false).ends_with(&format!("; {}", command)));
+        assert_eq!(wrap_command(command, true), command);
+
+        let output = execute_shell_command(&ExecutionContext {
+            command: command.to_string(),
+            directory: env::temp_dir().to_string_lossy().to_string(),
+            test_mode: true,
+            debug_mode: false,
+            shell: Some("/bin/sh".to_string()),
+        }).expect("command runs");
+        assert_eq!(output.trim(), "bar");
+    }
+
+    #[test]
+    fn test_exec_invocation() {
+        let mut command = create_test_command("ls -la");
+        command.id = Some(42);
+        assert_eq!(exec_invocation(&command), "command-vault exec 42");
+
+        let mut command = create_test_command("deploy @env --version @version:/^v[0-9]+$/");
+        command.id = Some(7);
+        assert_eq!(exec_invocation(&command), "command-vault exec 7  # prompts for: env, version");
+    }
+
+    #[test]
+    fn test_missing_shell_falls_back_to_sh() {
+        let ctx = ExecutionContext {
+            command: "echo fallback".to_string(),
+            directory: env::temp_dir().to_string_lossy().to_string(),
+            test_mode: true,
+            debug_mode: false,
+            shell: None,
+        };
+
+        for shell in ["/nonexistent/zsh", ""] {
+            env::set_var("SHELL", shell);
+            let output = execute_shell_command(&ctx);
+            env::remove_var("SHELL");
+            assert_eq!(output.expect("falls back to /bin/sh").trim(), "fallback");
+        }
+    }
+
+    #[test]
+    fn test_capture_shell_command() {
+        let ctx = |command: &str| ExecutionContext {
+            command: command.to_string(),
+            directory: env::temp_dir().to_string_lossy().to_string(),
+            test_mode: true,
+            debug_mode: false,
+            shell: Some("/bin/sh".to_string()),
+        };
+
+        let result = capture_shell_command(&ctx("echo out; echo err >&2")).expect("command runs");
+        assert_eq!(result.command, "echo out; echo err >&2");
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.stdout, "out\n");
+        assert_eq!(result.stderr, "err\n");
+
+        // A failing command is reported, not an error
+        let result = capture_shell_command(&ctx("exit 3")).expect("command runs");
+        assert_eq!(result.exit_code, Some(3));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["exit_code"], 3);
+        assert_eq!(json["stdout"], "");
+    }
+
+    #[test]
+    fn test_binary_output_is_not_kept() {
+        assert!(!looks_binary(b"plain text\n"));
+        assert!(!looks_binary("caf\u{e9} \u{2713}".as_bytes()));
+        // One stray Latin-1 byte in a long line is still text
+        assert!(!looks_binary(b"file name with caf\xe9 in it, printed lossily"));
+        assert!(looks_binary(b"ELF\0\x01\x02"));
+        assert!(looks_binary(b"\xff\xfe\xfd\xfcab"));
+
+        let output = execute_shell_command(&ExecutionContext {
+            command: "printf 'a\\000b'".to_string(),
+            directory: env::temp_dir().to_string_lossy().to_string(),
+            test_mode: true,
+            debug_mode: false,
+            shell: Some("/bin/sh".to_string()),
+        }).expect("command runs");
+        assert_eq!(output, "");
+    }
+
     #[test]
     fn test_command_with_quoted_parameters() -> std::io::Result<()> {
         let (temp_dir, temp_path) = get_safe_temp_dir()?;
@@ -201,28 +336,51 @@ mod tests {
     #[test]
     fn test_command_with_directory_traversal() -> std::io::Result<()> {
         let (temp_dir, temp_path) = get_safe_temp_dir()?;
-        let dir_path = temp_path.canonicalize()?.to_string_lossy().to_string();
-        
+
         // Create a test directory structure
         let test_dir = temp_path.join("test_dir");
         ensure_directory_exists(&test_dir)?;
-        
-        // Create a test file in the test directory
-        let test_file = test_dir.join("test.txt");
-        fs::write(&test_file, "test content")?;
-        
-        // Small delay to ensure file is written
-        thread::sleep(Duration::from_millis(100));
-        
-        // Attempt to traverse outside the test directory
-        let mut command = create_test_command("cat ../test.txt");
+        fs::write(temp_path.join("test.txt"), "test content")?;
+
+        // `..` in the command is ordinary shell usage
+        let mut command = create_test_command("cat ../test.txt && cd .. && echo a..b");
         command.directory = test_dir.canonicalize()?.to_string_lossy().to_string();
-        
+
         setup_test_env();
         let result = execute_command(&command);
         cleanup_test_env();
-        
-        assert!(result.is_err(), "Directory traversal should be prevented");
+        assert!(result.is_ok(), "Command failed: {:?}", result.err());
+
+        // Only the directory is checked, and only against a root
+        assert!(!is_path_traversal_attempt(&test_dir, None));
+        assert!(!is_path_traversal_attempt(&test_dir, Some(&temp_path)));
+        assert!(!is_path_traversal_attempt(&test_dir.join(".."), Some(&temp_path)));
+        assert!(is_path_traversal_attempt(&temp_path.join(".."), Some(&temp_path)));
+        assert!(is_path_traversal_attempt(&temp_path.join("../.."), Some(&test_dir)));
+        assert!(is_path_traversal_attempt(&temp_path, Some(&test_dir)));
+        // Unresolvable paths are rejected
+        assert!(is_path_traversal_attempt(&test_dir.join("missing"), Some(&temp_path)));
+
+        drop(temp_dir);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_with_git_revision_range() -> std::io::Result<()> {
+        let (temp_dir, temp_path) = get_safe_temp_dir()?;
+        let git = "git -c user.name=test -c user.email=test@example.com";
+
+        let mut command = create_test_command(&format!(
+            "git init -q && {git} commit -q --allow-empty -m one && \
+             {git} commit -q --allow-empty -m two && git log --oneline HEAD~1..HEAD"
+        ));
+        command.directory = temp_path.canonicalize()?.to_string_lossy().to_string();
+
+        setup_test_env();
+        let result = execute_command(&command);
+        cleanup_test_env();
+
+        assert!(result.is_ok(), "Command failed: {:?}", result.err());
         drop(temp_dir);
         Ok(())
     }
@@ -258,6 +416,9 @@ mod tests {
             Parameter {
                 name: "p".to_string(),
                 description: Some("Test parameter".to_string()),
+                pattern: None,
+                default_value: None,
+                secret: false,
             },
         ];
         
@@ -286,6 +447,9 @@ mod tests {
             Parameter {
                 name: "p".to_string(),
                 description: Some("Test parameter".to_string()),
+                pattern: None,
+                default_value: None,
+                secret: false,
             },
         ];
         
@@ -308,4 +472,67 @@ mod tests {
         drop(temp_dir);
         Ok(())
     }
+
+    #[test]
+    fn test_resolve_directory_tilde() {
+        let home = dirs::home_dir().expect("home directory");
+        assert_eq!(resolve_directory("~"), home);
+        assert_eq!(resolve_directory("~/project"), home.join("project"));
+
+        // Only a leading tilde is expanded
+        assert_eq!(resolve_directory("/tmp/~"), PathBuf::from("/tmp/~"));
+    }
+
+    #[test]
+    fn test_resolve_directory_env_vars() {
+        let home = env::var("HOME").expect("HOME is set");
+        assert_eq!(resolve_directory("$HOME/sub"), PathBuf::from(&home).join("sub"));
+        assert_eq!(resolve_directory("${HOME}/sub"), PathBuf::from(&home).join("sub"));
+
+        // Unset variables are left as written
+        env::remove_var("COMMAND_VAULT_UNSET_DIR_VAR");
+        assert_eq!(
+            resolve_directory("/data/$COMMAND_VAULT_UNSET_DIR_VAR/x"),
+            PathBuf::from("/data/$COMMAND_VAULT_UNSET_DIR_VAR/x")
+        );
+        assert_eq!(resolve_directory("/data/${oops"), PathBuf::from("/data/${oops"));
+        assert_eq!(resolve_directory("/plain/path"), PathBuf::from("/plain/path"));
+    }
+
+    #[test]
+    fn test_find_shell() {
+        assert_eq!(find_shell("/bin/sh"), Some(PathBuf::from("/bin/sh")));
+        assert!(find_shell("sh").is_some_and(|path| path.ends_with("sh")));
+        assert_eq!(find_shell("no-such-shell-here"), None);
+        assert_eq!(find_shell("/no/such/shell"), None);
+    }
+
+    #[test]
+    fn test_command_with_shell() -> std::io::Result<()> {
+        // Needs a shell other than the /bin/sh the tests run with
+        let Some(bash) = find_shell("bash") else {
+            return Ok(());
+        };
+        let (temp_dir, temp_path) = get_safe_temp_dir()?;
+
+        // `$0` is the shell running the command
+        let mut command = create_test_command("echo $0 > shell.txt");
+        command.directory = temp_path.to_string_lossy().to_string();
+        command.shell = Some("bash".to_string());
+
+        setup_test_env();
+        let result = execute_command(&command);
+        assert!(result.is_ok(), "Command failed: {:?}", result.err());
+        assert_eq!(fs::read_to_string(temp_path.join("shell.txt"))?.trim(), bash.to_string_lossy());
+
+        // A shell that doesn't exist falls back to $SHELL
+        command.shell = Some("no-such-shell-here".to_string());
+        let result = execute_command(&command);
+        cleanup_test_env();
+        assert!(result.is_ok(), "Command failed: {:?}", result.err());
+        assert_eq!(fs::read_to_string(temp_path.join("shell.txt"))?.trim(), "/bin/sh");
+
+        drop(temp_dir);
+        Ok(())
+    }
 }
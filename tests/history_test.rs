@@ -0,0 +1,36 @@
+use command_vault::utils::history::{is_trivial, parse_history, select_commands};
+
+#[test]
+fn test_parse_bash_history() {
+    let contents = "git status\n#1700000000\ncargo build --release\n\nls\n";
+    assert_eq!(parse_history(contents), vec!["git status", "cargo build --release", "ls"]);
+}
+
+#[test]
+fn test_parse_zsh_history() {
+    let contents = ": 1700000000:0;git status\n\
+                    : 1700000005:12;for f in *.log; do\\\n  gzip $f\\\ndone\n\
+                    : 1700000010:0;echo a;b\n";
+    assert_eq!(
+        parse_history(contents),
+        vec!["git status", "for f in *.log; do\n  gzip $f\ndone", "echo a;b"]
+    );
+}
+
+#[test]
+fn test_select_commands() {
+    let history: Vec<String> = ["git pull", "ls", "cargo test", "cd ..", "git pull", "make"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    // Duplicates keep their latest position, trivial commands are dropped
+    assert_eq!(select_commands(history.clone(), None, false), vec!["cargo test", "git pull", "make"]);
+    // The limit keeps the most recent ones
+    assert_eq!(select_commands(history.clone(), Some(2), false), vec!["git pull", "make"]);
+    assert_eq!(select_commands(history, None, true), vec!["ls", "cargo test", "cd ..", "git pull", "make"]);
+
+    assert!(is_trivial("ls -la"));
+    assert!(is_trivial("cd ~/src"));
+    assert!(!is_trivial("lsof -i :8080"));
+}
@@ -0,0 +1,29 @@
+use command_vault::utils::limits::{check_command_length_with, truncate_output, DEFAULT_MAX_COMMAND_LENGTH, MAX_OUTPUT_LENGTH};
+
+#[test]
+fn test_command_length_limit() {
+    let limit = Some(10);
+    assert!(check_command_length_with("ls -la", limit).is_ok());
+    assert!(check_command_length_with("0123456789", limit).is_ok());
+
+    let err = check_command_length_with("echo too long", limit).unwrap_err();
+    assert!(err.to_string().contains("maximum is 10 bytes"));
+}
+
+#[test]
+fn test_command_length_limit_disabled() {
+    let long = "x".repeat(DEFAULT_MAX_COMMAND_LENGTH * 2);
+    assert!(check_command_length_with(&long, None).is_ok());
+}
+
+#[test]
+fn test_truncate_output() {
+    assert_eq!(truncate_output("hello\n"), "hello\n");
+
+    let long = "x".repeat(MAX_OUTPUT_LENGTH + 10);
+    assert_eq!(truncate_output(&long).len(), MAX_OUTPUT_LENGTH);
+
+    // Never cuts a character in half
+    let multibyte = format!("{}é", "x".repeat(MAX_OUTPUT_LENGTH - 1));
+    assert_eq!(truncate_output(&multibyte).len(), MAX_OUTPUT_LENGTH - 1);
+}
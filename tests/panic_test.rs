@@ -0,0 +1,35 @@
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use command_vault::ui::panic::PanicHookGuard;
+
+#[test]
+fn test_panic_hook_guard() {
+    // Stands in for the default hook, so the test output stays quiet
+    let reported = Arc::new(AtomicUsize::new(0));
+    let previous_reported = Arc::clone(&reported);
+    panic::set_hook(Box::new(move |_| {
+        previous_reported.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let cleaned_up = Arc::new(AtomicUsize::new(0));
+    {
+        let counter = Arc::clone(&cleaned_up);
+        let _guard = PanicHookGuard::with_cleanup(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert!(panic::catch_unwind(|| panic!("TUI crashed")).is_err());
+
+        // Cleanup ran, then the previous hook still reported the panic
+        assert_eq!(cleaned_up.load(Ordering::SeqCst), 1);
+        assert_eq!(reported.load(Ordering::SeqCst), 1);
+    }
+
+    // Once the guard is gone, only the previous hook runs
+    assert!(panic::catch_unwind(|| panic!("after the TUI")).is_err());
+    assert_eq!(cleaned_up.load(Ordering::SeqCst), 1);
+    assert_eq!(reported.load(Ordering::SeqCst), 2);
+
+    drop(panic::take_hook());
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use command_vault::{
     db::models::Parameter,
-    utils::params::{parse_parameters, substitute_parameters},
+    utils::params::{parse_parameters, prompt_parameters_with_defaults, substitute_parameters, ValueInput},
 };
 
 #[test]
@@ -75,6 +77,9 @@ fn test_substitute_parameters_with_special_chars() -> Result<(), Box<dyn std::er
     let parameters = vec![Parameter {
         name: "pattern".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
     
     let result = substitute_parameters(command, &parameters, Some("test-pattern"))?;
@@ -92,6 +97,9 @@ fn test_substitute_parameters_empty_value() -> Result<(), Box<dyn std::error::Er
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some(""))?;
@@ -156,6 +164,9 @@ fn test_substitute_parameters_with_defaults() -> Result<(), Box<dyn std::error::
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: Some("default value".to_string()),
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some(""))?;
@@ -174,10 +185,16 @@ fn test_substitute_parameters_multiple() -> Result<(), Box<dyn std::error::Error
         Parameter {
             name: "message".to_string(),
             description: None,
+            pattern: None,
+            default_value: None,
+            secret: false,
         },
         Parameter {
             name: "author".to_string(),
             description: None,
+            pattern: None,
+            default_value: None,
+            secret: false,
         },
     ];
     
@@ -196,6 +213,9 @@ fn test_substitute_parameters_with_quotes() -> Result<(), Box<dyn std::error::Er
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("hello * world"))?;
@@ -233,6 +253,55 @@ fn test_substitute_parameters_no_parameters() -> Result<(), Box<dyn std::error::
     Ok(())
 }
 
+#[test]
+fn test_escaped_at_is_literal() -> Result<(), Box<dyn std::error::Error>> {
+    let command = "echo @@user";
+    let parameters = parse_parameters(command);
+    assert!(parameters.is_empty());
+
+    let result = substitute_parameters(command, &parameters, Some(""))?;
+    assert_eq!(result, "echo @user");
+
+    // Escapes and real parameters can be mixed, and `@@@name` is an `@` followed by a parameter
+    let command = r#"git commit -m "@@channel please review" && echo @@@name"#;
+    let parameters = parse_parameters(command);
+    assert_eq!(parameters.len(), 1);
+    assert_eq!(parameters[0].name, "name");
+
+    let result = substitute_parameters(command, &parameters, Some("bob"))?;
+    assert_eq!(result, r#"git commit -m "@channel please review" && echo @bob"#);
+    Ok(())
+}
+
+#[test]
+fn test_secret_parameters() -> Result<(), Box<dyn std::error::Error>> {
+    let command = "curl -H 'Authorization: @token!' @url @key:API_key!secret @@not! @name:hi!";
+    let parameters = parse_parameters(command);
+    assert_eq!(parameters.len(), 4);
+    assert!(parameters[0].secret);
+    assert_eq!(parameters[0].description, None);
+    assert!(!parameters[1].secret);
+    assert!(parameters[2].secret);
+    assert_eq!(parameters[2].description, Some("API_key".to_string()));
+    // Only `!secret` marks a description; a plain `!` is part of it
+    assert!(!parameters[3].secret);
+    assert_eq!(parameters[3].description, Some("hi!".to_string()));
+
+    let command = "login @password:password!secret @port:/^[0-9]+$/!secret";
+    let parameters = parse_parameters(command);
+    assert_eq!(parameters[0].description, Some("password".to_string()));
+    assert!(parameters[0].secret);
+    assert_eq!(parameters[1].pattern, Some("^[0-9]+$".to_string()));
+    assert!(parameters[1].secret);
+
+    // The real values go into the command, without the secret markers
+    let command = "curl -H @token! @url:Endpoint @key:Key!secret";
+    let parameters = parse_parameters(command);
+    let result = substitute_parameters(command, &parameters, Some("s3cret\nexample.com\nabc"))?;
+    assert_eq!(result, "curl -H s3cret example.com abc");
+    Ok(())
+}
+
 #[test]
 fn test_substitute_parameters_with_git_commands() -> Result<(), Box<dyn std::error::Error>> {
     std::env::set_var("COMMAND_VAULT_TEST", "1");
@@ -241,6 +310,9 @@ fn test_substitute_parameters_with_git_commands() -> Result<(), Box<dyn std::err
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("test commit"))?;
@@ -258,6 +330,9 @@ fn test_substitute_parameters_with_grep() -> Result<(), Box<dyn std::error::Erro
     let parameters = vec![Parameter {
         name: "pattern".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("hello * world"))?;
@@ -274,6 +349,9 @@ fn test_substitute_parameters_with_multiple_occurrences() -> Result<(), Box<dyn
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("test")).unwrap();
@@ -289,6 +367,9 @@ fn test_substitute_parameters_with_descriptions() -> Result<(), Box<dyn std::err
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: Some("A test message".to_string()),
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("test")).unwrap();
@@ -305,6 +386,9 @@ fn test_substitute_parameters_empty_value_removed_duplicate() -> Result<(), Box<
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some(""))?;
@@ -340,6 +424,9 @@ fn test_substitute_parameters_with_semicolon() -> Result<(), Box<dyn std::error:
     let parameters = vec![Parameter {
         name: "cmd".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("echo hello; ls"))?;
@@ -357,6 +444,9 @@ fn test_substitute_parameters_with_pipe() -> Result<(), Box<dyn std::error::Erro
     let parameters = vec![Parameter {
         name: "cmd".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("ls | grep test"))?;
@@ -374,6 +464,9 @@ fn test_substitute_parameters_with_redirection() -> Result<(), Box<dyn std::erro
     let parameters = vec![Parameter {
         name: "cmd".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("echo test > file.txt"))?;
@@ -391,6 +484,9 @@ fn test_substitute_parameters_with_existing_quotes() -> Result<(), Box<dyn std::
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("'already quoted'"))?;
@@ -408,6 +504,9 @@ fn test_substitute_parameters_with_escaped_quotes() -> Result<(), Box<dyn std::e
     let parameters = vec![Parameter {
         name: "message".to_string(),
         description: None,
+        pattern: None,
+        default_value: None,
+        secret: false,
     }];
 
     let result = substitute_parameters(command, &parameters, Some("It's a test"))?;
@@ -467,4 +566,89 @@ fn test_parse_parameters_with_dash_in_description() {
     assert_eq!(params.len(), 1);
     assert_eq!(params[0].name, "branch");
     assert_eq!(params[0].description, Some("feature-123".to_string()));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_prompt_parameters_with_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("COMMAND_VAULT_TEST", "1");
+    let command = "git checkout @branch:Branch";
+    let params = parse_parameters(command);
+
+    // A remembered value takes precedence over the description
+    let mut defaults = HashMap::new();
+    defaults.insert("branch".to_string(), "develop".to_string());
+    let (result, values) = prompt_parameters_with_defaults(command, &params, None, &defaults)?;
+    assert_eq!(result, "git checkout develop");
+    assert_eq!(values.get("branch"), Some(&"develop".to_string()));
+
+    // Explicit input still wins
+    let (result, _) = prompt_parameters_with_defaults(command, &params, Some("main"), &defaults)?;
+    assert_eq!(result, "git checkout main");
+
+    // Without a default the description is used, as before
+    let (result, _) = prompt_parameters_with_defaults(command, &params, None, &HashMap::new())?;
+    assert_eq!(result, "git checkout Branch");
+    Ok(())
+}
+
+#[test]
+fn test_parse_parameters_with_pattern() {
+    let params = parse_parameters("nc -l @port:/^[0-9]+$/ @host:localhost");
+
+    assert_eq!(params.len(), 2);
+    assert_eq!(params[0].name, "port");
+    assert_eq!(params[0].pattern, Some("^[0-9]+$".to_string()));
+    assert_eq!(params[0].description, None);
+    assert_eq!(params[1].pattern, None);
+
+    // A lone slash is an ordinary description
+    assert_eq!(parse_parameters("ls @dir:/")[0].description, Some("/".to_string()));
+}
+
+#[test]
+fn test_substitute_parameters_checks_pattern() -> Result<(), Box<dyn std::error::Error>> {
+    let command = "nc -l @port:/^[0-9]+$/";
+    let params = parse_parameters(command);
+
+    // Matching values are substituted, without the pattern
+    assert_eq!(substitute_parameters(command, &params, Some("8080"))?, "nc -l 8080");
+
+    let err = substitute_parameters(command, &params, Some("http")).unwrap_err();
+    assert_eq!(err.to_string(), "Value 'http' for parameter 'port' doesn't match /^[0-9]+$/");
+
+    let err = prompt_parameters_with_defaults(command, &params, Some("http"), &HashMap::new()).unwrap_err();
+    assert!(err.to_string().contains("parameter 'port'"));
+
+    // A broken pattern names the parameter too
+    let params = parse_parameters("nc -l @port:/[0-9/");
+    let err = substitute_parameters("nc -l @port:/[0-9/", &params, Some("1")).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid pattern for parameter 'port'"));
+    Ok(())
+}
+
+#[test]
+fn test_value_input_multibyte() {
+    use crossterm::event::KeyCode;
+
+    // A remembered value with multibyte characters, cursor at its end
+    let mut input = ValueInput::new("café".to_string());
+    assert_eq!(input.cursor, 4);
+
+    input.handle_key(KeyCode::Backspace);
+    assert_eq!(input.value, "caf");
+
+    input.handle_key(KeyCode::Char('é'));
+    input.handle_key(KeyCode::Left);
+    input.handle_key(KeyCode::Left);
+    input.handle_key(KeyCode::Char('日'));
+    assert_eq!(input.value, "ca日fé");
+    assert_eq!(input.cursor, 3);
+
+    // Right stops at the end of the value, counted in characters
+    for _ in 0..5 {
+        input.handle_key(KeyCode::Right);
+    }
+    assert_eq!(input.cursor, 5);
+    input.handle_key(KeyCode::Char('!'));
+    assert_eq!(input.value, "ca日fé!");
+}
@@ -0,0 +1,50 @@
+use command_vault::db::path::{default_db_path, resolve_data_dir, resolve_data_dir_from, resolve_db_path, DATA_DIR_ENV_VAR, DB_ENV_VAR};
+use std::path::PathBuf;
+
+#[test]
+fn test_db_path_env_override() {
+    let path = resolve_db_path(
+        Some("/custom/vault.db".to_string()),
+        resolve_data_dir_from(Some("/vault".to_string()), Some("/xdg".to_string()), Some(PathBuf::from("/data"))),
+    );
+    assert_eq!(path, PathBuf::from("/custom/vault.db"));
+}
+
+#[test]
+fn test_data_dir_env_override() {
+    let dir = resolve_data_dir_from(Some("/vault".to_string()), Some("/xdg".to_string()), Some(PathBuf::from("/data")));
+    assert_eq!(dir, Some(PathBuf::from("/vault")));
+    assert_eq!(resolve_db_path(None, dir), PathBuf::from("/vault/commands.db"));
+}
+
+#[test]
+fn test_db_path_xdg_data_home() {
+    let dir = resolve_data_dir_from(None, Some("/xdg".to_string()), Some(PathBuf::from("/data")));
+    assert_eq!(resolve_db_path(None, dir), PathBuf::from("/xdg/command-vault/commands.db"));
+}
+
+#[test]
+fn test_db_path_platform_data_dir() {
+    // Empty variables are ignored
+    let dir = resolve_data_dir_from(Some(String::new()), Some(String::new()), Some(PathBuf::from("/data")));
+    let path = resolve_db_path(Some(String::new()), dir);
+    assert_eq!(path, PathBuf::from("/data/command-vault/commands.db"));
+}
+
+#[test]
+fn test_db_path_falls_back_to_current_dir() {
+    assert_eq!(resolve_data_dir_from(None, None, None), None);
+    let path = resolve_db_path(None, None);
+    assert_eq!(path, PathBuf::from("command-vault.db"));
+}
+
+#[test]
+fn test_data_dir_from_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    std::env::remove_var(DB_ENV_VAR);
+    std::env::set_var(DATA_DIR_ENV_VAR, dir.path());
+
+    assert_eq!(resolve_data_dir().unwrap(), dir.path());
+    assert_eq!(default_db_path(), dir.path().join("commands.db"));
+    std::env::remove_var(DATA_DIR_ENV_VAR);
+}
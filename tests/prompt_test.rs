@@ -0,0 +1,30 @@
+use command_vault::utils::prompt::{split_prompt_marker, strip_prompt_marker};
+
+#[test]
+fn test_strip_prompt_marker() {
+    assert_eq!(strip_prompt_marker("$ ls -la"), Some("ls -la"));
+    assert_eq!(strip_prompt_marker("# apt update"), Some("apt update"));
+    // Only a single marker is removed
+    assert_eq!(strip_prompt_marker("$ $ ls"), Some("$ ls"));
+}
+
+#[test]
+fn test_strip_prompt_marker_no_marker() {
+    assert_eq!(strip_prompt_marker("ls -la"), None);
+    // Variables and mid-command markers are left alone
+    assert_eq!(strip_prompt_marker("$HOME/bin/run"), None);
+    assert_eq!(strip_prompt_marker("echo $ hi"), None);
+}
+
+#[test]
+fn test_split_prompt_marker() {
+    let args: Vec<String> = ["$", "ls", "-la"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(split_prompt_marker(&args), (Some("$"), &args[1..]));
+
+    // A marker on its own is the whole command, not a marker
+    let args = vec!["#".to_string()];
+    assert_eq!(split_prompt_marker(&args), (None, &args[..]));
+
+    let args: Vec<String> = ["$HOME/bin/run", "now"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(split_prompt_marker(&args), (None, &args[..]));
+}
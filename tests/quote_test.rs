@@ -0,0 +1,41 @@
+use command_vault::utils::quote::join_args;
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_join_args_minimal_quoting() -> anyhow::Result<()> {
+    // Nothing to quote
+    assert_eq!(join_args(&args(&["ssh", "host:22", "@target"]))?, "ssh host:22 @target");
+
+    // Spaces, `$` and embedded quotes
+    assert_eq!(join_args(&args(&["echo", "Hello, World!"]))?, "echo 'Hello, World!'");
+    assert_eq!(join_args(&args(&["echo", "$HOME"]))?, "echo '$HOME'");
+    assert_eq!(join_args(&args(&["echo", "it's"]))?, "echo \"it's\"");
+    assert_eq!(join_args(&args(&["grep", "say \"hi\" for $5"]))?, "grep 'say \"hi\" for $5'");
+    assert_eq!(join_args(&args(&["touch", ""]))?, "touch ''");
+    Ok(())
+}
+
+#[test]
+fn test_join_args_round_trips() -> anyhow::Result<()> {
+    let cases = [
+        args(&["git", "log", "--pretty=format:%Cred%h%Creset %s (%cr)"]),
+        args(&["printf", "%s\\n", "it's \"quoted\"", "$PATH", "a b  c"]),
+        args(&["echo", "`date`", "*.rs", "x;y", "a|b"]),
+    ];
+    for case in cases {
+        let joined = join_args(&case)?;
+        assert_eq!(shlex::split(&joined), Some(case), "{} did not round-trip", joined);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_join_args_single_argument_is_kept() -> anyhow::Result<()> {
+    // A single argument is already a complete command
+    let command = "git commit -m \"@message:Commit message\"";
+    assert_eq!(join_args(&args(&[command]))?, command);
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+use chrono::Utc;
+use command_vault::db::models::{Command, Parameter};
+use command_vault::server::{route, Server};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use tiny_http::Method;
+
+mod test_utils;
+use test_utils::create_test_db;
+
+fn create_test_command(command: &str) -> Command {
+    Command {
+        id: None,
+        command: command.to_string(),
+        timestamp: Utc::now(),
+        directory: "/test".to_string(),
+        tags: vec!["test".to_string()],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    }
+}
+
+fn http_get(addr: &std::net::SocketAddr, path: &str) -> Result<(String, Value)> {
+    http_request(addr, "GET", path, &[("Host", &addr.to_string())])
+}
+
+fn http_request(addr: &std::net::SocketAddr, method: &str, path: &str, headers: &[(&str, &str)]) -> Result<(String, Value)> {
+    let mut stream = TcpStream::connect(addr)?;
+    write!(stream, "{} {} HTTP/1.1\r\nConnection: close\r\nContent-Length: 0\r\n", method, path)?;
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(stream, "\r\n")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response.split_once("\r\n\r\n").expect("Malformed HTTP response");
+    let status_line = head.lines().next().unwrap_or_default().to_string();
+    Ok((status_line, serde_json::from_str(body)?))
+}
+
+#[test]
+fn test_server_list_commands() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    db.add_command(&create_test_command("git status"))?;
+    db.add_command(&create_test_command("docker ps"))?;
+
+    let server = Server::bind("127.0.0.1:0", false)?;
+    let addr = server.local_addr().expect("Server should listen on an IP address");
+    let handle = thread::spawn(move || server.handle_next(&db));
+
+    let (status, body) = http_get(&addr, "/commands")?;
+    handle.join().unwrap()?;
+
+    assert!(status.contains("200"), "Unexpected status: {}", status);
+    let commands = body.as_array().expect("Expected a JSON array");
+    assert_eq!(commands.len(), 2);
+    let texts: Vec<&str> = commands.iter().map(|c| c["command"].as_str().unwrap()).collect();
+    assert!(texts.contains(&"git status"));
+    assert!(texts.contains(&"docker ps"));
+    assert_eq!(commands[0]["tags"][0], "test");
+    Ok(())
+}
+
+#[test]
+fn test_server_routes() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let id = db.add_command(&create_test_command("git push origin main"))?;
+    db.add_command(&create_test_command("docker ps"))?;
+
+    // Single command
+    let (status, body) = route(&db, &Method::Get, &format!("/commands/{}", id), false);
+    assert_eq!(status, 200);
+    assert_eq!(body["command"], "git push origin main");
+
+    // Missing and invalid ids
+    let (status, _) = route(&db, &Method::Get, "/commands/999", false);
+    assert_eq!(status, 404);
+    let (status, _) = route(&db, &Method::Get, "/commands/abc", false);
+    assert_eq!(status, 400);
+
+    // Search with an encoded query
+    let (status, body) = route(&db, &Method::Get, "/search?q=push%20origin", false);
+    assert_eq!(status, 200);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    let (status, _) = route(&db, &Method::Get, "/search", false);
+    assert_eq!(status, 400);
+
+    // Limit
+    let (status, body) = route(&db, &Method::Get, "/commands?limit=1", false);
+    assert_eq!(status, 200);
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    // Execution is disabled unless requested
+    let (status, body) = route(&db, &Method::Post, &format!("/commands/{}/exec", id), false);
+    assert_eq!(status, 403);
+    assert!(body["error"].as_str().unwrap().contains("--allow-exec"));
+
+    // Unknown routes
+    let (status, _) = route(&db, &Method::Get, "/unknown", false);
+    assert_eq!(status, 404);
+    Ok(())
+}
+
+#[test]
+fn test_server_rejects_other_hosts_and_origins() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let dir = tempfile::tempdir()?;
+    let mut command = create_test_command("true");
+    command.directory = dir.path().to_string_lossy().to_string();
+    let id = db.add_command(&command)?;
+
+    let server = Server::bind("127.0.0.1:0", true)?;
+    let addr = server.local_addr().expect("Server should listen on an IP address");
+    let token = server.token().to_string();
+    let handle = thread::spawn(move || -> Result<()> {
+        for _ in 0..8 {
+            server.handle_next(&db)?;
+        }
+        Ok(())
+    });
+    let host = addr.to_string();
+    let localhost = format!("localhost:{}", addr.port());
+    let exec = format!("/commands/{}/exec", id);
+
+    // A domain name could point anywhere (DNS rebinding)
+    let (status, _) = http_request(&addr, "GET", "/commands", &[("Host", &format!("evil.example:{}", addr.port()))])?;
+    assert!(status.contains("403"), "Unexpected status: {}", status);
+    let (status, _) = http_request(&addr, "GET", "/commands", &[("Host", &localhost)])?;
+    assert!(status.contains("200"), "Unexpected status: {}", status);
+
+    // Browsers on other sites send their own origin
+    let (status, _) = http_request(&addr, "GET", "/commands", &[("Host", &host), ("Origin", "https://evil.example")])?;
+    assert!(status.contains("403"), "Unexpected status: {}", status);
+    let (status, _) = http_request(&addr, "GET", "/commands", &[("Host", &host), ("Origin", &format!("http://{}", host))])?;
+    assert!(status.contains("200"), "Unexpected status: {}", status);
+
+    // Exec needs the session token
+    let (status, body) = http_request(&addr, "POST", &exec, &[("Host", &host)])?;
+    assert!(status.contains("403"), "Unexpected status: {}", status);
+    assert!(body["error"].as_str().unwrap().contains("X-Command-Vault-Token"));
+    let (status, _) = http_request(&addr, "POST", &exec, &[("Host", &host), ("X-Command-Vault-Token", "guess")])?;
+    assert!(status.contains("403"), "Unexpected status: {}", status);
+    let (status, _) = http_request(&addr, "POST", &exec, &[("Host", &host), ("X-Command-Vault-Token", &token), ("Origin", "https://evil.example")])?;
+    assert!(status.contains("403"), "Unexpected status: {}", status);
+    let (status, body) = http_request(&addr, "POST", &exec, &[("Host", &host), ("X-Command-Vault-Token", &token)])?;
+    assert!(status.contains("200"), "Unexpected status: {} {}", status, body);
+
+    handle.join().unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn test_server_exec_substitutes_and_confirms() -> Result<()> {
+    let (mut db, _db_dir) = create_test_db()?;
+    let dir = tempfile::tempdir()?;
+    let directory = dir.path().to_string_lossy().to_string();
+
+    // Parameters take their defaults, in the same pass that turns `@@` into `@`
+    let mut name = Parameter::new("name".to_string());
+    name.default_value = Some("ops".to_string());
+    let mut command = create_test_command("echo @@team @name > out.txt");
+    command.directory = directory.clone();
+    command.parameters = vec![name];
+    let id = db.add_command(&command)?;
+    let (status, body) = route(&db, &Method::Post, &format!("/commands/{}/exec", id), true);
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(std::fs::read_to_string(dir.path().join("out.txt"))?, "@team ops\n");
+
+    // A parameter without a default can't be asked for
+    let mut command = create_test_command("echo @message");
+    command.directory = directory.clone();
+    let id = db.add_command(&command)?;
+    let (status, body) = route(&db, &Method::Post, &format!("/commands/{}/exec", id), true);
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("message"));
+
+    // Commands saved with --confirm only run when the request confirms
+    let mut command = create_test_command("true");
+    command.directory = directory;
+    command.confirm_before_run = true;
+    let id = db.add_command(&command)?;
+    let (status, body) = route(&db, &Method::Post, &format!("/commands/{}/exec", id), true);
+    assert_eq!(status, 403);
+    assert!(body["error"].as_str().unwrap().contains("confirm=true"));
+    let (status, _) = route(&db, &Method::Post, &format!("/commands/{}/exec?confirm=true", id), true);
+    assert_eq!(status, 200);
+    Ok(())
+}
@@ -1,10 +1,10 @@
 use std::env;
-use std::path::PathBuf;
 use anyhow::Result;
 use serial_test::serial;
 use command_vault::shell::hooks::{
     detect_current_shell, get_shell_integration_dir, get_shell_integration_script,
-    get_zsh_integration_path, get_bash_integration_path, get_fish_integration_path, init_shell
+    get_zsh_integration_path, get_bash_integration_path, get_fish_integration_path,
+    get_xonsh_integration_path, get_elvish_integration_path, init_shell
 };
 
 #[test]
@@ -284,3 +284,50 @@ fn test_init_shell_invalid_shell() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[serial]
+fn test_detect_current_shell_xonsh_and_elvish() {
+    // Save original environment
+    let original_shell = env::var("SHELL").ok();
+    let original_fish_version = env::var("FISH_VERSION").ok();
+    
+    env::remove_var("FISH_VERSION");
+    
+    env::set_var("SHELL", "/usr/local/bin/xonsh");
+    assert_eq!(detect_current_shell(), "xonsh", "Should detect Xonsh via SHELL");
+    
+    env::set_var("SHELL", "/opt/homebrew/bin/elvish");
+    assert_eq!(detect_current_shell(), "elvish", "Should detect Elvish via SHELL");
+    
+    // FISH_VERSION still takes precedence over SHELL
+    env::set_var("FISH_VERSION", "3.1.2");
+    assert_eq!(detect_current_shell(), "fish", "FISH_VERSION should take precedence over SHELL");
+    
+    // Restore original environment
+    if let Some(shell) = original_shell {
+        env::set_var("SHELL", shell);
+    } else {
+        env::remove_var("SHELL");
+    }
+    if let Some(version) = original_fish_version {
+        env::set_var("FISH_VERSION", version);
+    } else {
+        env::remove_var("FISH_VERSION");
+    }
+}
+
+#[test]
+fn test_xonsh_and_elvish_integration_scripts() -> Result<()> {
+    let xonsh_path = get_xonsh_integration_path();
+    let elvish_path = get_elvish_integration_path();
+    
+    assert!(xonsh_path.ends_with("xonsh-integration.xsh"));
+    assert!(elvish_path.ends_with("elvish-integration.elv"));
+    assert!(xonsh_path.exists(), "Xonsh integration script should exist");
+    assert!(elvish_path.exists(), "Elvish integration script should exist");
+    
+    assert_eq!(get_shell_integration_script("xonsh")?, xonsh_path);
+    assert_eq!(get_shell_integration_script("Elvish")?, elvish_path);
+    Ok(())
+}
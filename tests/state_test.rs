@@ -0,0 +1,78 @@
+use std::env;
+use anyhow::Result;
+use command_vault::db::Database;
+use command_vault::ui::state::{state_path, ColumnSet, UiState, REMEMBER_FILTER_ENV_VAR};
+use command_vault::ui::App;
+use serial_test::serial;
+use tempfile::tempdir;
+
+#[test]
+fn test_ui_state_round_trip() -> Result<()> {
+    let dir = tempdir()?;
+    let path = dir.path().join("nested").join("state.json");
+
+    // Missing or broken files fall back to an empty filter
+    assert_eq!(UiState::load(&path), UiState::default());
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(&path, "not json")?;
+    assert_eq!(UiState::load(&path), UiState::default());
+
+    let state = UiState { filter_text: "git".to_string(), columns: ColumnSet { id: false, ..ColumnSet::default() } };
+    state.save(&path)?;
+    assert_eq!(UiState::load(&path), state);
+
+    // State saved before columns were remembered keeps the default columns
+    std::fs::write(&path, r#"{"filter_text": "git"}"#)?;
+    assert_eq!(UiState::load(&path).columns, ColumnSet::default());
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_state_path_is_opt_in() {
+    let original = env::var("XDG_DATA_HOME").ok();
+    env::set_var("XDG_DATA_HOME", "/tmp/xdg");
+
+    env::remove_var(REMEMBER_FILTER_ENV_VAR);
+    assert_eq!(state_path(), None);
+    env::set_var(REMEMBER_FILTER_ENV_VAR, "0");
+    assert_eq!(state_path(), None);
+
+    env::set_var(REMEMBER_FILTER_ENV_VAR, "1");
+    assert_eq!(
+        state_path(),
+        Some(std::path::PathBuf::from("/tmp/xdg/command-vault/state.json"))
+    );
+
+    env::remove_var(REMEMBER_FILTER_ENV_VAR);
+    match original {
+        Some(value) => env::set_var("XDG_DATA_HOME", value),
+        None => env::remove_var("XDG_DATA_HOME"),
+    }
+}
+
+#[test]
+#[serial]
+fn test_app_restores_filter() -> Result<()> {
+    let dir = tempdir()?;
+    let original = env::var("XDG_DATA_HOME").ok();
+    env::set_var("XDG_DATA_HOME", dir.path());
+    env::set_var(REMEMBER_FILTER_ENV_VAR, "1");
+
+    let path = state_path().unwrap();
+    let columns = ColumnSet { directory: true, time: false, ..ColumnSet::default() };
+    UiState { filter_text: "cargo".to_string(), columns }.save(&path)?;
+
+    let mut db = Database::new(":memory:")?;
+    let app = App::new(Vec::new(), &mut db, false);
+    assert_eq!(app.state_path, Some(path));
+    assert_eq!(app.filter_text, "cargo");
+    assert_eq!(app.columns, columns);
+
+    env::remove_var(REMEMBER_FILTER_ENV_VAR);
+    match original {
+        Some(value) => env::set_var("XDG_DATA_HOME", value),
+        None => env::remove_var("XDG_DATA_HOME"),
+    }
+    Ok(())
+}
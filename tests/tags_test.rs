@@ -0,0 +1,64 @@
+use command_vault::utils::tags::{extract_inline_tags, merge_tags, normalize_tag, normalize_tags, split_inline_tag_args};
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_extract_inline_tags() {
+    assert_eq!(
+        extract_inline_tags("git push #git #deploy"),
+        ("git push".to_string(), vec!["git".to_string(), "deploy".to_string()])
+    );
+    assert_eq!(
+        extract_inline_tags("kubectl apply -f k8s/ #k8s-prod  "),
+        ("kubectl apply -f k8s/".to_string(), vec!["k8s-prod".to_string()])
+    );
+}
+
+#[test]
+fn test_extract_inline_tags_leaves_other_hashes() {
+    let untouched = [
+        "curl https://example.com/page#section",
+        "echo \"release #notes\"",
+        "echo 'a #b'",
+        "git push #git origin main",
+        "gh issue view #123",
+        "ls # list files",
+        "#deploy",
+    ];
+    for command in untouched {
+        assert_eq!(extract_inline_tags(command), (command.to_string(), vec![]), "{}", command);
+    }
+}
+
+#[test]
+fn test_split_inline_tag_args() {
+    let parts = args(&["git", "push", "#git", "#deploy"]);
+    let (command, tags) = split_inline_tag_args(&parts);
+    assert_eq!(command, &parts[..2]);
+    assert_eq!(tags, vec!["git", "deploy"]);
+
+    // The command itself is never consumed
+    let parts = args(&["#only"]);
+    let (command, tags) = split_inline_tag_args(&parts);
+    assert_eq!(command, &parts[..]);
+    assert!(tags.is_empty());
+}
+
+#[test]
+fn test_merge_tags() {
+    let mut tags = vec!["git".to_string()];
+    merge_tags(&mut tags, vec!["deploy".to_string(), "git".to_string()]);
+    assert_eq!(tags, vec!["git", "deploy"]);
+}
+
+#[test]
+fn test_normalize_tag() {
+    assert_eq!(normalize_tag(" Git ", true).as_deref(), Some("git"));
+    assert_eq!(normalize_tag(" Git ", false).as_deref(), Some("Git"));
+    assert_eq!(normalize_tag("   ", true), None);
+
+    let tags = vec![" Git ".to_string(), "git".to_string(), "".to_string(), "Deploy".to_string()];
+    assert_eq!(normalize_tags(&tags, true), vec!["git", "deploy"]);
+}
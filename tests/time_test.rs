@@ -1,5 +1,5 @@
-use command_vault::utils::time::parse_datetime;
-use chrono::{DateTime, Utc};
+use command_vault::utils::time::{humanize_ago, humanize_ago_from, parse_datetime};
+use chrono::{DateTime, Duration, Utc};
 
 #[test]
 fn test_parse_datetime_valid() {
@@ -37,3 +37,21 @@ fn test_parse_datetime_different_formats() {
         assert!(result.is_some(), "Failed to parse: {}", input);
     }
 }
+
+#[test]
+fn test_humanize_ago() {
+    let now = parse_datetime("2024-06-01 12:00:00").unwrap();
+    let cases = [
+        (Duration::seconds(30), "just now"),
+        (Duration::minutes(3), "3m ago"),
+        (Duration::minutes(90), "1h ago"),
+        (Duration::days(2), "2d ago"),
+        (Duration::days(45), "1mo ago"),
+        (Duration::days(400), "1y ago"),
+        (-Duration::minutes(5), "just now"),
+    ];
+    for (age, expected) in cases {
+        assert_eq!(humanize_ago_from(now - age, now), expected);
+    }
+    assert_eq!(humanize_ago(Utc::now()), "just now");
+}
@@ -1,15 +1,17 @@
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use command_vault::{
-    db::{Command, Database},
-    ui::{app::App, AddCommandApp},
+    db::{models::Parameter, Command, Database},
+    ui::{app::{osc52_sequence, App, FilterMode, MESSAGE_TIMEOUT}, AddCommandApp},
 };
 use crate::test_utils::create_test_db;
 use command_vault::ui::add::InputMode;
+use command_vault::ui::display::{collapse_lines, count_summary, highlight_segments, history_summary, indent_continuation, parse_color, truncate_to_width, wrap_to_width};
 use ratatui::style::Color;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, is_raw_mode_enabled};
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::io::stdout;
+use std::time::Instant;
 
 mod test_utils;
 
@@ -22,6 +24,13 @@ fn create_test_commands() -> Vec<Command> {
             directory: "/home/user".to_string(),
             tags: vec!["file".to_string(), "list".to_string()],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         },
         Command {
             id: Some(2),
@@ -30,6 +39,13 @@ fn create_test_commands() -> Vec<Command> {
             directory: "/home/user/project".to_string(),
             tags: vec!["git".to_string()],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         },
         Command {
             id: Some(3),
@@ -38,6 +54,13 @@ fn create_test_commands() -> Vec<Command> {
             directory: "/home/user".to_string(),
             tags: vec!["docker".to_string()],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         },
     ]
 }
@@ -55,6 +78,13 @@ fn test_app_new() -> Result<()> {
             directory: "/test".to_string(),
             tags: vec!["test".to_string(), "example".to_string()],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         }
     ];
     
@@ -62,12 +92,14 @@ fn test_app_new() -> Result<()> {
     
     assert_eq!(app.commands, commands);
     assert_eq!(app.selected, None);
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
+    assert_eq!(app.state_path, None);
     assert_eq!(app.message, None);
     assert_eq!(app.filter_text, "");
     assert_eq!(app.filtered_commands, vec![0]);
     assert_eq!(app.confirm_delete, None);
-    assert_eq!(app.debug_mode, false);
+    assert!(!app.debug_mode);
+    assert!(!app.stay_after_exec);
     
     Ok(())
 }
@@ -83,6 +115,13 @@ fn test_app_filter() -> Result<()> {
             directory: "/".to_string(),
             tags: vec![],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         },
         Command {
             id: Some(2),
@@ -91,6 +130,13 @@ fn test_app_filter() -> Result<()> {
             directory: "/".to_string(),
             tags: vec![],
             parameters: vec![],
+            note: None,
+            alias: None,
+            shell: None,
+            interactive: None,
+            hostname: None,
+            created_at: None,
+            confirm_before_run: false,
         },
     ];
     let mut app = App::new(commands.clone(), &mut db, false);
@@ -101,6 +147,96 @@ fn test_app_filter() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_app_case_sensitive_filter() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let commands = create_test_commands();
+    let mut app = App::new(commands.clone(), &mut db, false);
+
+    // Ignores case by default
+    app.set_filter("GIT".to_string());
+    assert_eq!(app.filtered_commands.len(), 1);
+
+    app.handle_case_toggle()?;
+    assert!(app.case_sensitive);
+    assert!(app.filtered_commands.is_empty());
+
+    app.set_filter("git".to_string());
+    assert_eq!(app.filtered_commands.len(), 1);
+
+    app.handle_case_toggle()?;
+    app.set_filter("GIT".to_string());
+    assert_eq!(app.filtered_commands.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_app_tag_filter() -> Result<()> {
+    use crossterm::event::KeyCode;
+    let (mut db, _dir) = create_test_db()?;
+    let mut commands = create_test_commands();
+    // Mentions "git" in its text and directory, but isn't tagged with it
+    commands[0].command = "cd ~/git && ls".to_string();
+    let mut app = App::new(commands, &mut db, false);
+
+    app.set_filter("git".to_string());
+    assert_eq!(app.filtered_commands, vec![0, 1]);
+
+    // Only tags are matched in the tag filter
+    app.handle_tag_filter_start()?;
+    assert_eq!(app.filter_mode, FilterMode::Tag);
+    assert!(app.filter_text.is_empty());
+    app.set_filter("GIT".to_string());
+    assert_eq!(app.filtered_commands, vec![1]);
+
+    // Esc clears the filter and goes back to matching everything
+    app.handle_escape()?;
+    assert_eq!(app.filter_mode, FilterMode::Text);
+    assert_eq!(app.filtered_commands.len(), 3);
+
+    // `#` starts a tag filter only before anything is typed
+    app.handle_unbound_key(KeyCode::Char('#'))?;
+    assert_eq!(app.filter_mode, FilterMode::Tag);
+    app.handle_escape()?;
+    app.handle_unbound_key(KeyCode::Char('a'))?;
+    app.handle_unbound_key(KeyCode::Char('#'))?;
+    assert_eq!(app.filter_mode, FilterMode::Text);
+    assert_eq!(app.filter_text, "a#");
+    Ok(())
+}
+
+#[test]
+fn test_count_summary() -> Result<()> {
+    let commands = create_test_commands();
+    assert_eq!(count_summary(&commands, 120), "showing 3 of 120, IDs 1-3");
+    assert_eq!(count_summary(&commands[1..2], 120), "showing 1 of 120, ID 2");
+    assert_eq!(count_summary(&[], 0), "showing 0 of 0");
+
+    // The total counts the whole vault, not just the loaded commands
+    let (mut db, _dir) = create_test_db()?;
+    for cmd in &commands {
+        db.add_command(cmd)?;
+    }
+    let app = App::new(commands[..1].to_vec(), &mut db, false);
+    assert_eq!(app.total_commands, 3);
+    Ok(())
+}
+
+#[test]
+fn test_app_time_toggle() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    assert!(!app.absolute_time);
+
+    app.handle_time_toggle()?;
+    assert!(app.absolute_time);
+    assert_eq!(app.message.as_ref().unwrap().0, "Showing absolute times");
+
+    app.handle_time_toggle()?;
+    assert!(!app.absolute_time);
+    Ok(())
+}
+
 #[test]
 fn test_app_filtering() -> Result<()> {
     let (mut db, _dir) = create_test_db()?;
@@ -129,6 +265,13 @@ fn test_app_filtering() -> Result<()> {
     assert_eq!(app.filtered_commands.len(), 1);
     assert_eq!(app.commands[app.filtered_commands[0]].command, "git status");
 
+    // Test filtering by note
+    app.commands[0].note = Some("Shows hidden files too".to_string());
+    app.filter_text = "hidden".to_string();
+    app.update_filtered_commands();
+    assert_eq!(app.filtered_commands.len(), 1);
+    assert_eq!(app.commands[app.filtered_commands[0]].command, "ls -la");
+
     // Test no matches
     app.filter_text = "nonexistent".to_string();
     app.update_filtered_commands();
@@ -204,6 +347,23 @@ fn test_app_message() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_app_message_expires() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let mut app = App::new(create_test_commands(), &mut db, false);
+
+    app.set_success_message("Command copied to clipboard!".to_string());
+    app.clear_expired_message();
+    assert!(app.message.is_some());
+
+    // Once the timeout has passed, the next redraw clears it
+    app.message_time = Instant::now().checked_sub(MESSAGE_TIMEOUT);
+    app.clear_expired_message();
+    assert_eq!(app.message, None);
+    assert_eq!(app.message_time, None);
+    Ok(())
+}
+
 #[test]
 fn test_app_selection() -> Result<()> {
     let (mut db, _dir) = create_test_db()?;
@@ -252,12 +412,12 @@ fn test_app_debug_mode() -> Result<()> {
     let commands = create_test_commands();
     
     // Test debug mode enabled
-    let mut app = App::new(commands.clone(), &mut db, true);
-    assert_eq!(app.debug_mode, true);
+    let app = App::new(commands.clone(), &mut db, true);
+    assert!(app.debug_mode);
     
     // Test debug mode disabled
-    let mut app = App::new(commands.clone(), &mut db, false);
-    assert_eq!(app.debug_mode, false);
+    let app = App::new(commands.clone(), &mut db, false);
+    assert!(!app.debug_mode);
 
     Ok(())
 }
@@ -269,15 +429,35 @@ fn test_app_help_toggle() -> Result<()> {
     let mut app = App::new(commands.clone(), &mut db, false);
 
     // Test initial state
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
 
     // Test toggling help on
     app.show_help = true;
-    assert_eq!(app.show_help, true);
+    assert!(app.show_help);
 
     // Test toggling help off
     app.show_help = false;
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
+
+    Ok(())
+}
+
+#[test]
+fn test_app_escape_closes_help() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let commands = create_test_commands();
+    let mut app = App::new(commands.clone(), &mut db, false);
+
+    // Esc closes help without touching the filter behind it
+    app.set_filter("git".to_string());
+    app.show_help = true;
+    assert_eq!(app.handle_escape()?, None);
+    assert!(!app.show_help);
+    assert_eq!(app.filter_text, "git");
+
+    // With help closed, Esc clears the filter as before
+    app.handle_escape()?;
+    assert!(app.filter_text.is_empty());
 
     Ok(())
 }
@@ -396,6 +576,27 @@ fn test_add_command_app_multiline() {
     assert_eq!(app.command_cursor, 6);
 }
 
+#[test]
+fn test_add_command_app_paste() {
+    let mut app = AddCommandApp::new();
+    app.set_command("echo ".to_string());
+    app.command_cursor = 0;
+
+    // Pasted newlines stay in the command instead of moving on to tags
+    app.handle_paste("set -e\r\ncd /tmp\n");
+    assert_eq!(app.command, "set -e\ncd /tmp\necho ");
+    assert_eq!(app.command_cursor, 15);
+    assert_eq!(app.command_line, 2);
+    assert_eq!(app.input_mode, InputMode::Command);
+
+    // Tags are single-line
+    app.input_mode = InputMode::Tag;
+    app.handle_paste("deploy\n");
+    assert_eq!(app.current_tag, "deploy");
+    assert!(app.tags.is_empty());
+    assert_eq!(app.input_mode, InputMode::Tag);
+}
+
 #[test]
 fn test_add_command_app_key_events() {
     use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -655,6 +856,13 @@ fn test_app_edit_command() -> Result<()> {
         directory: original_command.directory.clone(),
         tags: vec!["test".to_string(), "updated".to_string()],
         parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
     };
 
     // Update in database
@@ -684,15 +892,15 @@ fn test_app_help_mode() -> Result<()> {
     let mut app = App::new(commands.clone(), &mut db, false);
 
     // Initial state
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
 
     // Toggle help mode on
     app.show_help = true;
-    assert_eq!(app.show_help, true);
+    assert!(app.show_help);
 
     // Toggle help mode off
     app.show_help = false;
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
 
     // Verify that help mode doesn't affect other app state
     assert_eq!(app.commands.len(), 3);
@@ -701,7 +909,7 @@ fn test_app_help_mode() -> Result<()> {
     assert_eq!(app.message, None);
     assert_eq!(app.filter_text, "");
     assert_eq!(app.confirm_delete, None);
-    assert_eq!(app.debug_mode, false);
+    assert!(!app.debug_mode);
 
     Ok(())
 }
@@ -818,6 +1026,179 @@ fn test_app_navigation_methods() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_app_wrap_navigation() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    assert!(!app.wrap_navigation);
+    app.wrap_navigation = true;
+    let last = app.filtered_commands.len() - 1;
+
+    // Down from the last item goes to the first
+    app.selected = Some(last);
+    app.select_next();
+    assert_eq!(app.selected, Some(0));
+
+    // Up from the first item goes to the last
+    app.select_previous();
+    assert_eq!(app.selected, Some(last));
+
+    // Moving within the list is unchanged
+    app.select_previous();
+    assert_eq!(app.selected, Some(last - 1));
+
+    // A single item wraps onto itself
+    app.set_filter("git".to_string());
+    app.selected = Some(0);
+    app.select_next();
+    assert_eq!(app.selected, Some(0));
+    app.select_previous();
+    assert_eq!(app.selected, Some(0));
+    Ok(())
+}
+
+#[test]
+fn test_app_no_matches_status() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    assert_eq!(app.no_matches_status(), None);
+
+    app.set_filter("ls".to_string());
+    assert!(!app.filtered_commands.is_empty());
+    assert_eq!(app.no_matches_status(), None);
+
+    app.set_filter("no-such-command".to_string());
+    assert!(app.filtered_commands.is_empty());
+    assert_eq!(app.no_matches_status(), Some("No matches — press Esc to clear"));
+
+    app.handle_escape()?;
+    assert_eq!(app.no_matches_status(), None);
+    Ok(())
+}
+
+#[test]
+fn test_app_column_toggle() -> Result<()> {
+    use command_vault::ui::state::ColumnSet;
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    assert_eq!(app.columns, ColumnSet { id: true, time: true, directory: false, tags: true });
+
+    app.handle_column_toggle('1')?;
+    assert!(!app.columns.id);
+    assert_eq!(app.message, Some(("Hiding IDs".to_string(), Color::Blue)));
+
+    app.handle_column_toggle('3')?;
+    assert!(app.columns.directory);
+    assert_eq!(app.message, Some(("Showing directories".to_string(), Color::Blue)));
+
+    // Other keys leave the columns alone
+    app.handle_column_toggle('9')?;
+    assert_eq!(app.columns, ColumnSet { id: false, time: true, directory: true, tags: true });
+    Ok(())
+}
+
+#[test]
+fn test_app_tag_edit() -> Result<()> {
+    use crossterm::event::KeyCode;
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+    let id = db.add_command(&create_test_commands()[0])?;
+    let command = db.get_command(id)?.unwrap();
+
+    let mut app = App::new(vec![command], &mut db, false);
+    app.set_selection(Some(0));
+    app.handle_tag_edit_start()?;
+    assert!(app.tag_edit.is_some());
+
+    // Typing and Enter adds a tag
+    for c in "Docker".chars() {
+        app.handle_tag_edit_key(KeyCode::Char(c))?;
+    }
+    app.handle_tag_edit_key(KeyCode::Enter)?;
+    assert!(app.commands[0].tags.contains(&"docker".to_string()));
+    assert_eq!(app.tag_edit.as_ref().unwrap().input, "");
+
+    // ←/→ pick an existing tag and Delete removes it
+    let first = app.commands[0].tags[0].clone();
+    app.handle_tag_edit_key(KeyCode::Right)?;
+    assert_eq!(app.tag_edit.as_ref().unwrap().selected, Some(0));
+    app.handle_tag_edit_key(KeyCode::Delete)?;
+    assert!(!app.commands[0].tags.contains(&first));
+    assert_eq!(app.tag_edit.as_ref().unwrap().selected, Some(0));
+
+    app.handle_tag_edit_key(KeyCode::Esc)?;
+    assert!(app.tag_edit.is_none());
+
+    // In the middle of a filter, `T` is typed into it
+    app.set_filter("echo".to_string());
+    app.handle_unbound_key(KeyCode::Char('T'))?;
+    assert!(app.tag_edit.is_none());
+    assert_eq!(app.filter_text, "echoT");
+    app.clear_filter();
+    let tags = app.commands[0].tags.clone();
+    drop(app);
+
+    // Both changes were saved
+    let mut stored = db.get_command(id)?.unwrap().tags;
+    stored.sort();
+    let mut expected = tags;
+    expected.sort();
+    assert_eq!(stored, expected);
+    assert_eq!(stored.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_app_goto_row() -> Result<()> {
+    use crossterm::event::KeyCode;
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    let rows = app.filtered_commands.len();
+    assert!(rows >= 2);
+
+    app.handle_goto_start()?;
+    app.handle_goto_key(KeyCode::Char('2'))?;
+    app.handle_goto_key(KeyCode::Char('x'))?;
+    assert_eq!(app.goto_input.as_deref(), Some("2"));
+    app.handle_goto_key(KeyCode::Enter)?;
+    assert_eq!(app.selected, Some(1));
+    assert!(app.goto_input.is_none());
+
+    // Out of range rows leave the selection alone
+    app.handle_goto_start()?;
+    for c in (rows + 1).to_string().chars() {
+        app.handle_goto_key(KeyCode::Char(c))?;
+    }
+    app.handle_goto_key(KeyCode::Enter)?;
+    assert_eq!(app.selected, Some(1));
+    assert!(app.message.is_some());
+
+    // Esc cancels
+    app.handle_goto_start()?;
+    app.handle_goto_key(KeyCode::Char('1'))?;
+    app.handle_goto_key(KeyCode::Esc)?;
+    assert!(app.goto_input.is_none());
+    assert_eq!(app.selected, Some(1));
+
+    // In the middle of a filter, `:` is typed into it (`localhost:8080`)
+    app.handle_unbound_key(KeyCode::Char(':'))?;
+    assert_eq!(app.goto_input.as_deref(), Some(""));
+    app.handle_goto_key(KeyCode::Esc)?;
+    app.set_filter("localhost".to_string());
+    app.handle_unbound_key(KeyCode::Char(':'))?;
+    assert!(app.goto_input.is_none());
+    assert_eq!(app.filter_text, "localhost:");
+    Ok(())
+}
+
 #[test]
 fn test_app_filter_methods() -> Result<()> {
     let mut db = Database::new(":memory:")?;
@@ -984,9 +1365,48 @@ fn test_app_selection_update_methods() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_app_selection_after_delete_keeps_position() -> Result<()> {
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+    let mut app = App::new(create_test_commands(), &mut db, false);
+
+    // Deleting a row above the selection keeps the same command highlighted
+    app.filtered_commands = vec![0, 1, 2, 3];
+    app.selected = Some(2);
+    app.filtered_commands.remove(0);
+    app.update_selection_after_delete(0);
+    assert_eq!(app.selected, Some(1));
+    assert_eq!(app.filtered_commands[1], 2);
+
+    // Deleting the selected row moves the highlight onto the next command
+    app.filtered_commands = vec![0, 1, 2, 3];
+    app.selected = Some(1);
+    app.filtered_commands.remove(1);
+    app.update_selection_after_delete(1);
+    assert_eq!(app.selected, Some(1));
+    assert_eq!(app.filtered_commands[1], 2);
+
+    // ...or the new last one if it was at the end
+    app.filtered_commands = vec![0, 1, 2, 3];
+    app.selected = Some(3);
+    app.filtered_commands.remove(3);
+    app.update_selection_after_delete(3);
+    assert_eq!(app.selected, Some(2));
+
+    // Deleting a row below the selection leaves it alone
+    app.filtered_commands = vec![0, 1, 2, 3];
+    app.selected = Some(1);
+    app.filtered_commands.remove(3);
+    app.update_selection_after_delete(3);
+    assert_eq!(app.selected, Some(1));
+
+    Ok(())
+}
+
 #[test]
 fn test_app_key_events() -> Result<()> {
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    
     let mut db = Database::new(":memory:")?;
     db.init()?;
     
@@ -994,11 +1414,11 @@ fn test_app_key_events() -> Result<()> {
     let mut app = App::new(commands.clone(), &mut db, false);
 
     // Test help toggle with '?'
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
     app.show_help = !app.show_help; // Simulate '?' key press
-    assert_eq!(app.show_help, true);
+    assert!(app.show_help);
     app.show_help = !app.show_help; // Simulate '?' key press again
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
 
     // Test filter operations
     assert_eq!(app.filter_text, "");
@@ -1067,7 +1487,7 @@ fn test_app_clipboard_operations() -> Result<()> {
     assert_eq!(command_to_copy, "ls -la");
 
     // Try to copy command to clipboard, but don't fail the test if clipboard is unavailable
-    if let Err(_) = copy_to_clipboard(&command_to_copy) {
+    if copy_to_clipboard(&command_to_copy).is_err() {
         eprintln!("Warning: Clipboard operations not available in this environment");
         return Ok(());
     }
@@ -1080,6 +1500,39 @@ fn test_app_clipboard_operations() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_app_copy_id() -> Result<()> {
+    use crossterm::event::KeyCode;
+    let mut db = Database::new(":memory:")?;
+    db.init()?;
+
+    let mut app = App::new(create_test_commands(), &mut db, false);
+    app.selected = Some(0);
+
+    // In the middle of a filter, `i` is typed into it (`git`, `pip`)
+    app.set_filter("g".to_string());
+    app.handle_unbound_key(KeyCode::Char('i'))?;
+    assert_eq!(app.filter_text, "gi");
+    assert_eq!(app.message, None);
+    app.clear_filter();
+    app.selected = Some(0);
+
+    // Skip the clipboard in CI environment
+    if std::env::var("CI").is_ok() {
+        return Ok(());
+    }
+    let id = app.get_selected_command().unwrap().id.unwrap();
+
+    // Don't fail the test if clipboard is unavailable
+    if app.handle_copy_id().is_err() {
+        eprintln!("Warning: Clipboard operations not available in this environment");
+        return Ok(());
+    }
+    assert_eq!(app.message, Some((format!("Copied ID {}", id), Color::Green)));
+
+    Ok(())
+}
+
 #[test]
 fn test_app_terminal_setup() -> Result<()> {
     // Skip this test in CI environment
@@ -1091,16 +1544,16 @@ fn test_app_terminal_setup() -> Result<()> {
     db.init()?;
     
     let commands = create_test_commands();
-    let mut app = App::new(commands.clone(), &mut db, false);
+    let _app = App::new(commands.clone(), &mut db, false);
 
     // Test terminal setup
     match enable_raw_mode() {
         Ok(_) => {
             let stdout = stdout();
             let backend = CrosstermBackend::new(stdout);
-            if let Ok(terminal) = Terminal::new(backend) {
+            if let Ok(_terminal) = Terminal::new(backend) {
                 // Test terminal restoration
-                if let Ok(_) = disable_raw_mode() {
+                if disable_raw_mode().is_ok() {
                     assert!(!is_raw_mode_enabled().unwrap_or(true));
                 }
             }
@@ -1122,7 +1575,7 @@ fn test_app_ui_state() -> Result<()> {
     let mut app = App::new(commands.clone(), &mut db, false);
 
     // Test initial state
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
     assert_eq!(app.message, None);
     assert_eq!(app.filter_text, "");
     assert_eq!(app.filtered_commands.len(), 3);
@@ -1131,7 +1584,7 @@ fn test_app_ui_state() -> Result<()> {
 
     // Test help state
     app.show_help = true;
-    assert_eq!(app.show_help, true);
+    assert!(app.show_help);
 
     // Test filter state
     app.show_help = false;
@@ -1184,11 +1637,11 @@ fn test_app_handle_quit() -> Result<()> {
 
     // Test quit with help screen
     app.show_help = true;
-    assert_eq!(app.show_help, true);
+    assert!(app.show_help);
     
     let result = app.handle_quit()?;
     assert_eq!(result, None);
-    assert_eq!(app.show_help, false);
+    assert!(!app.show_help);
 
     // Test normal quit
     let result = app.handle_quit()?;
@@ -1234,3 +1687,314 @@ fn test_app_handle_escape() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_add_command_app_directory_and_parameters() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    let mut app = AddCommandApp::new();
+    app.set_directory("/tmp".to_string());
+
+    // Parameters are detected when leaving command input
+    app.set_command("echo @name".to_string());
+    assert!(app.parameters.is_empty());
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+    assert_eq!(app.input_mode, InputMode::Tag);
+    assert_eq!(app.parameters.len(), 1);
+    assert_eq!(app.parameters[0].name, "name");
+
+    // An empty tag moves on to directory input
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+    assert_eq!(app.input_mode, InputMode::Directory);
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::empty()));
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty()));
+    assert_eq!(app.directory, "/tmq");
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+    assert_eq!(app.input_mode, InputMode::Confirm);
+}
+
+#[test]
+fn test_add_command_app_multibyte_input() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    let mut app = AddCommandApp::new();
+
+    for c in "café 日本".chars() {
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty()));
+    }
+    assert_eq!(app.command, "café 日本");
+    assert_eq!(app.command_cursor, 7);
+
+    // Backspace over multibyte characters
+    app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::empty()));
+    app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::empty()));
+    assert_eq!(app.command, "café ");
+
+    // Insert in the middle, after the 'é'
+    app.handle_key_event(KeyEvent::new(KeyCode::Left, KeyModifiers::empty()));
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('ü'), KeyModifiers::empty()));
+    assert_eq!(app.command, "caféü ");
+    app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::empty()));
+    app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::empty()));
+    assert_eq!(app.command, "caf ");
+    assert_eq!(app.command_cursor, 3);
+
+    // set_command puts the cursor after the last character
+    app.set_command("日本".to_string());
+    assert_eq!(app.command_cursor, 2);
+    app.handle_key_event(KeyEvent::new(KeyCode::Right, KeyModifiers::empty()));
+    assert_eq!(app.command_cursor, 2);
+}
+
+#[test]
+fn test_app_parameter_summary() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let mut commands = create_test_commands();
+    commands.push(Command {
+        id: Some(4),
+        command: "git commit -m @message:Commit @branch".to_string(),
+        timestamp: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 3).unwrap(),
+        directory: "/home/user".to_string(),
+        tags: vec![],
+        parameters: vec![],
+        note: None,
+        alias: None,
+        shell: None,
+        interactive: None,
+        hostname: None,
+        created_at: None,
+        confirm_before_run: false,
+    });
+    let mut app = App::new(commands, &mut db, false);
+
+    // Nothing selected
+    assert_eq!(app.parameter_summary(), None);
+
+    // Command without parameters
+    app.selected = Some(0);
+    assert_eq!(app.parameter_summary(), None);
+
+    // Command with parameters
+    app.selected = Some(3);
+    assert_eq!(
+        app.parameter_summary(),
+        Some("Parameters: message (Commit), branch".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_display_helpers() {
+    // Multi-line commands collapse onto one line with a marker
+    assert_eq!(collapse_lines("ls -la"), "ls -la");
+    assert_eq!(collapse_lines("cd /tmp && \\\n  ls\n"), "cd /tmp && \\ ⏎ ls");
+
+    // Truncation counts characters, not bytes
+    assert_eq!(truncate_to_width("git status", 20), "git status");
+    assert_eq!(truncate_to_width("git status", 10), "git status");
+    assert_eq!(truncate_to_width("git status", 6), "git s…");
+    assert_eq!(truncate_to_width("échoéé", 4), "éch…");
+    assert_eq!(truncate_to_width("git", 0), "");
+
+    // Wrapping keeps the command's own line breaks
+    assert_eq!(wrap_to_width("abcdef", 4), vec!["abcd", "ef"]);
+    assert_eq!(wrap_to_width("ab\n\ncd", 4), vec!["ab", "", "cd"]);
+    assert_eq!(wrap_to_width("abc", 0), vec!["a", "b", "c"]);
+
+    // Later lines of a multi-line command are indented to line up under the first
+    assert_eq!(indent_continuation("docker run \\\n  -it ubuntu", 4), vec!["docker run \\", "      -it ubuntu"]);
+    assert_eq!(indent_continuation("ls -la", 4), vec!["ls -la"]);
+    assert_eq!(indent_continuation("", 4), vec![""]);
+}
+
+#[test]
+fn test_highlight_segments() {
+    let seg = |text: &str, matched| (text.to_string(), matched);
+
+    // No filter, no highlighting
+    assert_eq!(highlight_segments("git status", "", false), vec![seg("git status", false)]);
+    assert_eq!(highlight_segments("git status", "docker", false), vec![seg("git status", false)]);
+
+    // Matches are case-insensitive and keep the original casing
+    assert_eq!(
+        highlight_segments("Git log | git show", "GIT", false),
+        vec![seg("Git", true), seg(" log | ", false), seg("git", true), seg(" show", false)]
+    );
+
+    // Multibyte text is split on character boundaries
+    assert_eq!(
+        highlight_segments("échoé é", "é", false),
+        vec![seg("é", true), seg("cho", false), seg("é", true), seg(" ", false), seg("é", true)]
+    );
+
+    // Case-sensitive matching skips other casings
+    assert_eq!(
+        highlight_segments("Git log | git show", "git", true),
+        vec![seg("Git log | ", false), seg("git", true), seg(" show", false)]
+    );
+}
+
+#[test]
+fn test_tag_colors() -> Result<()> {
+    assert_eq!(parse_color("red"), Some(Color::Red));
+    assert_eq!(parse_color(" Light-Blue "), Some(Color::LightBlue));
+    assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+    assert_eq!(parse_color("#ff8800"), Some(Color::Rgb(0xff, 0x88, 0x00)));
+    assert_eq!(parse_color("not-a-color"), None);
+
+    let (mut db, _dir) = create_test_db()?;
+    db.set_tag_color("git", "magenta")?;
+    let app = App::new(create_test_commands(), &mut db, false);
+    assert_eq!(app.tag_color("git"), Color::Magenta);
+    // Tags without a color keep the default
+    assert_eq!(app.tag_color("docker"), Color::Green);
+    Ok(())
+}
+
+#[test]
+fn test_apply_edit() -> Result<()> {
+    let (mut db, _dir) = create_test_db()?;
+    let mut command = create_test_commands().remove(1);
+    command.id = None;
+    let id = db.add_command(&command)?;
+    command.id = Some(id);
+    let mut app = App::new(vec![command.clone()], &mut db, false);
+
+    // An empty edit is rejected and nothing changes
+    app.apply_edit(0, "   ".to_string(), vec![], String::new());
+    assert_eq!(app.message, Some(("Cannot save an empty command".to_string(), Color::Red)));
+    assert_eq!(app.commands[0], command);
+    assert_eq!(app.db.get_command(id)?.unwrap().command, "git status");
+
+    // A cleared directory keeps the old one
+    app.apply_edit(0, "git status -s".to_string(), vec!["git".to_string()], String::new());
+    assert_eq!(app.commands[0].command, "git status -s");
+    assert_eq!(app.commands[0].directory, command.directory);
+    assert_eq!(app.db.get_command(id)?.unwrap().command, "git status -s");
+
+    // Parameters still in the text keep their default
+    let mut with_param = app.commands[0].clone();
+    with_param.command = "git status @path".to_string();
+    with_param.parameters = vec![Parameter { default_value: Some(".".to_string()), ..Parameter::new("path".to_string()) }];
+    app.db.update_command(&with_param)?;
+    app.commands[0] = with_param;
+    app.apply_edit(0, "git status -s @path".to_string(), vec![], String::new());
+    assert_eq!(app.db.get_command(id)?.unwrap().parameters[0].default_value.as_deref(), Some("."));
+    Ok(())
+}
+
+#[test]
+fn test_osc52_sequence() {
+    assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+    assert_eq!(osc52_sequence("ls -la"), "\x1b]52;c;bHMgLWxh\x07");
+    assert_eq!(osc52_sequence("git push"), "\x1b]52;c;Z2l0IHB1c2g=\x07");
+    assert_eq!(osc52_sequence("cd ~"), "\x1b]52;c;Y2Qgfg==\x07");
+    assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+}
+
+#[test]
+fn test_add_command_app_find() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let mut app = AddCommandApp::new();
+    app.set_command("cat log | grep err\n| grep -v warn".to_string());
+    app.command_cursor = 0;
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+    assert_eq!(app.find_query.as_deref(), Some(""));
+    for c in "grep".chars() {
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+    // Typing goes to the query, not the command
+    assert_eq!(app.command, "cat log | grep err\n| grep -v warn");
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(app.command_cursor, 10);
+    assert_eq!(app.command_line, 0);
+
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(app.command_cursor, 21);
+    assert_eq!(app.command_line, 1);
+
+    // Wraps around to the first match
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+    assert_eq!(app.command_cursor, 10);
+
+    app.find_query = Some("missing".to_string());
+    assert!(!app.find_next());
+    assert_eq!(app.command_cursor, 10);
+
+    // Esc closes find and keeps the cursor; typing edits the command again
+    app.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert_eq!(app.find_query, None);
+    assert_eq!(app.input_mode, InputMode::Command);
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+    assert_eq!(app.command, "cat log | xgrep err\n| grep -v warn");
+}
+
+#[test]
+fn test_add_command_app_tag_completion() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    let mut app = AddCommandApp::new();
+    app.input_mode = InputMode::Tag;
+    app.suggested_tags = vec!["git".to_string()];
+    app.set_known_tags(vec!["kubernetes".to_string(), "kafka".to_string(), "docker".to_string()]);
+
+    for c in "kb".chars() {
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+    assert_eq!(app.tag_suggestions(), vec!["kubernetes"]);
+
+    // Tab highlights a suggestion and Enter adds it instead of what was typed
+    app.handle_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+    assert_eq!(app.tag_selection, Some(0));
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(app.tags, vec!["kubernetes"]);
+    assert!(app.current_tag.is_empty());
+    assert_eq!(app.tag_selection, None);
+
+    // Tags already added aren't suggested again
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE));
+    assert_eq!(app.tag_suggestions(), vec!["kafka", "docker"]);
+
+    // Without a match, the suggestions based on the command are offered
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE));
+    assert_eq!(app.tag_suggestions(), vec!["git"]);
+
+    // Without a highlighted suggestion, Enter adds the typed tag
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert_eq!(app.tags, vec!["kubernetes", "kz"]);
+    app.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    assert!(matches!(app.input_mode, InputMode::Directory));
+}
+
+#[test]
+fn test_picker_label() {
+    use command_vault::ui::picker::picker_label;
+
+    let mut commands = create_test_commands();
+    assert_eq!(picker_label(&commands[0]), "1: ls -la  [file, list]");
+
+    commands[1].command = "cd project\nmake".to_string();
+    commands[1].tags.clear();
+    assert_eq!(picker_label(&commands[1]), "2: cd project ⏎ make");
+}
+
+#[test]
+fn test_history_summary() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 10, 0, 0, 0).unwrap();
+    let mut cmd = create_test_commands().remove(0);
+    cmd.timestamp = Utc.with_ymd_and_hms(2024, 1, 9, 22, 0, 0).unwrap();
+
+    // Never edited
+    cmd.created_at = Some(cmd.timestamp);
+    assert_eq!(history_summary(&cmd, now), None);
+    cmd.hostname = Some("build-box".to_string());
+    assert_eq!(history_summary(&cmd, now).as_deref(), Some("added on build-box"));
+
+    cmd.created_at = Some(Utc.with_ymd_and_hms(2024, 1, 7, 0, 0, 0).unwrap());
+    assert_eq!(history_summary(&cmd, now).as_deref(), Some("added 3d ago on build-box, updated 2h ago"));
+    cmd.hostname = None;
+    assert_eq!(history_summary(&cmd, now).as_deref(), Some("added 3d ago, updated 2h ago"));
+}